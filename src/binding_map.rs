@@ -12,7 +12,7 @@ pub(crate) enum BindingMapField {
 }
 
 #[derive(Debug)]
-pub(crate) struct BindingMapKeys {
+pub struct BindingMapKeys {
     keys: Vec<(String, usize)>,
 }
 
@@ -0,0 +1,1046 @@
+use crate::utils::dash_to_camel;
+
+/// An attribute of a builtin component.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TmplBuiltinAttr {
+    /// The attribute name in dash case, e.g. `scroll-y`.
+    pub name: &'static str,
+    /// The accepted static values, or empty for any values.
+    pub values: &'static [&'static str],
+    pub deprecated: bool,
+}
+
+/// A builtin component with its attributes and events.
+///
+/// The common attributes and events of all components are not listed, see `COMMON_ATTRS` and `COMMON_EVENTS`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TmplBuiltinComponent {
+    pub name: &'static str,
+    pub attrs: &'static [TmplBuiltinAttr],
+    /// The event names, e.g. `change` for `bind:change`.
+    pub events: &'static [&'static str],
+    pub deprecated: bool,
+}
+
+impl TmplBuiltinComponent {
+    /// Find an attribute by its name in dash case or camel case.
+    pub fn attr(&self, name: &str) -> Option<&'static TmplBuiltinAttr> {
+        let name = dash_to_camel(name);
+        self.attrs.iter().find(|x| dash_to_camel(x.name) == name)
+    }
+
+    /// Whether the event can be bound on the component, including the common events.
+    pub fn has_event(&self, name: &str) -> bool {
+        let name = name.to_ascii_lowercase();
+        self.events.contains(&name.as_str()) || COMMON_EVENTS.contains(&name.as_str())
+    }
+}
+
+/// The attributes accepted by all builtin components, besides `id`, `class`, `style`, `slot` and `data-*`.
+pub const COMMON_ATTRS: &[&str] = &["hidden", "animation"];
+
+/// The events which can be bound on all builtin components.
+pub const COMMON_EVENTS: &[&str] = &[
+    "touchstart",
+    "touchmove",
+    "touchcancel",
+    "touchend",
+    "tap",
+    "longpress",
+    "longtap",
+    "transitionend",
+    "animationstart",
+    "animationiteration",
+    "animationend",
+    "touchforcechange",
+];
+
+const fn attr(name: &'static str) -> TmplBuiltinAttr {
+    TmplBuiltinAttr {
+        name,
+        values: &[],
+        deprecated: false,
+    }
+}
+
+const fn enum_attr(name: &'static str, values: &'static [&'static str]) -> TmplBuiltinAttr {
+    TmplBuiltinAttr {
+        name,
+        values,
+        deprecated: false,
+    }
+}
+
+const fn deprecated_attr(name: &'static str) -> TmplBuiltinAttr {
+    TmplBuiltinAttr {
+        name,
+        values: &[],
+        deprecated: true,
+    }
+}
+
+const fn component(
+    name: &'static str,
+    attrs: &'static [TmplBuiltinAttr],
+    events: &'static [&'static str],
+) -> TmplBuiltinComponent {
+    TmplBuiltinComponent {
+        name,
+        attrs,
+        events,
+        deprecated: false,
+    }
+}
+
+const fn deprecated_component(
+    name: &'static str,
+    attrs: &'static [TmplBuiltinAttr],
+    events: &'static [&'static str],
+) -> TmplBuiltinComponent {
+    TmplBuiltinComponent {
+        name,
+        attrs,
+        events,
+        deprecated: true,
+    }
+}
+
+const HOVER_ATTRS: [TmplBuiltinAttr; 4] = [
+    attr("hover-class"),
+    attr("hover-stop-propagation"),
+    attr("hover-start-time"),
+    attr("hover-stay-time"),
+];
+const LANG: &[&str] = &["en", "zh_CN", "zh_TW"];
+const CONFIRM_TYPE: &[&str] = &["send", "search", "next", "go", "done"];
+const DEVICE_POSITION: &[&str] = &["front", "back"];
+const REFERRER_POLICY: &[&str] = &["origin", "no-referrer"];
+const MINI_PROGRAM_VERSION: &[&str] = &["develop", "trial", "release"];
+const TEXT_SPACE: &[&str] = &["ensp", "emsp", "nbsp"];
+
+/// The builtin components, sorted by names.
+const BUILTIN_COMPONENTS: &[TmplBuiltinComponent] = &[
+    component(
+        "ad",
+        &[
+            attr("unit-id"),
+            attr("ad-intervals"),
+            enum_attr("ad-type", &["banner", "video", "grid"]),
+            enum_attr("ad-theme", &["white", "black"]),
+        ],
+        &["load", "error", "close"],
+    ),
+    component(
+        "ad-custom",
+        &[attr("unit-id"), attr("ad-intervals")],
+        &["load", "error"],
+    ),
+    deprecated_component(
+        "audio",
+        &[
+            attr("src"),
+            attr("loop"),
+            attr("controls"),
+            attr("poster"),
+            attr("name"),
+            attr("author"),
+        ],
+        &["error", "play", "pause", "timeupdate", "ended"],
+    ),
+    component(
+        "button",
+        &[
+            enum_attr("size", &["default", "mini"]),
+            enum_attr("type", &["primary", "default", "warn"]),
+            attr("plain"),
+            attr("disabled"),
+            attr("loading"),
+            enum_attr("form-type", &["submit", "reset"]),
+            enum_attr(
+                "open-type",
+                &[
+                    "contact",
+                    "liveActivity",
+                    "share",
+                    "getPhoneNumber",
+                    "getRealtimePhoneNumber",
+                    "getUserInfo",
+                    "launchApp",
+                    "openSetting",
+                    "feedback",
+                    "chooseAvatar",
+                    "agreePrivacyAuthorization",
+                ],
+            ),
+            HOVER_ATTRS[0],
+            HOVER_ATTRS[1],
+            HOVER_ATTRS[2],
+            HOVER_ATTRS[3],
+            enum_attr("lang", LANG),
+            attr("session-from"),
+            attr("send-message-title"),
+            attr("send-message-path"),
+            attr("send-message-img"),
+            attr("app-parameter"),
+            attr("show-message-card"),
+            attr("phone-number-no-quota-toast"),
+        ],
+        &[
+            "getuserinfo",
+            "contact",
+            "getphonenumber",
+            "getrealtimephonenumber",
+            "error",
+            "opensetting",
+            "launchapp",
+            "chooseavatar",
+            "agreeprivacyauthorization",
+        ],
+    ),
+    component(
+        "camera",
+        &[
+            enum_attr("mode", &["normal", "scanCode"]),
+            enum_attr("resolution", &["low", "medium", "high"]),
+            enum_attr("device-position", DEVICE_POSITION),
+            enum_attr("flash", &["auto", "on", "off", "torch"]),
+            enum_attr("frame-size", &["small", "medium", "large"]),
+        ],
+        &["stop", "error", "initdone", "scancode"],
+    ),
+    component(
+        "canvas",
+        &[
+            enum_attr("type", &["2d", "webgl"]),
+            attr("canvas-id"),
+            attr("disable-scroll"),
+        ],
+        &["error"],
+    ),
+    component(
+        "channel-live",
+        &[attr("feed-id"), attr("finder-user-name")],
+        &[],
+    ),
+    component(
+        "channel-video",
+        &[
+            attr("feed-id"),
+            attr("finder-user-name"),
+            attr("feed-token"),
+            attr("autoplay"),
+            attr("loop"),
+            attr("muted"),
+            enum_attr("object-fit", &["fill", "contain", "cover"]),
+        ],
+        &["error"],
+    ),
+    component(
+        "checkbox",
+        &[
+            attr("value"),
+            attr("disabled"),
+            attr("checked"),
+            attr("color"),
+        ],
+        &[],
+    ),
+    component("checkbox-group", &[], &["change"]),
+    component(
+        "cover-image",
+        &[attr("src"), enum_attr("referrer-policy", REFERRER_POLICY)],
+        &["load", "error"],
+    ),
+    component("cover-view", &[attr("scroll-top")], &[]),
+    component(
+        "editor",
+        &[
+            attr("read-only"),
+            attr("placeholder"),
+            attr("show-img-size"),
+            attr("show-img-toolbar"),
+            attr("show-img-resize"),
+        ],
+        &["ready", "focus", "blur", "input", "statuschange"],
+    ),
+    component(
+        "form",
+        &[attr("report-submit"), attr("report-submit-timeout")],
+        &["submit", "reset"],
+    ),
+    deprecated_component(
+        "functional-page-navigator",
+        &[
+            enum_attr("version", MINI_PROGRAM_VERSION),
+            enum_attr(
+                "name",
+                &[
+                    "loginAndGetUserInfo",
+                    "requestPayment",
+                    "chooseAddress",
+                    "chooseInvoice",
+                    "chooseInvoiceTitle",
+                ],
+            ),
+            attr("args"),
+        ],
+        &["success", "fail", "cancel"],
+    ),
+    component(
+        "grid-view",
+        &[
+            enum_attr("type", &["aligned", "masonry"]),
+            attr("cross-axis-count"),
+            attr("max-cross-axis-extent"),
+            attr("main-axis-gap"),
+            attr("cross-axis-gap"),
+            attr("padding"),
+        ],
+        &[],
+    ),
+    component(
+        "icon",
+        &[
+            enum_attr(
+                "type",
+                &[
+                    "success",
+                    "success_no_circle",
+                    "info",
+                    "warn",
+                    "waiting",
+                    "cancel",
+                    "download",
+                    "search",
+                    "clear",
+                ],
+            ),
+            attr("size"),
+            attr("color"),
+        ],
+        &[],
+    ),
+    component(
+        "image",
+        &[
+            attr("src"),
+            enum_attr(
+                "mode",
+                &[
+                    "scaleToFill",
+                    "aspectFit",
+                    "aspectFill",
+                    "widthFix",
+                    "heightFix",
+                    "top",
+                    "bottom",
+                    "center",
+                    "left",
+                    "right",
+                    "top left",
+                    "top right",
+                    "bottom left",
+                    "bottom right",
+                ],
+            ),
+            attr("webp"),
+            attr("lazy-load"),
+            attr("show-menu-by-longpress"),
+            attr("fade-in"),
+        ],
+        &["error", "load"],
+    ),
+    component(
+        "input",
+        &[
+            attr("value"),
+            enum_attr(
+                "type",
+                &[
+                    "text",
+                    "number",
+                    "idcard",
+                    "digit",
+                    "safe-password",
+                    "nickname",
+                ],
+            ),
+            attr("password"),
+            attr("placeholder"),
+            attr("placeholder-style"),
+            attr("placeholder-class"),
+            attr("disabled"),
+            attr("maxlength"),
+            attr("cursor-spacing"),
+            deprecated_attr("auto-focus"),
+            attr("focus"),
+            enum_attr("confirm-type", CONFIRM_TYPE),
+            attr("always-embed"),
+            attr("confirm-hold"),
+            attr("cursor"),
+            attr("cursor-color"),
+            attr("selection-start"),
+            attr("selection-end"),
+            attr("adjust-position"),
+            attr("hold-keyboard"),
+            attr("safe-password-cert-path"),
+            attr("safe-password-length"),
+            attr("safe-password-time-stamp"),
+            attr("safe-password-nonce"),
+            attr("safe-password-salt"),
+            attr("safe-password-custom-hash"),
+        ],
+        &[
+            "input",
+            "focus",
+            "blur",
+            "confirm",
+            "keyboardheightchange",
+            "nicknamereview",
+        ],
+    ),
+    component("keyboard-accessory", &[], &[]),
+    component("label", &[attr("for")], &[]),
+    component("list-view", &[attr("padding")], &[]),
+    component(
+        "live-player",
+        &[
+            attr("src"),
+            enum_attr("mode", &["live", "RTC"]),
+            attr("autoplay"),
+            attr("muted"),
+            enum_attr("orientation", &["vertical", "horizontal"]),
+            enum_attr("object-fit", &["contain", "fillCrop"]),
+            deprecated_attr("background-mute"),
+            attr("min-cache"),
+            attr("max-cache"),
+            enum_attr("sound-mode", &["speaker", "ear"]),
+            attr("auto-pause-if-navigate"),
+            attr("auto-pause-if-open-native"),
+            attr("picture-in-picture-mode"),
+        ],
+        &[
+            "statechange",
+            "fullscreenchange",
+            "netstatus",
+            "audiovolumenotify",
+            "enterpictureinpicture",
+            "leavepictureinpicture",
+        ],
+    ),
+    component(
+        "live-pusher",
+        &[
+            attr("url"),
+            enum_attr("mode", &["SD", "HD", "FHD", "RTC"]),
+            attr("autopush"),
+            attr("muted"),
+            attr("enable-camera"),
+            attr("auto-focus"),
+            enum_attr("orientation", &["vertical", "horizontal"]),
+            attr("beauty"),
+            attr("whiteness"),
+            enum_attr("aspect", &["3:4", "9:16"]),
+            attr("min-bitrate"),
+            attr("max-bitrate"),
+            enum_attr("audio-quality", &["high", "low"]),
+            attr("waiting-image"),
+            attr("waiting-image-hash"),
+            attr("zoom"),
+            enum_attr("device-position", DEVICE_POSITION),
+            attr("background-mute"),
+            deprecated_attr("mirror"),
+            attr("remote-mirror"),
+            enum_attr("local-mirror", &["auto", "enable", "disable"]),
+            attr("audio-reverb-type"),
+            attr("enable-mic"),
+            attr("enable-agc"),
+            attr("enable-ans"),
+            enum_attr("audio-volume-type", &["auto", "media", "voicecall"]),
+            attr("video-width"),
+            attr("video-height"),
+            enum_attr("beauty-style", &["smooth", "nature"]),
+            attr("filter"),
+        ],
+        &[
+            "statechange",
+            "netstatus",
+            "error",
+            "bgmstart",
+            "bgmprogress",
+            "bgmcomplete",
+            "audiovolumenotify",
+        ],
+    ),
+    component(
+        "map",
+        &[
+            attr("longitude"),
+            attr("latitude"),
+            attr("scale"),
+            attr("min-scale"),
+            attr("max-scale"),
+            attr("markers"),
+            deprecated_attr("covers"),
+            attr("polyline"),
+            attr("circles"),
+            deprecated_attr("controls"),
+            attr("include-points"),
+            attr("show-location"),
+            attr("polygons"),
+            attr("subkey"),
+            attr("layer-style"),
+            attr("rotate"),
+            attr("skew"),
+            attr("enable-3D"),
+            attr("show-compass"),
+            attr("show-scale"),
+            attr("enable-overlooking"),
+            attr("enable-zoom"),
+            attr("enable-scroll"),
+            attr("enable-rotate"),
+            attr("enable-satellite"),
+            attr("enable-traffic"),
+            attr("enable-poi"),
+            attr("enable-building"),
+            attr("setting"),
+        ],
+        &[
+            "markertap",
+            "labeltap",
+            "controltap",
+            "callouttap",
+            "updated",
+            "regionchange",
+            "poitap",
+            "anchorpointtap",
+        ],
+    ),
+    component(
+        "match-media",
+        &[
+            attr("min-width"),
+            attr("max-width"),
+            attr("width"),
+            attr("min-height"),
+            attr("max-height"),
+            attr("height"),
+            enum_attr("orientation", &["landscape", "portrait"]),
+        ],
+        &[],
+    ),
+    component("movable-area", &[attr("scale-area")], &[]),
+    component(
+        "movable-view",
+        &[
+            enum_attr("direction", &["all", "vertical", "horizontal", "none"]),
+            attr("inertia"),
+            attr("out-of-bounds"),
+            attr("x"),
+            attr("y"),
+            attr("damping"),
+            attr("friction"),
+            attr("disabled"),
+            attr("scale"),
+            attr("scale-min"),
+            attr("scale-max"),
+            attr("scale-value"),
+        ],
+        &["change", "scale", "htouchmove", "vtouchmove"],
+    ),
+    component(
+        "navigation-bar",
+        &[
+            attr("title"),
+            attr("loading"),
+            enum_attr("front-color", &["#ffffff", "#000000"]),
+            attr("background-color"),
+            attr("color-animation-duration"),
+            enum_attr(
+                "color-animation-timing-func",
+                &["linear", "easeIn", "easeOut", "easeInOut"],
+            ),
+        ],
+        &[],
+    ),
+    component(
+        "navigator",
+        &[
+            enum_attr("target", &["self", "miniProgram"]),
+            attr("url"),
+            enum_attr(
+                "open-type",
+                &[
+                    "navigate",
+                    "redirect",
+                    "switchTab",
+                    "reLaunch",
+                    "navigateBack",
+                    "exit",
+                ],
+            ),
+            attr("delta"),
+            attr("app-id"),
+            attr("path"),
+            attr("extra-data"),
+            enum_attr("version", MINI_PROGRAM_VERSION),
+            attr("short-link"),
+            HOVER_ATTRS[0],
+            HOVER_ATTRS[1],
+            HOVER_ATTRS[2],
+            HOVER_ATTRS[3],
+        ],
+        &["success", "fail", "complete"],
+    ),
+    component("official-account", &[], &["load", "error"]),
+    component(
+        "open-data",
+        &[
+            enum_attr(
+                "type",
+                &[
+                    "groupName",
+                    "userNickName",
+                    "userAvatarUrl",
+                    "userGender",
+                    "userCity",
+                    "userProvince",
+                    "userCountry",
+                    "userLanguage",
+                ],
+            ),
+            attr("open-gid"),
+            enum_attr("lang", LANG),
+            attr("default-text"),
+            attr("default-avatar"),
+        ],
+        &["error"],
+    ),
+    component(
+        "page-container",
+        &[
+            attr("show"),
+            attr("duration"),
+            attr("z-index"),
+            attr("overlay"),
+            enum_attr("position", &["top", "bottom", "right", "center"]),
+            attr("round"),
+            attr("close-on-slide-down"),
+            attr("overlay-style"),
+            attr("custom-style"),
+        ],
+        &[
+            "beforeenter",
+            "enter",
+            "afterenter",
+            "beforeleave",
+            "leave",
+            "afterleave",
+            "clickoverlay",
+        ],
+    ),
+    component(
+        "page-meta",
+        &[
+            attr("background-text-style"),
+            attr("background-color"),
+            attr("background-color-top"),
+            attr("background-color-bottom"),
+            attr("root-background-color"),
+            attr("scroll-top"),
+            attr("scroll-duration"),
+            attr("page-style"),
+            attr("root-font-size"),
+            attr("page-font-size"),
+            attr("page-orientation"),
+        ],
+        &["resize", "scroll", "scrolldone"],
+    ),
+    component(
+        "picker",
+        &[
+            enum_attr(
+                "mode",
+                &["selector", "multiSelector", "time", "date", "region"],
+            ),
+            attr("disabled"),
+            attr("range"),
+            attr("range-key"),
+            attr("value"),
+            attr("start"),
+            attr("end"),
+            enum_attr("fields", &["year", "month", "day"]),
+            attr("custom-item"),
+            enum_attr("level", &["province", "city", "region", "sub-district"]),
+            attr("header-text"),
+        ],
+        &["change", "cancel", "columnchange"],
+    ),
+    component(
+        "picker-view",
+        &[
+            attr("value"),
+            attr("indicator-style"),
+            attr("indicator-class"),
+            attr("mask-style"),
+            attr("mask-class"),
+            attr("immediate-change"),
+        ],
+        &["change", "pickstart", "pickend"],
+    ),
+    component("picker-view-column", &[], &[]),
+    component(
+        "progress",
+        &[
+            attr("percent"),
+            attr("show-info"),
+            attr("border-radius"),
+            attr("font-size"),
+            attr("stroke-width"),
+            deprecated_attr("color"),
+            attr("active-color"),
+            attr("background-color"),
+            attr("active"),
+            enum_attr("active-mode", &["backwards", "forwards"]),
+            attr("duration"),
+        ],
+        &["activeend"],
+    ),
+    component(
+        "radio",
+        &[
+            attr("value"),
+            attr("checked"),
+            attr("disabled"),
+            attr("color"),
+        ],
+        &[],
+    ),
+    component("radio-group", &[], &["change"]),
+    component(
+        "rich-text",
+        &[
+            attr("nodes"),
+            enum_attr("space", TEXT_SPACE),
+            attr("user-select"),
+        ],
+        &[],
+    ),
+    component("root-portal", &[attr("enable")], &[]),
+    component(
+        "scroll-view",
+        &[
+            attr("scroll-x"),
+            attr("scroll-y"),
+            attr("upper-threshold"),
+            attr("lower-threshold"),
+            attr("scroll-top"),
+            attr("scroll-left"),
+            attr("scroll-into-view"),
+            attr("scroll-with-animation"),
+            attr("enable-back-to-top"),
+            attr("enable-flex"),
+            attr("scroll-anchoring"),
+            attr("enable-passive"),
+            attr("refresher-enabled"),
+            attr("refresher-threshold"),
+            enum_attr("refresher-default-style", &["black", "white", "none"]),
+            attr("refresher-background"),
+            attr("refresher-triggered"),
+            attr("enhanced"),
+            attr("bounces"),
+            attr("show-scrollbar"),
+            attr("paging-enabled"),
+            attr("fast-deceleration"),
+            enum_attr("type", &["list", "custom"]),
+            attr("reverse"),
+            attr("cache-extent"),
+            attr("scroll-into-view-within-extent"),
+            enum_attr(
+                "scroll-into-view-alignment",
+                &["start", "center", "end", "nearest"],
+            ),
+            attr("padding"),
+            attr("min-drag-distance"),
+            attr("associative-container"),
+            attr("using-sticky"),
+        ],
+        &[
+            "scrolltoupper",
+            "scrolltolower",
+            "scroll",
+            "refresherpulling",
+            "refresherrefresh",
+            "refresherrestore",
+            "refresherabort",
+            "dragstart",
+            "dragging",
+            "dragend",
+            "scrollstart",
+            "scrollend",
+        ],
+    ),
+    component(
+        "share-element",
+        &[
+            attr("key"),
+            deprecated_attr("name"),
+            attr("transform"),
+            attr("duration"),
+            attr("easing-function"),
+            attr("transition-on-gesture"),
+            enum_attr("shuttle-on-push", &["from", "to"]),
+            enum_attr("shuttle-on-pop", &["from", "to"]),
+            attr("rect-tween-type"),
+        ],
+        &[],
+    ),
+    component(
+        "slider",
+        &[
+            attr("min"),
+            attr("max"),
+            attr("step"),
+            attr("disabled"),
+            attr("value"),
+            deprecated_attr("color"),
+            deprecated_attr("selected-color"),
+            attr("active-color"),
+            attr("background-color"),
+            attr("block-size"),
+            attr("block-color"),
+            attr("show-value"),
+        ],
+        &["change", "changing"],
+    ),
+    component("sticky-header", &[attr("offset-top"), attr("padding")], &[]),
+    component(
+        "sticky-section",
+        &[attr("push-pinned-header"), attr("padding")],
+        &[],
+    ),
+    component(
+        "swiper",
+        &[
+            attr("indicator-dots"),
+            attr("indicator-color"),
+            attr("indicator-active-color"),
+            attr("autoplay"),
+            attr("current"),
+            attr("interval"),
+            attr("duration"),
+            attr("circular"),
+            attr("vertical"),
+            attr("previous-margin"),
+            attr("next-margin"),
+            attr("snap-to-edge"),
+            attr("display-multiple-items"),
+            enum_attr(
+                "easing-function",
+                &[
+                    "default",
+                    "linear",
+                    "easeInCubic",
+                    "easeOutCubic",
+                    "easeInOutCubic",
+                ],
+            ),
+            attr("layout-type"),
+            attr("indicator-type"),
+            attr("indicator-margin"),
+            attr("indicator-spacing"),
+            attr("indicator-radius"),
+            attr("indicator-width"),
+            attr("indicator-height"),
+            attr("indicator-alignment"),
+            attr("indicator-offset"),
+            attr("scroll-with-animation"),
+            attr("cache-extent"),
+            deprecated_attr("skip-hidden-item-layout"),
+        ],
+        &["change", "transition", "animationfinish"],
+    ),
+    component(
+        "swiper-item",
+        &[attr("item-id"), attr("skip-hidden-item-layout")],
+        &[],
+    ),
+    component(
+        "switch",
+        &[
+            attr("checked"),
+            attr("disabled"),
+            enum_attr("type", &["switch", "checkbox"]),
+            attr("color"),
+        ],
+        &["change"],
+    ),
+    component(
+        "text",
+        &[
+            attr("user-select"),
+            deprecated_attr("selectable"),
+            enum_attr("space", TEXT_SPACE),
+            attr("decode"),
+            enum_attr("overflow", &["clip", "fade", "ellipsis", "visible"]),
+            attr("max-lines"),
+        ],
+        &[],
+    ),
+    component(
+        "textarea",
+        &[
+            attr("value"),
+            attr("placeholder"),
+            attr("placeholder-style"),
+            attr("placeholder-class"),
+            attr("disabled"),
+            attr("maxlength"),
+            attr("auto-focus"),
+            attr("focus"),
+            attr("auto-height"),
+            attr("fixed"),
+            attr("cursor-spacing"),
+            attr("cursor"),
+            attr("cursor-color"),
+            attr("show-confirm-bar"),
+            attr("selection-start"),
+            attr("selection-end"),
+            attr("adjust-position"),
+            attr("hold-keyboard"),
+            attr("disable-default-padding"),
+            enum_attr(
+                "confirm-type",
+                &["send", "search", "next", "go", "done", "return"],
+            ),
+            attr("confirm-hold"),
+            attr("adjust-keyboard-to"),
+        ],
+        &[
+            "focus",
+            "blur",
+            "linechange",
+            "input",
+            "confirm",
+            "keyboardheightchange",
+        ],
+    ),
+    component(
+        "video",
+        &[
+            attr("src"),
+            attr("duration"),
+            attr("controls"),
+            attr("danmu-list"),
+            attr("danmu-btn"),
+            attr("enable-danmu"),
+            attr("autoplay"),
+            attr("loop"),
+            attr("muted"),
+            attr("initial-time"),
+            deprecated_attr("page-gesture"),
+            attr("direction"),
+            attr("show-progress"),
+            attr("show-fullscreen-btn"),
+            attr("show-play-btn"),
+            attr("show-center-play-btn"),
+            attr("enable-progress-gesture"),
+            enum_attr("object-fit", &["contain", "fill", "cover"]),
+            attr("poster"),
+            attr("show-mute-btn"),
+            attr("title"),
+            enum_attr("play-btn-position", &["bottom", "center"]),
+            attr("enable-play-gesture"),
+            attr("auto-pause-if-navigate"),
+            attr("auto-pause-if-open-native"),
+            attr("vslide-gesture"),
+            attr("vslide-gesture-in-fullscreen"),
+            attr("show-bottom-progress"),
+            attr("ad-unit-id"),
+            attr("poster-for-crawler"),
+            attr("show-casting-button"),
+            attr("picture-in-picture-mode"),
+            attr("picture-in-picture-show-progress"),
+            attr("picture-in-picture-init-position"),
+            attr("enable-auto-rotation"),
+            attr("show-screen-lock-button"),
+            attr("show-snapshot-button"),
+            attr("show-background-playback-button"),
+            attr("background-poster"),
+            enum_attr("referrer-policy", REFERRER_POLICY),
+            attr("is-drm"),
+            attr("is-live"),
+            attr("provision-url"),
+            attr("certificate-url"),
+            attr("license-url"),
+            attr("preferred-peak-bit-rate"),
+        ],
+        &[
+            "play",
+            "pause",
+            "ended",
+            "timeupdate",
+            "fullscreenchange",
+            "waiting",
+            "error",
+            "progress",
+            "loadedmetadata",
+            "controlstoggle",
+            "enterpictureinpicture",
+            "leavepictureinpicture",
+            "seekcomplete",
+            "castinguserselect",
+            "castingstatechange",
+            "castinginterrupt",
+        ],
+    ),
+    component("view", &HOVER_ATTRS, &[]),
+    component(
+        "voip-room",
+        &[
+            attr("openid"),
+            enum_attr("mode", &["camera", "video"]),
+            enum_attr("device-position", DEVICE_POSITION),
+        ],
+        &["error"],
+    ),
+    component("web-view", &[attr("src")], &["message", "load", "error"]),
+];
+
+/// Find a builtin component by its tag name.
+pub fn builtin_component(tag_name: &str) -> Option<&'static TmplBuiltinComponent> {
+    BUILTIN_COMPONENTS
+        .binary_search_by(|x| x.name.cmp(tag_name))
+        .ok()
+        .map(|index| &BUILTIN_COMPONENTS[index])
+}
+
+/// All builtin components, sorted by names.
+pub fn builtin_components() -> &'static [TmplBuiltinComponent] {
+    BUILTIN_COMPONENTS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_finds_builtin_components() {
+        assert!(BUILTIN_COMPONENTS.windows(2).all(|x| x[0].name < x[1].name));
+        let image = builtin_component("image").unwrap();
+        assert_eq!(image.attr("mode").unwrap().values.len(), 14);
+        assert!(image.attr("lazyLoad").is_some());
+        assert!(image.has_event("load"));
+        assert!(image.has_event("tap"));
+        assert!(!image.has_event("tapp"));
+        assert!(
+            builtin_component("input")
+                .unwrap()
+                .attr("auto-focus")
+                .unwrap()
+                .deprecated
+        );
+        assert!(builtin_component("audio").unwrap().deprecated);
+        assert!(builtin_component("my-comp").is_none());
+    }
+}
@@ -0,0 +1,1000 @@
+use std::collections::HashMap;
+
+use serde_json::json;
+
+use crate::{
+    diagnostic::{TmplDiagnostic, TmplPosRange},
+    dialect::TmplDialect,
+    display_debug::{text_segments, TmplPrinter},
+    element::{
+        TmplAttr, TmplAttrKind, TmplAttrValue, TmplElement, TmplNode, TmplTextNode, TmplVirtualType,
+    },
+    escape::gen_wxml_lit_str,
+    expr::{TmplExpr, TmplExprLevel},
+    lowering::{
+        component_name, component_names, event_prop, for_key, is_ident, style_object,
+        UnsupportedReporter, MAP_LIST,
+    },
+    tree::TmplTree,
+    utils::camel_to_dash,
+};
+
+const BASE64_CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// The build modes of `generate_js`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TmplCodegenMode {
+    /// Readable code with the template location of each element,
+    /// and runtime assertions such as the uniqueness of `wx:key`.
+    #[default]
+    Development,
+    /// Code without whitespace, with short identifiers and a shared table of the repeated tag and prop names.
+    Production,
+}
+
+/// The options of `generate_js`.
+#[derive(Debug, Clone, Default)]
+pub struct TmplCodegenOptions {
+    pub mode: TmplCodegenMode,
+    /// Generate a v3 source map from the element creations and the expressions back to the template.
+    pub source_map: bool,
+    /// Append the source map to the code as a `sourceMappingURL` data url, instead of returning it.
+    pub inline_source_map: bool,
+}
+
+/// The JavaScript module generated from a template.
+#[derive(Debug)]
+pub struct TmplCodegenOutput {
+    pub code: String,
+    /// The v3 source map in JSON, if it is generated and not inlined.
+    pub source_map: Option<String>,
+    pub diagnostics: Vec<TmplDiagnostic>,
+}
+
+/// Generate the template as a JavaScript module of React render functions, which call `createElement` directly.
+///
+/// The module exports the same functions as `print_tmpl_as_jsx` but runs without a JSX transform.
+/// The static subtrees, see `TmplElement::is_static`, are created once as module constants.
+/// The constructs without an equivalent are reported as `unsupported-in-codegen` warnings.
+/// The source map expects the module to be written as `<path>.js` beside `<path>.wxml`.
+pub fn generate_js(tree: &TmplTree, options: &TmplCodegenOptions) -> TmplCodegenOutput {
+    let name = tree.path.rsplit('/').next().unwrap_or_default();
+    let file = format!("{}.wxml", name);
+    let prod = options.mode == TmplCodegenMode::Production;
+    let mut gen = JsGenerator {
+        printer: TmplPrinter::new(tree, TmplDialect::Wechat),
+        reporter: UnsupportedReporter::new(
+            "unsupported-in-codegen",
+            "the generated JS",
+            &tree.path,
+        ),
+        file: &file,
+        prod,
+        w: JsWriter {
+            minify: prod,
+            ..Default::default()
+        },
+        string_counts: None,
+        strings: HashMap::new(),
+        uses_check_keys: false,
+        uses_map_list: false,
+        hoisted: vec![],
+        hoist_static: true,
+        components: component_names(tree),
+    };
+    gen.printer.data_var = Some(gen.data_var());
+    gen.printer.reserved_names = Some(if prod {
+        &["d", "e", "h", "M"]
+    } else {
+        &["checkKeys", "data", "e", "h", "mapList"]
+    });
+    gen.printer.js_interpolation = true;
+    if prod {
+        // count the names in a dry run, then share the repeated ones which are longer than the references
+        gen.string_counts = Some(HashMap::new());
+        gen.write_module(tree);
+        let mut list: Vec<(String, usize)> = gen
+            .string_counts
+            .take()
+            .unwrap()
+            .into_iter()
+            .filter(|(s, n)| *n > 1 && s.len() > 3)
+            .collect();
+        list.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        gen.strings = list
+            .into_iter()
+            .enumerate()
+            .map(|(i, (s, _))| (s, i))
+            .collect();
+        gen.w = JsWriter {
+            minify: true,
+            ..Default::default()
+        };
+        gen.reporter.diagnostics.clear();
+        gen.uses_check_keys = false;
+        gen.uses_map_list = false;
+    }
+    gen.reporter.report_imports_and_scripts(tree);
+    gen.write_module(tree);
+    let JsWriter {
+        mut out, mappings, ..
+    } = gen.w;
+    let mut source_map = None;
+    if options.source_map {
+        let map = json!({
+            "version": 3,
+            "file": format!("{}.js", name),
+            "sources": [file],
+            "names": [],
+            "mappings": encode_mappings(&mappings),
+        })
+        .to_string();
+        if options.inline_source_map {
+            out += &format!(
+                "//# sourceMappingURL=data:application/json;charset=utf-8;base64,{}\n",
+                base64(map.as_bytes())
+            );
+        } else {
+            source_map = Some(map);
+        }
+    }
+    TmplCodegenOutput {
+        code: out,
+        source_map,
+        diagnostics: gen.reporter.diagnostics,
+    }
+}
+
+// the dev mode helper to warn about the duplicated `wx:key`
+const CHECK_KEYS: &str = r#"
+function checkKeys(list, key, at) {
+  const keys = new Set();
+  mapList(list, (item, index) => {
+    const k = key(item, index);
+    if (keys.has(k)) console.warn(`duplicated wx:key ${String(k)} at ${at}`);
+    keys.add(k);
+  });
+  return list;
+}
+"#;
+
+// the minified `MAP_LIST` for the production mode
+const MAP_LIST_PROD: &str = "function M(l,f){return Array.isArray(l)?l.map(f):typeof l===\"string\"?Array.from(l,f):l&&typeof l===\"object\"?Object.keys(l).map(k=>f(l[k],k)):[]}\n";
+
+// a mapping of (generated line, generated column, source line, source column), all 0-based
+type Mapping = (usize, usize, usize, usize);
+
+// the output which tracks the generated position, in UTF-16 columns as the source maps use
+#[derive(Default)]
+struct JsWriter {
+    minify: bool,
+    out: String,
+    line: usize,
+    col: usize,
+    mappings: Vec<Mapping>,
+}
+
+impl JsWriter {
+    fn write(&mut self, s: &str) {
+        for c in s.chars() {
+            if c == '\n' {
+                self.line += 1;
+                self.col = 0;
+            } else {
+                self.col += c.len_utf16();
+            }
+        }
+        self.out += s;
+    }
+
+    // write the punctuation, in which the spaces are removed when minifying
+    fn punct(&mut self, s: &str) {
+        if self.minify {
+            self.write(&s.replace([' ', '\n'], ""));
+        } else {
+            self.write(s);
+        }
+    }
+
+    // map the current position to the 1-based position in the template
+    fn map(&mut self, pos: (usize, usize)) {
+        if pos.0 == 0 {
+            return;
+        }
+        let mapping = (self.line, self.col, pos.0 - 1, pos.1.saturating_sub(1));
+        match self.mappings.last_mut() {
+            Some(last) if (last.0, last.1) == (mapping.0, mapping.1) => *last = mapping,
+            _ => self.mappings.push(mapping),
+        }
+    }
+}
+
+// the position of the expression of a value, or `pos` if the value is not written as an expression
+fn value_pos(value: &TmplAttrValue, pos: (usize, usize)) -> (usize, usize) {
+    value.expr_pos().map_or(pos, |x| x.0)
+}
+
+fn write_vlq(out: &mut String, value: isize) {
+    let mut v = if value < 0 {
+        ((-value as usize) << 1) | 1
+    } else {
+        (value as usize) << 1
+    };
+    loop {
+        let mut digit = v & 31;
+        v >>= 5;
+        if v > 0 {
+            digit |= 32;
+        }
+        out.push(BASE64_CHARS[digit] as char);
+        if v == 0 {
+            break;
+        }
+    }
+}
+
+// encode the `mappings` field, in which each segment is relative to the previous one
+fn encode_mappings(mappings: &[Mapping]) -> String {
+    let mut ret = String::new();
+    let mut line = 0;
+    let mut prev = (0, 0, 0);
+    let mut first = true;
+    for &(gen_line, gen_col, src_line, src_col) in mappings {
+        while line < gen_line {
+            ret.push(';');
+            line += 1;
+            prev.0 = 0;
+            first = true;
+        }
+        if !first {
+            ret.push(',');
+        }
+        first = false;
+        write_vlq(&mut ret, gen_col as isize - prev.0 as isize);
+        write_vlq(&mut ret, 0);
+        write_vlq(&mut ret, src_line as isize - prev.1 as isize);
+        write_vlq(&mut ret, src_col as isize - prev.2 as isize);
+        prev = (gen_col, src_line, src_col);
+    }
+    ret
+}
+
+fn base64(bytes: &[u8]) -> String {
+    let mut ret = String::new();
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, b)| n | (*b as u32) << (16 - i * 8));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                ret.push(BASE64_CHARS[(n >> (18 - i * 6)) as usize & 63] as char);
+            } else {
+                ret.push('=');
+            }
+        }
+    }
+    ret
+}
+
+struct JsGenerator<'a> {
+    printer: TmplPrinter<'a>,
+    // the file name of the template in the locations
+    file: &'a str,
+    prod: bool,
+    w: JsWriter,
+    reporter: UnsupportedReporter<'a>,
+    // the occurrences of the names, counted in the dry run of the production mode
+    string_counts: Option<HashMap<String, usize>>,
+    // the indexes of the names in the shared table `S`
+    strings: HashMap<String, usize>,
+    uses_check_keys: bool,
+    uses_map_list: bool,
+    // the static subtrees to be hoisted as the module constants
+    hoisted: Vec<&'a TmplElement>,
+    // disabled while writing the hoisted subtrees
+    hoist_static: bool,
+    // the component names of the sub templates
+    components: HashMap<&'a str, String>,
+}
+
+impl<'a> JsGenerator<'a> {
+    fn data_var(&self) -> &'static str {
+        if self.prod {
+            "d"
+        } else {
+            "data"
+        }
+    }
+
+    fn fragment(&self) -> &'static str {
+        if self.prod {
+            "F"
+        } else {
+            "Fragment"
+        }
+    }
+
+    fn hoisted_name(&self, index: usize) -> String {
+        if self.prod {
+            format!("$s{}", index)
+        } else {
+            format!("$static{}", index)
+        }
+    }
+
+    fn write_module(&mut self, tree: &'a TmplTree) {
+        self.hoisted.clear();
+        if self.prod {
+            self.w
+                .write("import{createElement as h,Fragment as F}from\"react\";");
+            if !self.strings.is_empty() {
+                let mut list: Vec<(&String, &usize)> = self.strings.iter().collect();
+                list.sort_by_key(|x| x.1);
+                let list: Vec<String> = list.into_iter().map(|x| gen_wxml_lit_str(x.0)).collect();
+                self.w.write(&format!("const S=[{}];", list.join(",")));
+            }
+        } else {
+            self.w
+                .write("import { createElement as h, Fragment } from \"react\";\n\n");
+        }
+        let mut names: Vec<&String> = tree.sub_templates.keys().collect();
+        names.sort();
+        for name in names {
+            let elem = &tree.sub_templates[name];
+            self.w.write("export function ");
+            self.w.map(elem.start_pos);
+            self.w.write(&self.components[name.as_str()]);
+            self.w.write(&format!("({})", self.data_var()));
+            self.function_body_start();
+            self.children(elem);
+            self.w.punct(";\n}\n\n");
+        }
+        self.w.write(&format!(
+            "export default function render({})",
+            self.data_var()
+        ));
+        self.function_body_start();
+        self.children(&tree.root);
+        self.w.punct(";\n}");
+        self.w.write("\n");
+        // the hoisted constants are initialized before any render function is called
+        let hoisted = std::mem::take(&mut self.hoisted);
+        if !hoisted.is_empty() {
+            self.hoist_static = false;
+            self.w.punct("\n");
+            for (i, elem) in hoisted.iter().enumerate() {
+                self.w.write(&format!("const {}", self.hoisted_name(i)));
+                self.w.punct(" = ");
+                self.element(elem, None);
+                self.w.punct(";\n");
+            }
+            if self.prod {
+                self.w.write("\n");
+            }
+            self.hoist_static = true;
+        }
+        self.hoisted = hoisted;
+        if self.uses_check_keys {
+            self.w.write(CHECK_KEYS);
+        }
+        if self.uses_map_list && self.prod {
+            self.w.write(MAP_LIST_PROD);
+        } else if self.uses_map_list {
+            self.w.write(MAP_LIST);
+        }
+    }
+
+    fn function_body_start(&mut self) {
+        if self.prod {
+            self.w.write("{return ");
+        } else {
+            self.w.write(" {\n  return ");
+        }
+    }
+
+    // a tag name or a prop name, which may be shared in the production mode
+    fn string(&mut self, s: &str) -> String {
+        if let Some(counts) = &mut self.string_counts {
+            *counts.entry(s.to_string()).or_default() += 1;
+        }
+        match self.strings.get(s) {
+            Some(i) => format!("S[{}]", i),
+            None => gen_wxml_lit_str(s),
+        }
+    }
+
+    fn prop_key(&mut self, name: &str) -> String {
+        let s = self.string(name);
+        if s.starts_with("S[") {
+            format!("[{}]", s)
+        } else if is_ident(name) {
+            name.to_string()
+        } else {
+            s
+        }
+    }
+
+    // the location comment of an element in the development mode, e.g. `/* index.wxml:1:1 */`
+    fn location(&mut self, pos: (usize, usize)) {
+        if !self.prod && pos.0 > 0 {
+            self.w
+                .write(&format!("/* {}:{}:{} */ ", self.file, pos.0, pos.1));
+        }
+    }
+
+    // write an expression which is generated from the element at `pos`
+    fn expr(&mut self, expr: &TmplExpr, level: TmplExprLevel, pos: (usize, usize)) {
+        self.w.map(pos);
+        let s = self.printer.expr_string(expr, level);
+        self.w.write(&s);
+    }
+
+    fn value_expr(&mut self, value: &TmplAttrValue, level: TmplExprLevel, pos: (usize, usize)) {
+        match value {
+            TmplAttrValue::Static(s) => self.w.write(&gen_wxml_lit_str(s)),
+            TmplAttrValue::Dynamic { expr, .. } => self.expr(expr, level, value_pos(value, pos)),
+        }
+    }
+
+    // the children as a single element, a fragment or `null`
+    fn children(&mut self, elem: &'a TmplElement) {
+        let children: Vec<&TmplNode> = elem
+            .children
+            .iter()
+            .filter(|x| !matches!(x, TmplNode::Comment(_)))
+            .collect();
+        match children.as_slice() {
+            [] => self.w.write("null"),
+            [TmplNode::Element(child)] => self.node(child),
+            _ => {
+                self.w.write(&format!("h({}", self.fragment()));
+                self.w.punct(", null");
+                self.child_args(elem);
+                self.w.write(")");
+            }
+        }
+    }
+
+    // the children as the trailing arguments of `h`, each with a leading comma
+    fn child_args(&mut self, elem: &'a TmplElement) {
+        for child in elem.children.iter() {
+            match child {
+                TmplNode::TextNode(TmplTextNode::Static(s)) => {
+                    self.w.punct(", ");
+                    self.w.write(&gen_wxml_lit_str(s));
+                }
+                TmplNode::TextNode(text @ TmplTextNode::Dynamic { expr, .. }) => {
+                    let pos = text.expr_pos().map_or(elem.start_pos, |x| x.0);
+                    match text_segments(expr) {
+                        Some(segs) => {
+                            for seg in segs {
+                                self.w.punct(", ");
+                                match seg {
+                                    TmplExpr::LitStr(s) => self.w.write(&gen_wxml_lit_str(s)),
+                                    TmplExpr::ToStringWithoutUndefined(x) => {
+                                        self.expr(x, TmplExprLevel::Cond, pos)
+                                    }
+                                    _ => unreachable!(),
+                                }
+                            }
+                        }
+                        None => {
+                            self.w.punct(", ");
+                            self.expr(expr, TmplExprLevel::Cond, pos);
+                        }
+                    }
+                }
+                TmplNode::Comment(_) => {}
+                TmplNode::Element(x) => {
+                    self.w.punct(", ");
+                    self.node(x);
+                }
+            }
+        }
+    }
+
+    // an element, which may be a ternary expression or a `.map` call
+    fn node(&mut self, elem: &'a TmplElement) {
+        let pos = (elem.start_pos, elem.end_pos);
+        match &elem.virtual_type {
+            TmplVirtualType::None | TmplVirtualType::Pure => self.element(elem, None),
+            TmplVirtualType::IfGroup => {
+                let mut has_else = false;
+                for branch in elem.children.iter().filter_map(|x| x.as_element()) {
+                    match &branch.virtual_type {
+                        TmplVirtualType::If { cond } | TmplVirtualType::Elif { cond } => {
+                            self.value_expr(cond, TmplExprLevel::LogicOr, branch.start_pos);
+                            self.w.punct(" ? ");
+                            self.children(branch);
+                            self.w.punct(" : ");
+                        }
+                        _ => {
+                            self.children(branch);
+                            has_else = true;
+                            break;
+                        }
+                    }
+                }
+                if !has_else {
+                    self.w.write("null");
+                }
+            }
+            TmplVirtualType::If { cond } | TmplVirtualType::Elif { cond } => {
+                self.value_expr(cond, TmplExprLevel::LogicOr, elem.start_pos);
+                self.w.punct(" ? ");
+                self.children(elem);
+                self.w.punct(" : null");
+            }
+            TmplVirtualType::Else => self.children(elem),
+            TmplVirtualType::For {
+                list,
+                item_name,
+                index_name,
+                key,
+            } => {
+                let check_keys = !self.prod && key.is_some();
+                self.uses_map_list = true;
+                self.w.write(if self.prod { "M(" } else { "mapList(" });
+                if check_keys {
+                    self.uses_check_keys = true;
+                    self.w.write("checkKeys(");
+                }
+                self.value_expr(list, TmplExprLevel::Cond, elem.start_pos);
+                let scope_len = self.printer.enter_scope(elem);
+                let names = self.printer.entered_scope_names(scope_len);
+                let item_name = names.first().unwrap_or(item_name).clone();
+                let index_name = names.get(1).unwrap_or(index_name).clone();
+                let key = for_key(key.as_deref(), &item_name, &index_name);
+                if check_keys {
+                    let at = format!("{}:{}:{}", self.file, elem.start_pos.0, elem.start_pos.1);
+                    self.w.write(&format!(
+                        ", ({}, {}) => {}, {})",
+                        item_name,
+                        index_name,
+                        key,
+                        gen_wxml_lit_str(&at)
+                    ));
+                }
+                self.w.punct(", ");
+                self.w.write(&format!("({}", item_name));
+                self.w.punct(", ");
+                self.w.write(&format!("{})", index_name));
+                self.w.punct(" => ");
+                match elem.children.as_slice() {
+                    [TmplNode::Element(child)]
+                        if matches!(
+                            child.virtual_type,
+                            TmplVirtualType::None | TmplVirtualType::Pure
+                        ) =>
+                    {
+                        self.element(child, Some(&key))
+                    }
+                    _ => {
+                        self.location(elem.start_pos);
+                        self.w.map(elem.start_pos);
+                        self.fragment_with_key(&key);
+                        self.child_args(elem);
+                        self.w.write(")");
+                    }
+                }
+                self.printer.leave_scope(scope_len);
+                self.w.write(")");
+            }
+            TmplVirtualType::TemplateRef { target, data } => {
+                let name = match target {
+                    TmplAttrValue::Static(name) => match self.components.get(name.as_str()) {
+                        Some(x) => x.clone(),
+                        None => component_name(name),
+                    },
+                    TmplAttrValue::Dynamic { .. } => {
+                        self.reporter
+                            .report("<template is> with a dynamic name", pos);
+                        self.w.write("null");
+                        return;
+                    }
+                };
+                self.location(elem.start_pos);
+                self.w.map(elem.start_pos);
+                self.w.write(&format!("h({}", name));
+                self.w.punct(", ");
+                match data {
+                    TmplAttrValue::Dynamic { expr, .. } => match &**expr {
+                        TmplExpr::LitObj(x) if x.is_empty() => self.w.write("null"),
+                        _ => self.expr(expr, TmplExprLevel::Cond, value_pos(data, elem.start_pos)),
+                    },
+                    TmplAttrValue::Static(_) => {
+                        self.reporter.report("<template is> with static data", pos);
+                        self.w.write("null");
+                    }
+                }
+                self.w.write(")");
+            }
+            TmplVirtualType::Include { path } => {
+                self.reporter
+                    .report(&format!(r#"<include src="{}">"#, path), pos);
+                self.w.write("null");
+            }
+            TmplVirtualType::Slot { name, props } => {
+                let is_default = matches!(name, TmplAttrValue::Static(x) if x.is_empty());
+                if !is_default || props.as_ref().map(|x| x.len()).unwrap_or(0) > 0 {
+                    self.reporter
+                        .report("<slot> with a name or properties", pos);
+                    self.w.write("null");
+                    return;
+                }
+                self.w.map(elem.start_pos);
+                self.w.write(&format!("{}.children", self.data_var()));
+            }
+        }
+    }
+
+    // an element creation, e.g. `h('view', { id: 'a' }, 'text')`
+    fn element(&mut self, elem: &'a TmplElement, key: Option<&str>) {
+        // the keyed elements are in lists and not hoisted
+        if self.hoist_static && key.is_none() && elem.is_static() {
+            let name = self.hoisted_name(self.hoisted.len());
+            self.hoisted.push(elem);
+            self.w.map(elem.start_pos);
+            self.w.write(&name);
+            return;
+        }
+        let pos = (elem.start_pos, elem.end_pos);
+        self.location(elem.start_pos);
+        self.w.map(elem.start_pos);
+        if let TmplVirtualType::Pure = elem.virtual_type {
+            match key {
+                Some(key) => self.fragment_with_key(key),
+                None => {
+                    self.w.write(&format!("h({}", self.fragment()));
+                    self.w.punct(", null");
+                }
+            }
+            self.child_args(elem);
+            self.w.write(")");
+            return;
+        }
+        let tag_name = self.string(&elem.tag_name);
+        self.w.write(&format!("h({}", tag_name));
+        self.w.punct(", ");
+        // the props with the positions of their values
+        let mut props: Vec<(String, String, (usize, usize))> = vec![];
+        if let Some(key) = key {
+            props.push(("key".to_string(), key.to_string(), elem.start_pos));
+        }
+        for attr in elem.attrs.iter() {
+            if let Some((name, value)) = self.prop(attr, &elem.tag_name, pos) {
+                props.push((name, value, value_pos(&attr.value, elem.start_pos)));
+            }
+        }
+        match &elem.slot {
+            None => {}
+            Some(x) => props.push((
+                "slot".to_string(),
+                self.attr_value(x),
+                value_pos(x, elem.start_pos),
+            )),
+        }
+        for (capture_name, _) in elem.slot_values.iter() {
+            self.reporter
+                .report(&format!("slot:{}", camel_to_dash(capture_name)), pos);
+        }
+        if let Some(generics) = &elem.generics {
+            let mut list: Vec<&String> = generics.keys().collect();
+            list.sort();
+            for k in list {
+                self.reporter.report(&format!("generic:{}", k), pos);
+            }
+        }
+        if props.is_empty() {
+            self.w.write("null");
+        } else {
+            self.w.punct("{ ");
+            for (i, (name, value, value_pos)) in props.iter().enumerate() {
+                if i > 0 {
+                    self.w.punct(", ");
+                }
+                let key = self.prop_key(name);
+                self.w.write(&key);
+                self.w.punct(": ");
+                self.w.map(*value_pos);
+                self.w.write(value);
+            }
+            self.w.punct(" }");
+        }
+        self.child_args(elem);
+        self.w.write(")");
+    }
+
+    fn fragment_with_key(&mut self, key: &str) {
+        self.w.write(&format!("h({}", self.fragment()));
+        self.w.punct(", { key: ");
+        self.w.write(key);
+        self.w.punct(" }");
+    }
+
+    fn attr_value(&self, value: &TmplAttrValue) -> String {
+        match value {
+            TmplAttrValue::Static(x) => gen_wxml_lit_str(x),
+            TmplAttrValue::Dynamic { expr, .. } => {
+                self.printer.expr_string(expr, TmplExprLevel::Cond)
+            }
+        }
+    }
+
+    // the prop name and value of an attribute, or `None` if it is not supported
+    fn prop(
+        &mut self,
+        attr: &TmplAttr,
+        tag_name: &str,
+        pos: TmplPosRange,
+    ) -> Option<(String, String)> {
+        if self.reporter.report_wxml_only_attr(&attr.kind, pos) {
+            return None;
+        }
+        let name = match &attr.kind {
+            TmplAttrKind::Id => "id".to_string(),
+            TmplAttrKind::Class => "className".to_string(),
+            TmplAttrKind::Style => match style_object(&self.printer, &attr.value) {
+                Some(x) => return Some(("style".to_string(), x)),
+                None => {
+                    self.reporter
+                        .report("style with dynamic property names", pos);
+                    return None;
+                }
+            },
+            TmplAttrKind::Slot => "slot".to_string(),
+            TmplAttrKind::PropertyOrExternalClass { name } => name.to_string(),
+            TmplAttrKind::Data { name } => format!("data-{}", camel_to_dash(name)),
+            TmplAttrKind::ModelProperty { name } => {
+                self.reporter.report(&format!("model:{}", name), pos);
+                name.to_string()
+            }
+            TmplAttrKind::Event {
+                capture,
+                catch,
+                mut_bind,
+                name,
+            } => {
+                if *mut_bind {
+                    self.reporter.report(&format!("mut-bind:{}", name), pos);
+                }
+                // the handler is a method name in WXML
+                let handler = match &attr.value {
+                    TmplAttrValue::Static(x) if is_ident(x) => format!("{}.{}", self.data_var(), x),
+                    TmplAttrValue::Static(x) => {
+                        format!("{}[{}]", self.data_var(), gen_wxml_lit_str(x))
+                    }
+                    TmplAttrValue::Dynamic { expr, .. } => {
+                        self.printer.expr_string(expr, TmplExprLevel::Member)
+                    }
+                };
+                let handler = if *catch && self.prod {
+                    format!("e=>{{e.stopPropagation();{}(e)}}", handler)
+                } else if *catch {
+                    format!("(e) => {{ e.stopPropagation(); {}(e); }}", handler)
+                } else {
+                    handler
+                };
+                match event_prop(tag_name, name, *capture) {
+                    Some(prop) => return Some((prop, handler)),
+                    None => {
+                        self.reporter.report(&format!("bind:{}", name), pos);
+                        return None;
+                    }
+                }
+            }
+            // the WXML-only attributes, which are reported above
+            _ => return None,
+        };
+        Some((name, self.attr_value(&attr.value)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TmplGroup;
+
+    fn group() -> TmplGroup {
+        let mut group = TmplGroup::new();
+        group
+            .add_tmpl(
+                "pages/index",
+                r#"<template name="card"><text>{{ title }}</text></template>
+<view wx:for="{{ list }}" wx:key="id" class="a {{ item.c }}" bind:tap="onTap">#{{ index }}</view>
+<view wx:if="{{ a }}" hidden /><template is="card" data="{{ title: 'x' }}" />"#,
+            )
+            .unwrap();
+        group
+    }
+
+    #[test]
+    fn it_generates_js_with_source_maps() {
+        let group = group();
+        let tree = group.get_tree("pages/index").unwrap();
+        let options = TmplCodegenOptions {
+            source_map: true,
+            ..Default::default()
+        };
+        let ret = generate_js(tree, &options);
+        assert_eq!(
+            ret.code,
+            r#"import { createElement as h, Fragment } from "react";
+
+export function Card(data) {
+  return /* index.wxml:1:23 */ h('text', null, data.title);
+}
+
+export default function render(data) {
+  return h(Fragment, null, mapList(checkKeys(data.list, (item, index) => item.id, 'index.wxml:2:1'), (item, index) => /* index.wxml:2:1 */ h('view', { key: item.id, className: 'a '+(item.c??''), onClick: data.onTap }, '#', index)), data.a ? $static0 : null, /* index.wxml:3:32 */ h(Card, {title:'x'}));
+}
+
+const $static0 = /* index.wxml:3:1 */ h('view', { hidden: true });
+
+function checkKeys(list, key, at) {
+  const keys = new Set();
+  mapList(list, (item, index) => {
+    const k = key(item, index);
+    if (keys.has(k)) console.warn(`duplicated wx:key ${String(k)} at ${at}`);
+    keys.add(k);
+  });
+  return list;
+}
+
+function mapList(list, f) {
+  if (Array.isArray(list)) return list.map(f);
+  if (typeof list === "string") return Array.from(list, f);
+  if (list && typeof list === "object") return Object.keys(list).map((k) => f(list[k], k));
+  return [];
+}
+"#
+        );
+        assert!(ret.diagnostics.is_empty());
+        let source_map = ret.source_map.unwrap();
+        assert_eq!(
+            source_map,
+            r#"{"file":"index.js","mappings":";;gBAAA;+BAAsB,gBAAM;;;;6CACd,8FAAd,iBAAA,oBAA+C,4BAA/C,mBAA+E,SAClE,SAAb,uCAA+B,QAA0B;;;sCAAzD,oBAAA","names":[],"sources":["index.wxml"],"version":3}"#
+        );
+
+        // the class expression is mapped to the `{{ item.c }}` in the middle of the second line
+        let map: serde_json::Value = serde_json::from_str(&source_map).unwrap();
+        let mappings = decode_mappings(map["mappings"].as_str().unwrap());
+        assert!(mappings.contains(&(7, 176, 1, 47)));
+        assert!(ret.code.lines().nth(7).unwrap()[176..].starts_with("'a '+(item.c??'')"));
+    }
+
+    // decode the `mappings` field into the 0-based mappings
+    fn decode_mappings(mappings: &str) -> Vec<Mapping> {
+        let mut ret = vec![];
+        let (mut src_line, mut src_col) = (0, 0);
+        for (line, segs) in mappings.split(';').enumerate() {
+            let mut col = 0;
+            for seg in segs.split(',').filter(|x| !x.is_empty()) {
+                let mut fields = vec![];
+                let (mut v, mut shift) = (0, 0);
+                for c in seg.bytes() {
+                    let digit = BASE64_CHARS.iter().position(|x| *x == c).unwrap() as isize;
+                    v |= (digit & 31) << shift;
+                    shift += 5;
+                    if digit & 32 == 0 {
+                        fields.push(if v & 1 == 1 { -(v >> 1) } else { v >> 1 });
+                        (v, shift) = (0, 0);
+                    }
+                }
+                col += fields[0];
+                src_line += fields[2];
+                src_col += fields[3];
+                ret.push((line, col as usize, src_line as usize, src_col as usize));
+            }
+        }
+        ret
+    }
+
+    #[test]
+    fn it_generates_production_js() {
+        let group = group();
+        let tree = group.get_tree("pages/index").unwrap();
+        let options = TmplCodegenOptions {
+            mode: TmplCodegenMode::Production,
+            source_map: true,
+            inline_source_map: true,
+        };
+        let ret = generate_js(tree, &options);
+        let (code, map) = ret.code.split_once("//# sourceMappingURL=").unwrap();
+        assert_eq!(
+            code,
+            r#"import{createElement as h,Fragment as F}from"react";const S=['view'];export function Card(d){return h('text',null,d.title);}export default function render(d){return h(F,null,M(d.list,(item,index)=>h(S[0],{key:item.id,className:'a '+(item.c??''),onClick:d.onTap},'#',index)),d.a?$s0:null,h(Card,{title:'x'}));}
+const $s0=h(S[0],{hidden:true});
+function M(l,f){return Array.isArray(l)?l.map(f):typeof l==="string"?Array.from(l,f):l&&typeof l==="object"?Object.keys(l).map(k=>f(l[k],k)):[]}
+"#
+        );
+        assert!(
+            map.starts_with("data:application/json;charset=utf-8;base64,eyJmaWxlIjoiaW5kZXguanMi")
+        );
+        assert!(ret.source_map.is_none());
+
+        // the scope names shadowing the short identifiers are renamed
+        let mut group = TmplGroup::new();
+        group
+            .add_tmpl(
+                "list",
+                r#"<view wx:for="{{ list }}" wx:for-item="h" wx:for-index="i">{{ h }}{{ i }}</view>"#,
+            )
+            .unwrap();
+        let tree = group.get_tree("list").unwrap();
+        let ret = generate_js(tree, &options);
+        assert!(ret
+            .code
+            .contains("M(d.list,($0,i)=>h('view',{key:i},$0,i))"));
+    }
+
+    #[test]
+    fn it_hoists_static_subtrees() {
+        let mut group = TmplGroup::new();
+        group
+            .add_tmpl(
+                "list",
+                r#"<view class="a"><text>hi</text><image src="x.png" /></view><view>{{ a }}<text>{{ 'b' }}</text></view><view bind:tap="f">x</view>"#,
+            )
+            .unwrap();
+        let tree = group.get_tree("list").unwrap();
+        let list: Vec<bool> = tree
+            .root()
+            .children()
+            .iter()
+            .map(|x| x.is_static())
+            .collect();
+        assert_eq!(list, [true, false, false]);
+        let ret = generate_js(tree, &TmplCodegenOptions::default());
+        assert!(ret.code.contains(
+            "return h(Fragment, null, $static0, /* list.wxml:1:60 */ h('view', null, data.a, $static1), /* list.wxml:1:102 */ h('view', { onClick: data.f }, 'x'));"
+        ));
+        assert!(ret.code.ends_with(
+            "const $static0 = /* list.wxml:1:1 */ h('view', { className: 'a' }, /* list.wxml:1:17 */ h('text', null, 'hi'), /* list.wxml:1:32 */ h('image', { src: 'x.png' }));\nconst $static1 = /* list.wxml:1:73 */ h('text', null, 'b');\n"
+        ));
+    }
+
+    #[test]
+    fn it_reports_events_without_react_equivalent() {
+        let mut group = TmplGroup::new();
+        group
+            .add_tmpl("list", r#"<view bind:longpress="f" bind:touchend="g" />"#)
+            .unwrap();
+        let tree = group.get_tree("list").unwrap();
+        let ret = generate_js(tree, &TmplCodegenOptions::default());
+        assert!(ret.code.contains("h('view', { onTouchEnd: data.g })"));
+        assert_eq!(ret.diagnostics.len(), 1);
+        assert_eq!(ret.diagnostics[0].code, "unsupported-in-codegen");
+        assert_eq!(ret.diagnostics[0].start_pos, (1, 1));
+    }
+
+    #[test]
+    fn it_renames_colliding_components() {
+        let mut group = TmplGroup::new();
+        group
+            .add_tmpl(
+                "list",
+                r#"<template name="s"><text>{{ a }}</text></template><template name="f"><text>{{ a }}</text></template><template name="fragment"><text>{{ a }}</text></template><template name="card-item"><text>{{ a }}</text></template><template name="cardItem"><text>{{ a }}</text></template><template is="s" /><template is="f" /><template is="fragment" /><template is="card-item" /><template is="cardItem" />"#,
+            )
+            .unwrap();
+        let tree = group.get_tree("list").unwrap();
+        let options = TmplCodegenOptions {
+            mode: TmplCodegenMode::Production,
+            ..Default::default()
+        };
+        let code = generate_js(tree, &options).code;
+        assert!(code.contains("export function CardItem(d){"));
+        assert!(code.contains("export function CardItem$1(d){"));
+        assert!(code.contains("export function F$1(d){"));
+        assert!(code.contains("export function Fragment$1(d){"));
+        assert!(code.contains("export function S$1(d){"));
+        assert!(code.contains(
+            "return h(F,null,h(S$1,null),h(F$1,null),h(Fragment$1,null),h(CardItem,null),h(CardItem$1,null));"
+        ));
+
+        let code = generate_js(tree, &TmplCodegenOptions::default()).code;
+        assert!(code.contains("export function Fragment$1(data) {"));
+        assert!(code.contains("/* list.wxml:1:311 */ h(Fragment$1, null)"));
+    }
+}
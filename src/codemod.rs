@@ -0,0 +1,250 @@
+use serde_json::Value;
+
+use crate::cst::{quote_attr_value, CstAttr, CstQuote, TmplCst};
+
+/// A declarative rewrite rule of a codemod.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CodemodRule {
+    RenameTag {
+        from: String,
+        to: String,
+    },
+    /// Rename an attribute, such as `bind:tap` to `catch:tap` or `wx:for-items` to `wx:for`.
+    RenameAttr {
+        tag: Option<String>,
+        from: String,
+        to: String,
+    },
+    RemoveAttr {
+        tag: Option<String>,
+        name: String,
+    },
+    /// Replace the raw value of an attribute, optionally only when it equals `from`.
+    ///
+    /// The value cannot use both quotes in its `{{ }}` expressions.
+    SetAttrValue {
+        tag: Option<String>,
+        name: String,
+        from: Option<String>,
+        to: String,
+    },
+}
+
+/// A set of rewrite rules applied on templates.
+///
+/// The rules are applied on the lossless syntax tree,
+/// so the text not touched by the rules is kept as it is.
+#[derive(Debug, Clone, Default)]
+pub struct Codemod {
+    rules: Vec<CodemodRule>,
+}
+
+impl Codemod {
+    pub fn new(rules: Vec<CodemodRule>) -> Self {
+        Self { rules }
+    }
+
+    /// Read the rules from JSON like:
+    ///
+    /// ```json
+    /// { "rules": [
+    ///     { "rule": "rename-tag", "from": "old-button", "to": "ui-button" },
+    ///     { "rule": "rename-attr", "tag": "ui-button", "from": "bind:tap", "to": "catch:tap" },
+    ///     { "rule": "remove-attr", "name": "wx:key" },
+    ///     { "rule": "set-attr-value", "name": "type", "from": "default", "to": "normal" }
+    /// ] }
+    /// ```
+    ///
+    /// The `tag` field is optional and limits the rule to the elements with the tag name.
+    pub fn from_json(s: &str) -> Result<Self, String> {
+        let value: Value = serde_json::from_str(s).map_err(|e| e.to_string())?;
+        let rules = value
+            .get("rules")
+            .and_then(|x| x.as_array())
+            .ok_or_else(|| "`rules` should be an array".to_string())?;
+        let rules = rules
+            .iter()
+            .enumerate()
+            .map(|(index, rule)| {
+                let field = |name: &str| rule.get(name).and_then(|x| x.as_str()).map(String::from);
+                let required = |name: &str| {
+                    field(name)
+                        .ok_or_else(|| format!("rule {} requires the field `{}`", index, name))
+                };
+                let rule = match field("rule").as_deref() {
+                    Some("rename-tag") => CodemodRule::RenameTag {
+                        from: required("from")?,
+                        to: required("to")?,
+                    },
+                    Some("rename-attr") => CodemodRule::RenameAttr {
+                        tag: field("tag"),
+                        from: required("from")?,
+                        to: required("to")?,
+                    },
+                    Some("remove-attr") => CodemodRule::RemoveAttr {
+                        tag: field("tag"),
+                        name: required("name")?,
+                    },
+                    Some("set-attr-value") => {
+                        let to = required("to")?;
+                        quote_attr_value(&to, CstQuote::Double)
+                            .map_err(|e| format!("rule {}: {}", index, e))?;
+                        CodemodRule::SetAttrValue {
+                            tag: field("tag"),
+                            name: required("name")?,
+                            from: field("from"),
+                            to,
+                        }
+                    }
+                    _ => return Err(format!("rule {} has an unknown `rule` type", index)),
+                };
+                Ok(rule)
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(Self::new(rules))
+    }
+
+    pub fn rules(&self) -> &[CodemodRule] {
+        &self.rules
+    }
+
+    /// Apply the rules on a template source.
+    ///
+    /// The rules are applied in order on each element,
+    /// so a rule matches the tag and attribute names updated by the earlier rules.
+    ///
+    /// Returns `None` if nothing is changed,
+    /// or an error if the source cannot be parsed or a new value cannot be quoted.
+    pub fn apply(&self, source: &str) -> Result<Option<String>, String> {
+        let cst = TmplCst::parse(source).map_err(|e| e.to_string())?;
+        let mut edit = cst.edit();
+        for elem in cst.elements() {
+            let mut tag_name = elem.tag_name().to_string();
+            let mut attrs: Vec<AttrState> = elem
+                .attrs()
+                .iter()
+                .map(|attr| AttrState {
+                    attr,
+                    name: attr.name().to_string(),
+                    value: attr_raw_value(&cst, attr).to_string(),
+                    removed: false,
+                })
+                .collect();
+            for rule in self.rules.iter() {
+                let tag_matches = |tag: &Option<String>| tag.iter().all(|x| *x == tag_name);
+                let find = |attrs: &[AttrState], name: &str| {
+                    attrs.iter().position(|x| !x.removed && x.name == name)
+                };
+                match rule {
+                    CodemodRule::RenameTag { from, to } => {
+                        if tag_name == *from {
+                            tag_name = to.clone();
+                        }
+                    }
+                    CodemodRule::RenameAttr { tag, from, to } => {
+                        if let (true, Some(i)) = (tag_matches(tag), find(&attrs, from)) {
+                            attrs[i].name = to.clone();
+                        }
+                    }
+                    CodemodRule::RemoveAttr { tag, name } => {
+                        if let (true, Some(i)) = (tag_matches(tag), find(&attrs, name)) {
+                            attrs[i].removed = true;
+                        }
+                    }
+                    CodemodRule::SetAttrValue {
+                        tag,
+                        name,
+                        from,
+                        to,
+                    } => {
+                        if let (true, Some(i)) = (tag_matches(tag), find(&attrs, name)) {
+                            if from.is_none() || from.as_ref() == Some(&attrs[i].value) {
+                                attrs[i].value = to.clone();
+                            }
+                        }
+                    }
+                }
+            }
+            if tag_name != elem.tag_name() {
+                edit.rename_tag(elem, &tag_name);
+            }
+            for state in attrs.iter() {
+                if state.removed {
+                    edit.remove_attr(state.attr);
+                    continue;
+                }
+                if state.name != state.attr.name() {
+                    edit.rename_attr(state.attr, &state.name);
+                }
+                if state.value != attr_raw_value(&cst, state.attr) {
+                    edit.set_attr_value(state.attr, &state.value)?;
+                }
+            }
+        }
+        if edit.edits().is_empty() {
+            return Ok(None);
+        }
+        // each part of an element is edited at most once, so the edits never overlap
+        match edit.apply() {
+            Ok(x) => Ok(Some(x)),
+            Err(e) => unreachable!("{}", e),
+        }
+    }
+}
+
+// an attribute with the changes made by the rules applied so far
+struct AttrState<'a> {
+    attr: &'a CstAttr,
+    name: String,
+    value: String,
+    removed: bool,
+}
+
+fn attr_raw_value<'a>(cst: &'a TmplCst, attr: &CstAttr) -> &'a str {
+    match attr.value() {
+        Some(v) => cst.text(&v.inner_range()),
+        None => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_applies_rules() {
+        let codemod = Codemod::from_json(
+            r#"{ "rules": [
+                { "rule": "rename-tag", "from": "old-button", "to": "ui-button" },
+                { "rule": "rename-attr", "tag": "ui-button", "from": "bind:tap", "to": "catch:tap" },
+                { "rule": "rename-attr", "from": "wx:for-items", "to": "wx:for" },
+                { "rule": "remove-attr", "name": "legacy" },
+                { "rule": "set-attr-value", "name": "type", "from": "default", "to": "normal" },
+                { "rule": "rename-attr", "tag": "ui-button", "from": "type", "to": "kind" },
+                { "rule": "set-attr-value", "name": "title", "to": "it's \"{{ a }}\"" }
+            ] }"#,
+        )
+        .unwrap();
+        let ret = codemod
+            .apply(
+                r#"<old-button bind:tap="a" legacy type='default' title='t'>
+  <view wx:for-items="{{ list }}" bind:tap="b" type="primary" />
+</old-button>"#,
+            )
+            .unwrap();
+        assert_eq!(
+            ret.unwrap(),
+            r#"<ui-button catch:tap="a" kind='normal' title='it&#39;s "{{ a }}"'>
+  <view wx:for="{{ list }}" bind:tap="b" type="primary" />
+</ui-button>"#
+        );
+        assert_eq!(codemod.apply("<view />").unwrap(), None);
+        assert!(Codemod::from_json(r#"{ "rules": [{ "rule": "rename-tag" }] }"#).is_err());
+        let to = r#"{{ a ? \"x\" : 'y' }}"#;
+        let rules = format!(
+            r#"{{ "rules": [{{ "rule": "set-attr-value", "name": "a", "to": "{}" }}] }}"#,
+            to
+        );
+        assert!(Codemod::from_json(&rules).is_err());
+    }
+}
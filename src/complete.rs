@@ -0,0 +1,595 @@
+use std::collections::HashSet;
+
+use serde_json::{json, Value};
+
+use crate::{
+    builtin_component::{builtin_component, builtin_components, COMMON_ATTRS, COMMON_EVENTS},
+    component_config::{resolve_tag, TmplComponentConfig},
+    data_path::collect_data_paths,
+    element::TmplScript,
+    path,
+    tree::TmplTree,
+    utils::camel_to_dash,
+    TmplGroup,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TmplCompletionKind {
+    Tag,
+    Attribute,
+    Event,
+    /// A value of an enumerated attribute.
+    Value,
+    Template,
+    Path,
+    Variable,
+    Module,
+}
+
+impl TmplCompletionKind {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Tag => "tag",
+            Self::Attribute => "attribute",
+            Self::Event => "event",
+            Self::Value => "value",
+            Self::Template => "template",
+            Self::Path => "path",
+            Self::Variable => "variable",
+            Self::Module => "module",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TmplCompletionItem {
+    pub label: String,
+    pub kind: TmplCompletionKind,
+    pub detail: Option<String>,
+}
+
+/// The completion candidates at a cursor.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TmplCompletion {
+    /// The byte offset of the typed prefix, which the chosen candidate replaces up to the cursor.
+    pub start: usize,
+    pub items: Vec<TmplCompletionItem>,
+}
+
+impl TmplCompletion {
+    pub fn to_json(&self) -> Value {
+        let items: Vec<Value> = self
+            .items
+            .iter()
+            .map(|x| json!({ "label": x.label, "kind": x.kind.name(), "detail": x.detail }))
+            .collect();
+        json!({ "start": self.start, "items": items })
+    }
+
+    fn add(&mut self, prefix: &str, label: &str, kind: TmplCompletionKind, detail: Option<&str>) {
+        if label.starts_with(prefix) && !self.items.iter().any(|x| x.label == label) {
+            self.items.push(TmplCompletionItem {
+                label: label.to_string(),
+                kind,
+                detail: detail.map(|x| x.to_string()),
+            });
+        }
+    }
+}
+
+const VIRTUAL_TAGS: &[&str] = &["block", "template", "import", "include", "wxs", "slot"];
+const DIRECTIVES: &[&str] = &[
+    "wx:if",
+    "wx:elif",
+    "wx:else",
+    "wx:for",
+    "wx:for-item",
+    "wx:for-index",
+    "wx:key",
+];
+
+// a start tag with the attributes before the cursor
+struct OpenTag<'a> {
+    name: &'a str,
+    attrs: Vec<(&'a str, &'a str)>,
+}
+
+impl<'a> OpenTag<'a> {
+    fn attr(&self, name: &str) -> Option<&'a str> {
+        self.attrs.iter().find(|x| x.0 == name).map(|x| x.1)
+    }
+}
+
+enum Cursor<'a> {
+    Text,
+    TagName,
+    AttrName(OpenTag<'a>),
+    AttrValue(OpenTag<'a>, &'a str),
+    /// In comments or end tags.
+    Other,
+}
+
+// the cursor context at the end of `s`, with the unclosed tags and the start of the current token
+//
+// The source before the cursor is scanned lexically, since it is often incomplete while typing.
+fn scan(s: &str) -> (Vec<OpenTag<'_>>, Cursor<'_>, usize) {
+    let b = s.as_bytes();
+    let len = b.len();
+    let is_name_char = |c: u8| c.is_ascii_alphanumeric() || c == b'-' || c == b'_' || c == b':';
+    let mut open: Vec<OpenTag> = vec![];
+    let mut text_start = 0;
+    let mut i = 0;
+    while i < len {
+        if b[i..].starts_with(b"<!--") {
+            match s[i..].find("-->") {
+                Some(x) => i += x + 3,
+                None => return (open, Cursor::Other, i),
+            }
+            text_start = i;
+            continue;
+        }
+        if b[i..].starts_with(b"</") {
+            match s[i..].find('>') {
+                Some(x) => {
+                    let name = s[i + 2..i + x].trim();
+                    if let Some(pos) = open.iter().rposition(|t| t.name == name) {
+                        open.truncate(pos);
+                    }
+                    i += x + 1;
+                }
+                None => return (open, Cursor::Other, i),
+            }
+            text_start = i;
+            continue;
+        }
+        if b[i] != b'<' || (i + 1 < len && !is_name_char(b[i + 1])) {
+            i += 1;
+            continue;
+        }
+        let mut j = i + 1;
+        while j < len && is_name_char(b[j]) {
+            j += 1;
+        }
+        if j == len {
+            return (open, Cursor::TagName, i + 1);
+        }
+        let mut tag = OpenTag {
+            name: &s[i + 1..j],
+            attrs: vec![],
+        };
+        loop {
+            while j < len && b[j].is_ascii_whitespace() {
+                j += 1;
+            }
+            if j == len {
+                return (open, Cursor::AttrName(tag), j);
+            }
+            if b[j] == b'>' {
+                open.push(tag);
+                i = j + 1;
+                break;
+            }
+            if b[j..].starts_with(b"/>") {
+                i = j + 2;
+                break;
+            }
+            let name_start = j;
+            while j < len && !b[j].is_ascii_whitespace() && b[j] != b'=' && b[j] != b'>' {
+                if b[j..].starts_with(b"/>") {
+                    break;
+                }
+                j += 1;
+            }
+            if j == len {
+                return (open, Cursor::AttrName(tag), name_start);
+            }
+            // skip a stray `/`
+            if j == name_start {
+                j += 1;
+                continue;
+            }
+            let name = &s[name_start..j];
+            let mut k = j;
+            while k < len && b[k].is_ascii_whitespace() {
+                k += 1;
+            }
+            if k == len || b[k] != b'=' {
+                tag.attrs.push((name, ""));
+                continue;
+            }
+            j = k + 1;
+            while j < len && b[j].is_ascii_whitespace() {
+                j += 1;
+            }
+            if j == len {
+                return (open, Cursor::AttrValue(tag, name), j);
+            }
+            if b[j] == b'"' || b[j] == b'\'' {
+                let value_start = j + 1;
+                match s[value_start..].find(b[j] as char) {
+                    Some(x) => {
+                        tag.attrs.push((name, &s[value_start..value_start + x]));
+                        j = value_start + x + 1;
+                    }
+                    None => return (open, Cursor::AttrValue(tag, name), value_start),
+                }
+            } else {
+                let value_start = j;
+                while j < len && !b[j].is_ascii_whitespace() && b[j] != b'>' {
+                    j += 1;
+                }
+                if j == len {
+                    return (open, Cursor::AttrValue(tag, name), value_start);
+                }
+                tag.attrs.push((name, &s[value_start..j]));
+            }
+        }
+        text_start = i;
+    }
+    (open, Cursor::Text, text_start)
+}
+
+// the start of the expression if the cursor is in an unclosed `{{`
+fn expr_start(s: &str, start: usize) -> Option<usize> {
+    let seg = &s[start..];
+    let pos = seg.rfind("{{")?;
+    if seg[pos..].contains("}}") {
+        None
+    } else {
+        Some(start + pos + 2)
+    }
+}
+
+struct Completer<'a> {
+    group: Option<&'a TmplGroup>,
+    tree: &'a TmplTree,
+    ret: TmplCompletion,
+}
+
+impl<'a> Completer<'a> {
+    fn config(&self) -> Option<&'a TmplComponentConfig> {
+        self.group?.component_configs.get(&self.tree.path)
+    }
+
+    fn tags(&mut self, prefix: &str) {
+        for component in builtin_components() {
+            let detail = if component.deprecated {
+                "builtin, deprecated"
+            } else {
+                "builtin"
+            };
+            self.ret.add(
+                prefix,
+                component.name,
+                TmplCompletionKind::Tag,
+                Some(detail),
+            );
+        }
+        for name in VIRTUAL_TAGS {
+            self.ret.add(prefix, name, TmplCompletionKind::Tag, None);
+        }
+        let group = match self.group {
+            Some(x) => x,
+            None => return,
+        };
+        let configs = [self.config(), group.component_configs.get("app")];
+        for config in configs.into_iter().flatten() {
+            let mut names: Vec<(&String, &String)> = config.using_components().iter().collect();
+            names.sort();
+            for (name, path) in names {
+                self.ret
+                    .add(prefix, name, TmplCompletionKind::Tag, Some(path));
+            }
+        }
+        if let Some(config) = self.config() {
+            let mut names: Vec<&String> = config.component_generics().keys().collect();
+            names.sort();
+            for name in names {
+                self.ret
+                    .add(prefix, name, TmplCompletionKind::Tag, Some("generic"));
+            }
+        }
+    }
+
+    fn attrs(&mut self, tag: &OpenTag, prefix: &str) {
+        let start = self.ret.items.len();
+        let attr = TmplCompletionKind::Attribute;
+        for name in DIRECTIVES {
+            self.ret.add(prefix, name, attr, Some("directive"));
+        }
+        let special: &[&str] = match tag.name {
+            "template" => &["name", "is", "data"],
+            "import" | "include" => &["src"],
+            "wxs" => &["module", "src"],
+            "slot" => &["name"],
+            "block" => &[],
+            _ => &[],
+        };
+        for name in special {
+            self.ret.add(prefix, name, attr, None);
+        }
+        if VIRTUAL_TAGS.contains(&tag.name) {
+            self.remove_present(tag, start);
+            return;
+        }
+        for name in ["id", "class", "style", "slot"].iter().chain(COMMON_ATTRS) {
+            self.ret.add(prefix, name, attr, None);
+        }
+        let mut events: Vec<&str> = COMMON_EVENTS.to_vec();
+        let custom = self.group.and_then(|group| {
+            let config = TmplComponentConfig::new();
+            let config = self.config().unwrap_or(&config);
+            let path = resolve_tag(group, self.tree, config, tag.name)?;
+            group.component_properties.get(&path)
+        });
+        if let Some(properties) = custom {
+            let mut names: Vec<&String> = properties.properties().keys().collect();
+            names.sort();
+            for name in names.iter() {
+                self.ret
+                    .add(prefix, &camel_to_dash(name), attr, Some("property"));
+            }
+            for name in names.iter() {
+                let label = format!("model:{}", camel_to_dash(name));
+                self.ret.add(prefix, &label, attr, Some("property"));
+            }
+            for name in properties.external_classes() {
+                self.ret.add(prefix, name, attr, Some("external class"));
+            }
+        } else if let Some(component) = builtin_component(tag.name) {
+            for x in component.attrs.iter() {
+                let detail = if x.deprecated {
+                    "deprecated"
+                } else {
+                    "builtin"
+                };
+                self.ret.add(prefix, x.name, attr, Some(detail));
+            }
+            if component.attr("value").is_some() {
+                self.ret.add(prefix, "model:value", attr, Some("builtin"));
+            }
+            events.extend(component.events);
+        }
+        for bind in ["bind", "catch"] {
+            for event in events.iter() {
+                let label = format!("{}:{}", bind, event);
+                self.ret
+                    .add(prefix, &label, TmplCompletionKind::Event, None);
+            }
+        }
+        self.remove_present(tag, start);
+    }
+
+    // skip the attributes already written in the tag
+    fn remove_present(&mut self, tag: &OpenTag, start: usize) {
+        let mut index = 0;
+        self.ret.items.retain(|x| {
+            index += 1;
+            index <= start || tag.attr(&x.label).is_none()
+        });
+    }
+
+    fn values(&mut self, tag: &OpenTag, name: &str, prefix: &str) {
+        let tree = self.tree;
+        match (tag.name, name) {
+            ("template", "is") => {
+                let mut names: Vec<(&String, Option<&str>)> =
+                    tree.sub_templates.keys().map(|x| (x, None)).collect();
+                names.sort();
+                for import in tree.imports.iter() {
+                    let target = path::resolve(&tree.path, import);
+                    if let Some(imported) = self.group.and_then(|x| x.get_tree(&target)) {
+                        let mut list: Vec<&String> = imported.sub_templates.keys().collect();
+                        list.sort();
+                        names.extend(list.into_iter().map(|x| (x, Some(import.as_str()))));
+                    }
+                }
+                for (name, import) in names {
+                    self.ret
+                        .add(prefix, name, TmplCompletionKind::Template, import);
+                }
+            }
+            ("import", "src") | ("include", "src") | ("wxs", "src") => {
+                let group = match self.group {
+                    Some(x) => x,
+                    None => return,
+                };
+                let mut paths: Vec<&String> = if tag.name == "wxs" {
+                    group.scripts.keys().collect()
+                } else {
+                    group.trees.keys().filter(|x| **x != tree.path).collect()
+                };
+                paths.sort();
+                for target in paths {
+                    let label = path::relative(&tree.path, target);
+                    self.ret
+                        .add(prefix, &label, TmplCompletionKind::Path, Some(target));
+                }
+            }
+            _ => {
+                let values = builtin_component(tag.name)
+                    .and_then(|x| x.attr(name))
+                    .map(|x| x.values)
+                    .unwrap_or_default();
+                for value in values {
+                    self.ret.add(prefix, value, TmplCompletionKind::Value, None);
+                }
+            }
+        }
+    }
+
+    fn identifiers(&mut self, open: &[OpenTag], current: Option<&OpenTag>, prefix: &str) {
+        let tags = open.iter().chain(current);
+        let scopes: Vec<&OpenTag> = tags.filter(|x| x.attr("wx:for").is_some()).collect();
+        for tag in scopes.iter().rev() {
+            let item = tag.attr("wx:for-item").unwrap_or("item");
+            let index = tag.attr("wx:for-index").unwrap_or("index");
+            self.ret.add(
+                prefix,
+                item,
+                TmplCompletionKind::Variable,
+                Some("wx:for-item"),
+            );
+            self.ret.add(
+                prefix,
+                index,
+                TmplCompletionKind::Variable,
+                Some("wx:for-index"),
+            );
+        }
+        for script in self.tree.scripts.iter() {
+            let name = match script {
+                TmplScript::Inline { module_name, .. } => module_name,
+                TmplScript::GlobalRef { module_name, .. } => module_name,
+            };
+            self.ret
+                .add(prefix, name, TmplCompletionKind::Module, Some("wxs"));
+        }
+        // the fields of the data, as read elsewhere in the template or the sub template
+        let sub_template = open
+            .iter()
+            .find(|x| x.name == "template")
+            .and_then(|x| x.attr("name"));
+        let mut paths = collect_data_paths(self.tree);
+        let paths = match sub_template {
+            Some(name) => paths.sub_templates.remove(name).unwrap_or_default(),
+            None => paths.paths,
+        };
+        let mut seen = HashSet::new();
+        for p in paths.iter() {
+            let name = p.split(['.', '[']).next().unwrap_or("");
+            if !name.is_empty() && seen.insert(name) {
+                self.ret
+                    .add(prefix, name, TmplCompletionKind::Variable, Some("data"));
+            }
+        }
+    }
+}
+
+// the identifier before the cursor, or `None` after a `.`
+fn ident_prefix(s: &str) -> Option<&str> {
+    let start = s
+        .rfind(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '$'))
+        .map(|x| x + 1)
+        .unwrap_or(0);
+    if s[..start].trim_end().ends_with('.') {
+        None
+    } else {
+        Some(&s[start..])
+    }
+}
+
+pub(crate) fn complete_in(
+    group: Option<&TmplGroup>,
+    tree: &TmplTree,
+    source: &str,
+    offset: usize,
+) -> TmplCompletion {
+    let mut offset = offset.min(source.len());
+    while !source.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    let s = &source[..offset];
+    let mut completer = Completer {
+        group,
+        tree,
+        ret: TmplCompletion {
+            start: offset,
+            items: vec![],
+        },
+    };
+    let (open, cursor, start) = scan(s);
+    let (in_expr, current) = match &cursor {
+        Cursor::Text => (expr_start(s, start).is_some(), None),
+        Cursor::AttrValue(tag, _) => (expr_start(s, start).is_some(), Some(tag)),
+        _ => (false, None),
+    };
+    if in_expr {
+        if let Some(prefix) = ident_prefix(s) {
+            completer.ret.start = offset - prefix.len();
+            completer.identifiers(&open, current, prefix);
+        }
+        return completer.ret;
+    }
+    completer.ret.start = start;
+    let prefix = &s[start..];
+    match &cursor {
+        Cursor::TagName => completer.tags(prefix),
+        Cursor::AttrName(tag) => completer.attrs(tag, prefix),
+        Cursor::AttrValue(tag, name) => completer.values(tag, name, prefix),
+        Cursor::Text | Cursor::Other => {
+            completer.ret.start = offset;
+        }
+    }
+    completer.ret
+}
+
+/// Get the completion candidates at the byte `offset` of the template `source`.
+///
+/// The `tree` is the last parsed version of the source, which provides the sub templates,
+/// the wxs modules and the data fields, so the source itself does not need to be valid.
+/// Use `TmplGroup::complete` to also get the imported templates, the registered components and the paths.
+pub fn complete(tree: &TmplTree, source: &str, offset: usize) -> TmplCompletion {
+    complete_in(None, tree, source, offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels(ret: &TmplCompletion) -> Vec<&str> {
+        ret.items.iter().map(|x| x.label.as_str()).collect()
+    }
+
+    #[test]
+    fn it_completes_by_context() {
+        let mut group = TmplGroup::new();
+        group
+            .add_tmpl("comps/card", r#"<template name="card-a" />"#)
+            .unwrap();
+        let source = r#"<import src="../comps/card" />
+<wxs module="fmt" src="./fmt.wxs" />
+<template name="local" />
+<view wx:for="{{ list }}" wx:for-item="row">{{ row.a }}{{ r"#;
+        // the tree is parsed from the last valid version
+        let valid = format!("{}</view>", source.strip_suffix("{{ r").unwrap());
+        group.add_tmpl("pages/index", &valid).unwrap();
+        let mut config = TmplComponentConfig::new();
+        config.add_using_component("my-list", "../comps/list");
+        group.set_component_config("pages/index", config);
+        let tree = group.get_tree("pages/index").unwrap();
+
+        let ret = group.complete("pages/index", source, source.len()).unwrap();
+        assert_eq!(ret.start, source.len() - 1);
+        assert_eq!(labels(&ret), vec!["row"]);
+
+        let s = format!("{}}}}}<my", source);
+        let ret = group.complete("pages/index", &s, s.len()).unwrap();
+        assert_eq!(labels(&ret), vec!["my-list"]);
+
+        let s = format!("{}}}}}<image mode=\"aspectF", source);
+        assert_eq!(
+            labels(&complete(tree, &s, s.len())),
+            vec!["aspectFit", "aspectFill"]
+        );
+
+        let s = format!("{}}}}}<image src=\"a\" bind:lo", source);
+        assert_eq!(
+            labels(&complete(tree, &s, s.len())),
+            vec!["bind:longpress", "bind:longtap", "bind:load"]
+        );
+        let s = format!("{}}}}}<input wx:i", source);
+        assert_eq!(labels(&complete(tree, &s, s.len())), vec!["wx:if"]);
+
+        let s = format!("{}}}}}<template is=\"", source);
+        let ret = group.complete("pages/index", &s, s.len()).unwrap();
+        assert_eq!(labels(&ret), vec!["local", "card-a"]);
+        let s = format!("{}}}}}<include src=\"", source);
+        let ret = group.complete("pages/index", &s, s.len()).unwrap();
+        assert_eq!(labels(&ret), vec!["../comps/card"]);
+
+        let s = format!("{}}}}}<text class=\"{{{{ ", source);
+        let ret = complete(tree, &s, s.len());
+        assert_eq!(labels(&ret), vec!["row", "index", "fmt", "list"]);
+    }
+}
@@ -0,0 +1,261 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::{
+    builtin_component::builtin_component,
+    component_property::check_component_properties,
+    diagnostic::{TmplDiagnostic, TmplDiagnosticLevel, TmplPosRange},
+    element::TmplVirtualType,
+    path,
+    tree::TmplTree,
+    TmplGroup,
+};
+
+/// The component fields of a page or component `.json` config.
+#[derive(Debug, Clone, Default)]
+pub struct TmplComponentConfig {
+    using_components: HashMap<String, String>,
+    component_generics: HashMap<String, Option<String>>,
+}
+
+impl TmplComponentConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read the `usingComponents` and `componentGenerics` fields of a `.json` config like:
+    ///
+    /// ```json
+    /// {
+    ///     "usingComponents": { "list": "../list/list" },
+    ///     "componentGenerics": { "item": true, "footer": { "default": "../footer/footer" } }
+    /// }
+    /// ```
+    pub fn from_json(s: &str) -> Result<Self, String> {
+        let value: Value = serde_json::from_str(s).map_err(|e| e.to_string())?;
+        let mut ret = Self::new();
+        if let Some(components) = value.get("usingComponents") {
+            let components = components
+                .as_object()
+                .ok_or_else(|| "`usingComponents` should be an object".to_string())?;
+            for (name, path) in components.iter() {
+                let path = path.as_str().ok_or_else(|| {
+                    format!("the path of component `{}` should be a string", name)
+                })?;
+                ret.add_using_component(name, path);
+            }
+        }
+        if let Some(generics) = value.get("componentGenerics") {
+            let generics = generics
+                .as_object()
+                .ok_or_else(|| "`componentGenerics` should be an object".to_string())?;
+            for (name, generic) in generics.iter() {
+                let default = generic.get("default").and_then(|x| x.as_str());
+                ret.add_component_generic(name, default);
+            }
+        }
+        Ok(ret)
+    }
+
+    pub fn add_using_component(&mut self, name: &str, path: &str) {
+        self.using_components
+            .insert(name.to_string(), path.to_string());
+    }
+
+    pub fn add_component_generic(&mut self, name: &str, default: Option<&str>) {
+        self.component_generics
+            .insert(name.to_string(), default.map(|x| x.to_string()));
+    }
+
+    /// The registered components, from tag names to component paths.
+    pub fn using_components(&self) -> &HashMap<String, String> {
+        &self.using_components
+    }
+
+    /// The declared generics, with the default component paths.
+    pub fn component_generics(&self) -> &HashMap<String, Option<String>> {
+        &self.component_generics
+    }
+}
+
+// resolve a component path to a template path in the group,
+// or `None` for the npm and plugin components
+fn resolve_component(base: &str, component_path: &str) -> Option<String> {
+    if component_path.starts_with('/') || component_path.starts_with('.') {
+        Some(path::resolve(base, component_path))
+    } else {
+        None
+    }
+}
+
+// the path of the component registered for the tag, in the config or the `app` config
+pub(crate) fn resolve_tag(
+    group: &TmplGroup,
+    tree: &TmplTree,
+    config: &TmplComponentConfig,
+    tag_name: &str,
+) -> Option<String> {
+    let (base, component_path) = match config.using_components.get(tag_name) {
+        Some(x) => (tree.path.as_str(), x),
+        None => {
+            let app_config = group.component_configs.get("app")?;
+            ("app", app_config.using_components.get(tag_name)?)
+        }
+    };
+    resolve_component(base, component_path)
+}
+
+// check the component tags and the generics in the template against its config
+pub(crate) fn check_components(
+    group: &TmplGroup,
+    tree: &TmplTree,
+    config: &TmplComponentConfig,
+) -> Vec<TmplDiagnostic> {
+    let app_config = group.component_configs.get("app");
+    let registered = |name: &str| {
+        config.using_components.contains_key(name)
+            || app_config.is_some_and(|c| c.using_components.contains_key(name))
+    };
+    let mut ret = vec![];
+    let mut report = |level, code: &str, message: String, pos: TmplPosRange| {
+        ret.push(TmplDiagnostic::new(
+            level, code, message, &tree.path, pos.0, pos.1,
+        ));
+    };
+    let mut names: Vec<&String> = tree.sub_templates.keys().collect();
+    names.sort();
+    let mut roots = vec![&tree.root];
+    roots.extend(names.into_iter().map(|x| &tree.sub_templates[x]));
+    for root in roots {
+        for elem in root.descendants() {
+            if !matches!(elem.virtual_type, TmplVirtualType::None) {
+                continue;
+            }
+            let tag_name = elem.tag_name.as_str();
+            if !registered(tag_name)
+                && builtin_component(tag_name).is_none()
+                && !config.component_generics.contains_key(tag_name)
+            {
+                let message = format!(
+                    "`<{}>` is neither a builtin component nor registered in usingComponents",
+                    tag_name
+                );
+                report(
+                    TmplDiagnosticLevel::Error,
+                    "unknown-component",
+                    message,
+                    (elem.start_pos, elem.end_pos),
+                );
+            }
+            let generics = match &elem.generics {
+                Some(x) => x,
+                None => continue,
+            };
+            let target_config = resolve_tag(group, tree, config, tag_name)
+                .and_then(|x| group.component_configs.get(&x));
+            let mut list: Vec<(&String, &String)> = generics.iter().collect();
+            list.sort();
+            for (name, value) in list {
+                if let Some(target_config) = target_config {
+                    if !target_config.component_generics.contains_key(name) {
+                        let message =
+                            format!("`<{}>` does not declare the generic `{}`", tag_name, name);
+                        report(
+                            TmplDiagnosticLevel::Error,
+                            "unknown-generic",
+                            message,
+                            (elem.start_pos, elem.end_pos),
+                        );
+                    }
+                }
+                if !registered(value) && !config.component_generics.contains_key(value) {
+                    let message = format!(
+                        "`generic:{}` refers to `{}`, which is not registered in usingComponents",
+                        name, value
+                    );
+                    report(
+                        TmplDiagnosticLevel::Error,
+                        "unknown-generic",
+                        message,
+                        (elem.start_pos, elem.end_pos),
+                    );
+                }
+            }
+        }
+    }
+    let mut generics: Vec<(&String, &Option<String>)> = config.component_generics.iter().collect();
+    generics.sort();
+    for (name, default) in generics {
+        let default = match default {
+            Some(x) => x,
+            None => continue,
+        };
+        if let Some(target) = resolve_component(&tree.path, default) {
+            if !group.trees.contains_key(&target) && !group.component_configs.contains_key(&target)
+            {
+                let message = format!(
+                    "the default component `{}` of the generic `{}` is not found",
+                    default, name
+                );
+                report(
+                    TmplDiagnosticLevel::Warning,
+                    "unresolved-generic-default",
+                    message,
+                    ((1, 1), (1, 1)),
+                );
+            }
+        }
+    }
+    ret.extend(check_component_properties(group, tree, config));
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_checks_components() {
+        let mut group = TmplGroup::new();
+        group
+            .add_tmpl(
+                "pages/index",
+                r#"<view><list generic:item="card" /><unknown-tag /><list generic:foo="missing" /><nav-bar /></view>"#,
+            )
+            .unwrap();
+        group
+            .add_tmpl("comps/list", "<view><item /></view>")
+            .unwrap();
+        group.set_component_config(
+            "pages/index",
+            TmplComponentConfig::from_json(
+                r#"{ "usingComponents": { "list": "../comps/list", "card": "/comps/card" } }"#,
+            )
+            .unwrap(),
+        );
+        group.set_component_config(
+            "comps/list",
+            TmplComponentConfig::from_json(
+                r#"{ "componentGenerics": { "item": true, "footer": { "default": "./footer" } } }"#,
+            )
+            .unwrap(),
+        );
+        let mut app = TmplComponentConfig::new();
+        app.add_using_component("nav-bar", "/comps/nav-bar");
+        group.set_component_config("app", app);
+
+        let diagnostics = group.check_components("pages/index").unwrap();
+        let codes: Vec<&str> = diagnostics.iter().map(|x| x.code.as_str()).collect();
+        assert_eq!(
+            codes,
+            ["unknown-component", "unknown-generic", "unknown-generic"]
+        );
+        assert_eq!(diagnostics[0].start_pos, (1, 35));
+
+        let diagnostics = group.check_components("comps/list").unwrap();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, "unresolved-generic-default");
+        assert!(group.check_components("comps/card").is_none());
+    }
+}
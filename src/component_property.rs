@@ -0,0 +1,360 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::{
+    builtin_component::COMMON_ATTRS,
+    component_config::{resolve_tag, TmplComponentConfig},
+    diagnostic::{TmplDiagnostic, TmplDiagnosticLevel},
+    display_debug::text_segments,
+    element::{TmplAttrKind, TmplAttrValue, TmplElement, TmplVirtualType},
+    expr::TmplExpr,
+    tree::TmplTree,
+    utils::dash_to_camel,
+    TmplGroup,
+};
+
+/// The type of a component property, as the `type` in the component definition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TmplPropertyType {
+    String,
+    Number,
+    Boolean,
+    Object,
+    Array,
+}
+
+impl TmplPropertyType {
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "String" => Some(Self::String),
+            "Number" => Some(Self::Number),
+            "Boolean" => Some(Self::Boolean),
+            "Object" => Some(Self::Object),
+            "Array" => Some(Self::Array),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::String => "String",
+            Self::Number => "Number",
+            Self::Boolean => "Boolean",
+            Self::Object => "Object",
+            Self::Array => "Array",
+        }
+    }
+}
+
+/// A component property definition.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TmplProperty {
+    /// The accepted types, or empty for any types.
+    pub types: Vec<TmplPropertyType>,
+    pub default: Option<Value>,
+}
+
+/// The property definitions of a component.
+#[derive(Debug, Clone, Default)]
+pub struct TmplComponentProperties {
+    properties: HashMap<String, TmplProperty>,
+    external_classes: Vec<String>,
+}
+
+impl TmplComponentProperties {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read the definitions in the same shape as the component constructor, like:
+    ///
+    /// ```json
+    /// {
+    ///     "properties": {
+    ///         "title": "String",
+    ///         "count": { "type": "Number", "value": 0, "optionalTypes": ["String"] },
+    ///         "extra": null
+    ///     },
+    ///     "externalClasses": ["custom-class"]
+    /// }
+    /// ```
+    pub fn from_json(s: &str) -> Result<Self, String> {
+        let value: Value = serde_json::from_str(s).map_err(|e| e.to_string())?;
+        let mut ret = Self::new();
+        let parse_type = |name: &str, x: &Value| match x {
+            Value::Null => Ok(None),
+            Value::String(x) => TmplPropertyType::parse(x)
+                .map(Some)
+                .ok_or_else(|| format!("invalid type `{}` of property `{}`", x, name)),
+            _ => Err(format!("invalid type of property `{}`", name)),
+        };
+        if let Some(properties) = value.get("properties") {
+            let properties = properties
+                .as_object()
+                .ok_or_else(|| "`properties` should be an object".to_string())?;
+            for (name, def) in properties.iter() {
+                let mut property = TmplProperty {
+                    types: vec![],
+                    default: None,
+                };
+                match def {
+                    Value::Object(def) => {
+                        let types = std::iter::once(def.get("type").unwrap_or(&Value::Null)).chain(
+                            def.get("optionalTypes")
+                                .and_then(|x| x.as_array())
+                                .into_iter()
+                                .flatten(),
+                        );
+                        for ty in types {
+                            match parse_type(name, ty)? {
+                                Some(ty) => property.types.push(ty),
+                                // `null` accepts any types
+                                None => {
+                                    property.types.clear();
+                                    break;
+                                }
+                            }
+                        }
+                        property.default = def.get("value").cloned();
+                    }
+                    x => property.types.extend(parse_type(name, x)?),
+                }
+                ret.add_property(name, property);
+            }
+        }
+        if let Some(classes) = value.get("externalClasses") {
+            let classes = classes
+                .as_array()
+                .ok_or_else(|| "`externalClasses` should be an array".to_string())?;
+            for class in classes.iter() {
+                let class = class
+                    .as_str()
+                    .ok_or_else(|| "the external classes should be strings".to_string())?;
+                ret.add_external_class(class);
+            }
+        }
+        Ok(ret)
+    }
+
+    pub fn add_property(&mut self, name: &str, property: TmplProperty) {
+        self.properties.insert(name.to_string(), property);
+    }
+
+    pub fn add_external_class(&mut self, name: &str) {
+        self.external_classes.push(name.to_string());
+    }
+
+    /// The property definitions keyed by names.
+    pub fn properties(&self) -> &HashMap<String, TmplProperty> {
+        &self.properties
+    }
+
+    pub fn property(&self, name: &str) -> Option<&TmplProperty> {
+        self.properties.get(name)
+    }
+
+    pub fn external_classes(&self) -> &[String] {
+        &self.external_classes
+    }
+}
+
+// the type of a value if it can be told without the data
+fn value_type(value: &TmplAttrValue) -> Option<Result<TmplPropertyType, &str>> {
+    let expr = match value {
+        TmplAttrValue::Static(s) => return Some(Err(s)),
+        TmplAttrValue::Dynamic { expr, .. } => expr,
+    };
+    if text_segments(expr).is_some() {
+        return Some(Ok(TmplPropertyType::String));
+    }
+    let ty = match &**expr {
+        TmplExpr::LitStr(_) => TmplPropertyType::String,
+        TmplExpr::LitInt(_) | TmplExpr::LitFloat(_) => TmplPropertyType::Number,
+        TmplExpr::LitBool(_) => TmplPropertyType::Boolean,
+        TmplExpr::LitObj(_) => TmplPropertyType::Object,
+        TmplExpr::LitArr(_) => TmplPropertyType::Array,
+        _ => return None,
+    };
+    Some(Ok(ty))
+}
+
+// whether a static string is accepted by the type
+// the strings are converted to numbers for `Number`;
+// for `Boolean`, only `flag` and `flag="true"` are accepted, since any other string is also true,
+// which is likely a mistake such as `flag="false"`
+fn static_matches(ty: TmplPropertyType, s: &str) -> bool {
+    match ty {
+        TmplPropertyType::String => true,
+        TmplPropertyType::Number => s.trim().parse::<f64>().is_ok(),
+        TmplPropertyType::Boolean => s.is_empty() || s == "true",
+        TmplPropertyType::Object | TmplPropertyType::Array => false,
+    }
+}
+
+// whether the expression is a data field which `model:` can write back to
+//
+// Only the top level fields can be written back, so `a.b` and `list[0]` are not accepted.
+fn is_assignable(expr: &TmplExpr) -> bool {
+    matches!(expr, TmplExpr::Ident(_))
+}
+
+struct PropertyChecker<'a> {
+    path: &'a str,
+    diagnostics: Vec<TmplDiagnostic>,
+}
+
+impl<'a> PropertyChecker<'a> {
+    fn report(&mut self, code: &str, message: String, elem: &TmplElement) {
+        self.diagnostics.push(TmplDiagnostic::new(
+            TmplDiagnosticLevel::Error,
+            code,
+            message,
+            self.path,
+            elem.start_pos,
+            elem.end_pos,
+        ));
+    }
+
+    fn check_element(&mut self, elem: &TmplElement, properties: &TmplComponentProperties) {
+        for attr in elem.attrs.iter() {
+            let (prefix, name) = match &attr.kind {
+                TmplAttrKind::PropertyOrExternalClass { name } => ("", name),
+                TmplAttrKind::ModelProperty { name } => ("model:", name),
+                TmplAttrKind::ChangeProperty { name } => ("change:", name),
+                _ => continue,
+            };
+            if prefix.is_empty()
+                && (properties.external_classes.contains(name)
+                    || COMMON_ATTRS.contains(&name.as_str()))
+            {
+                continue;
+            }
+            let property = match properties.property(&dash_to_camel(name)) {
+                Some(x) => x,
+                None => {
+                    let message = format!(
+                        "`<{}>` has no property `{}`",
+                        elem.tag_name,
+                        dash_to_camel(name)
+                    );
+                    self.report("unknown-property", message, elem);
+                    continue;
+                }
+            };
+            if let TmplAttrKind::ModelProperty { .. } = &attr.kind {
+                let assignable = match &attr.value {
+                    TmplAttrValue::Static(_) => false,
+                    TmplAttrValue::Dynamic { expr, .. } => is_assignable(expr),
+                };
+                if !assignable {
+                    let message = format!("the value of `model:{}` is not a data path", name);
+                    self.report("model-not-assignable", message, elem);
+                }
+            }
+            if property.types.is_empty() || !prefix.is_empty() && prefix != "model:" {
+                continue;
+            }
+            let matched = match value_type(&attr.value) {
+                None => true,
+                Some(Ok(ty)) => property.types.contains(&ty),
+                Some(Err(s)) => property.types.iter().any(|ty| static_matches(*ty, s)),
+            };
+            if !matched {
+                let types: Vec<&str> = property.types.iter().map(|x| x.name()).collect();
+                let message = format!(
+                    "the value of `{}{}` does not match the property type {}",
+                    prefix,
+                    name,
+                    types.join(" | ")
+                );
+                self.report("property-type-mismatch", message, elem);
+            }
+        }
+    }
+}
+
+// check the attributes of the components which have property definitions
+pub(crate) fn check_component_properties(
+    group: &TmplGroup,
+    tree: &TmplTree,
+    config: &TmplComponentConfig,
+) -> Vec<TmplDiagnostic> {
+    let mut checker = PropertyChecker {
+        path: &tree.path,
+        diagnostics: vec![],
+    };
+    let mut names: Vec<&String> = tree.sub_templates.keys().collect();
+    names.sort();
+    let mut roots = vec![&tree.root];
+    roots.extend(names.into_iter().map(|x| &tree.sub_templates[x]));
+    for root in roots {
+        for elem in root.descendants() {
+            if !matches!(elem.virtual_type, TmplVirtualType::None) {
+                continue;
+            }
+            let properties = resolve_tag(group, tree, config, &elem.tag_name)
+                .and_then(|x| group.component_properties.get(&x));
+            if let Some(properties) = properties {
+                checker.check_element(elem, properties);
+            }
+        }
+    }
+    checker.diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_checks_component_properties() {
+        let mut group = TmplGroup::new();
+        group
+            .add_tmpl(
+                "pages/index",
+                r#"<counter count="abc" title="{{ 1 }}" custom-class="x" hidden />
+<counter count="{{ 2 }}" model:title="{{ list[0].name }}" flag="false" />
+<counter wx:for="{{ list }}" model:title="{{ item.name }}" unknown-prop="1" />"#,
+            )
+            .unwrap();
+        group.set_component_config(
+            "pages/index",
+            TmplComponentConfig::from_json(
+                r#"{ "usingComponents": { "counter": "/comps/counter" } }"#,
+            )
+            .unwrap(),
+        );
+        group.set_component_properties(
+            "comps/counter",
+            TmplComponentProperties::from_json(
+                r#"{
+                    "properties": {
+                        "count": { "type": "Number", "value": 0 },
+                        "title": "String",
+                        "flag": { "type": "Boolean" },
+                        "unknownProp2": null
+                    },
+                    "externalClasses": ["custom-class"]
+                }"#,
+            )
+            .unwrap(),
+        );
+        let diagnostics = group.check_components("pages/index").unwrap();
+        let list: Vec<(&str, usize)> = diagnostics
+            .iter()
+            .map(|x| (x.code.as_str(), x.start_pos.0))
+            .collect();
+        assert_eq!(
+            list,
+            [
+                ("property-type-mismatch", 1),
+                ("property-type-mismatch", 1),
+                ("model-not-assignable", 2),
+                ("property-type-mismatch", 2),
+                ("model-not-assignable", 3),
+                ("unknown-property", 3),
+            ]
+        );
+    }
+}
@@ -2,6 +2,7 @@ use std::collections::HashMap;
 
 use crate::{
     binding_map::BindingMapCollector,
+    diagnostic::{TmplDiagnostic, TmplDiagnosticLevel, TmplPosRange},
     element::{
         TmplAttr, TmplAttrKind, TmplAttrValue, TmplElement, TmplNode, TmplScript, TmplTextNode,
         TmplVirtualType,
@@ -13,31 +14,64 @@ use crate::{
 
 pub(crate) fn convert_directives(tree: &mut TmplTree) {
     let TmplTree {
-        path: _,
+        path,
         root,
         imports,
+        import_positions,
         includes,
         sub_templates,
         binding_map_collector: _,
         scripts,
+        comments: _,
+        whitespace: _,
+        diagnostics,
     } = tree;
 
-    convert_nodes_directives(root, imports, includes, sub_templates, scripts);
+    let mut ctx = ConvertContext {
+        path,
+        imports,
+        import_positions,
+        includes,
+        sub_templates,
+        scripts,
+        diagnostics,
+    };
+    convert_nodes_directives(root, &mut ctx);
 }
 
-fn convert_nodes_directives(
-    parent: &mut TmplElement,
-    imports: &mut Vec<String>,
-    includes: &mut Vec<String>,
-    sub_templates: &mut HashMap<String, TmplElement>,
-    scripts: &mut Vec<TmplScript>,
-) {
+struct ConvertContext<'a> {
+    path: &'a str,
+    imports: &'a mut Vec<String>,
+    import_positions: &'a mut Vec<TmplPosRange>,
+    includes: &'a mut Vec<String>,
+    sub_templates: &'a mut HashMap<String, TmplElement>,
+    scripts: &'a mut Vec<TmplScript>,
+    diagnostics: &'a mut Vec<TmplDiagnostic>,
+}
+
+impl<'a> ConvertContext<'a> {
+    fn warn(&mut self, code: &str, message: String, elem: &TmplElement) {
+        self.diagnostics.push(TmplDiagnostic::new(
+            TmplDiagnosticLevel::Warning,
+            code,
+            message,
+            self.path,
+            elem.start_pos,
+            elem.end_pos,
+        ));
+    }
+}
+
+fn convert_nodes_directives(parent: &mut TmplElement, ctx: &mut ConvertContext) {
     let old_children = std::mem::replace(&mut parent.children, vec![]);
     for node in old_children.into_iter() {
         match node {
             TmplNode::TextNode(text_node) => {
                 parent.children.push(TmplNode::TextNode(text_node));
             }
+            TmplNode::Comment(comment) => {
+                parent.children.push(TmplNode::Comment(comment));
+            }
             TmplNode::Element(mut elem) => {
                 let mut inner_depth = 0;
 
@@ -62,7 +96,10 @@ fn convert_nodes_directives(
                                 "for-item" => attr_for_item = Some(attr.value.static_value()),
                                 "for-index" => attr_for_index = Some(attr.value.static_value()),
                                 "key" => attr_key = Some(attr.value.static_value()),
-                                _ => {}
+                                _ => {
+                                    let message = format!("unknown directive `wx:{}`", name);
+                                    ctx.warn("unknown-directive", message, &elem);
+                                }
                             }
                             continue;
                         }
@@ -128,9 +165,10 @@ fn convert_nodes_directives(
                         match path {
                             Some(path) => {
                                 if elem.tag_name.as_str() == "import" {
-                                    imports.push(path);
+                                    ctx.imports.push(path);
+                                    ctx.import_positions.push((elem.start_pos, elem.end_pos));
                                 } else {
-                                    includes.push(path.clone());
+                                    ctx.includes.push(path.clone());
                                     elem.virtual_type = TmplVirtualType::Include { path };
                                     elem.children.clear();
                                     parent.children.push(TmplNode::Element(elem));
@@ -176,14 +214,8 @@ fn convert_nodes_directives(
                                 if target.is_some() || data.is_some() {
                                     // FIXME warn unused attr
                                 }
-                                convert_nodes_directives(
-                                    &mut elem,
-                                    imports,
-                                    includes,
-                                    sub_templates,
-                                    scripts,
-                                );
-                                sub_templates.insert(name, elem);
+                                convert_nodes_directives(&mut elem, ctx);
+                                ctx.sub_templates.insert(name, elem);
                                 continue;
                             }
                             None => {
@@ -193,10 +225,8 @@ fn convert_nodes_directives(
                                             target,
                                             data: match data {
                                                 Some(field) => {
-                                                    if let TmplAttrValue::Dynamic {
-                                                        expr,
-                                                        binding_map_keys,
-                                                    } = field
+                                                    if let TmplAttrValue::Dynamic { expr, meta } =
+                                                        field
                                                     {
                                                         let expr = match *expr {
                                                             TmplExpr::Ident(s) => {
@@ -215,22 +245,20 @@ fn convert_nodes_directives(
                                                         };
                                                         TmplAttrValue::Dynamic {
                                                             expr: Box::new(expr),
-                                                            binding_map_keys,
+                                                            meta,
                                                         }
                                                     } else {
                                                         // FIXME warn must be object data binding
-                                                        TmplAttrValue::Dynamic {
-                                                            expr: Box::new(TmplExpr::LitObj(
-                                                                vec![],
-                                                            )),
-                                                            binding_map_keys: None,
-                                                        }
+                                                        TmplAttrValue::new_dynamic(
+                                                            Box::new(TmplExpr::LitObj(vec![])),
+                                                            ((0, 0), (0, 0)),
+                                                        )
                                                     }
                                                 }
-                                                None => TmplAttrValue::Dynamic {
-                                                    expr: Box::new(TmplExpr::LitObj(vec![])),
-                                                    binding_map_keys: None,
-                                                },
+                                                None => TmplAttrValue::new_dynamic(
+                                                    Box::new(TmplExpr::LitObj(vec![])),
+                                                    ((0, 0), (0, 0)),
+                                                ),
                                             },
                                         }
                                     }
@@ -291,15 +319,19 @@ fn convert_nodes_directives(
                                 None => "",
                                 _ => unreachable!(),
                             };
-                            scripts.push(TmplScript::Inline {
+                            ctx.scripts.push(TmplScript::Inline {
                                 module_name,
                                 content: content.to_string(),
+                                start_pos: elem.start_pos,
+                                end_pos: elem.end_pos,
                             });
                         } else {
                             // FIXME warn unused script content
-                            scripts.push(TmplScript::GlobalRef {
+                            ctx.scripts.push(TmplScript::GlobalRef {
                                 module_name,
                                 rel_path: src,
+                                start_pos: elem.start_pos,
+                                end_pos: elem.end_pos,
                             });
                         }
                         continue;
@@ -316,6 +348,8 @@ fn convert_nodes_directives(
                         elem.virtual_type = virtual_type;
                     } else {
                         let mut p = TmplElement::new("block", virtual_type);
+                        p.start_pos = elem.start_pos;
+                        p.end_pos = elem.end_pos;
                         p.append_element(elem);
                         elem = p;
                         inner_depth += 1;
@@ -323,6 +357,11 @@ fn convert_nodes_directives(
                     elem
                 };
 
+                if attr_for.is_some() && !matches!(attr_if, IfType::None) {
+                    let message = "`wx:for` and `wx:if` on the same element, the condition is evaluated for each item".to_string();
+                    ctx.warn("if-with-for", message, &elem);
+                }
+
                 // handling if
                 match attr_if {
                     IfType::None => {}
@@ -333,22 +372,12 @@ fn convert_nodes_directives(
                     }
                     IfType::Elif(attr_if) => {
                         let virtual_type = TmplVirtualType::Elif { cond: attr_if };
-                        if let Some(last) = parent.children.last_mut() {
-                            if let TmplNode::Element(last) = last {
-                                if let TmplVirtualType::IfGroup = last.virtual_type {
-                                    convert_nodes_directives(
-                                        &mut elem,
-                                        imports,
-                                        includes,
-                                        sub_templates,
-                                        scripts,
-                                    );
-                                    elem = wrap_virtual_elem(elem, virtual_type);
-                                    last.append_element(elem);
-                                    // FIXME here should display a warning if <for> is found
-                                    continue;
-                                }
-                            }
+                        if let Some(last) = last_if_group(parent) {
+                            convert_nodes_directives(&mut elem, ctx);
+                            elem = wrap_virtual_elem(elem, virtual_type);
+                            last.append_element(elem);
+                            // FIXME here should display a warning if <for> is found
+                            continue;
                         }
                         // FIXME here should display a warning if no matching <if> found
                         elem = wrap_virtual_elem(elem, virtual_type);
@@ -356,22 +385,12 @@ fn convert_nodes_directives(
                     }
                     IfType::Else => {
                         let virtual_type = TmplVirtualType::Else;
-                        if let Some(last) = parent.children.last_mut() {
-                            if let TmplNode::Element(last) = last {
-                                if let TmplVirtualType::IfGroup = last.virtual_type {
-                                    convert_nodes_directives(
-                                        &mut elem,
-                                        imports,
-                                        includes,
-                                        sub_templates,
-                                        scripts,
-                                    );
-                                    elem = wrap_virtual_elem(elem, virtual_type);
-                                    last.append_element(elem);
-                                    // FIXME here should display a warning if <for> is found
-                                    continue;
-                                }
-                            }
+                        if let Some(last) = last_if_group(parent) {
+                            convert_nodes_directives(&mut elem, ctx);
+                            elem = wrap_virtual_elem(elem, virtual_type);
+                            last.append_element(elem);
+                            // FIXME here should display a warning if <for> is found
+                            continue;
                         }
                         // FIXME here should display a warning if no matching <if> found
                         elem = wrap_virtual_elem(elem, virtual_type);
@@ -397,10 +416,10 @@ fn convert_nodes_directives(
                 for _ in 0..inner_depth {
                     next = match next.children.first_mut().unwrap() {
                         TmplNode::Element(elem) => elem,
-                        TmplNode::TextNode(_) => unreachable!(),
+                        _ => unreachable!(),
                     }
                 }
-                convert_nodes_directives(next, imports, includes, sub_templates, scripts);
+                convert_nodes_directives(next, ctx);
 
                 // eliminate pure virtual node
                 let is_pure_virtual = if let TmplVirtualType::Pure = elem.virtual_type {
@@ -411,7 +430,7 @@ fn convert_nodes_directives(
                 if is_pure_virtual && elem.slot.is_none() {
                     for child in elem.children.iter_mut() {
                         match child {
-                            TmplNode::TextNode(..) => {}
+                            TmplNode::TextNode(..) | TmplNode::Comment(..) => {}
                             TmplNode::Element(x) => {
                                 x.slot_values = slot_values.clone();
                             }
@@ -427,6 +446,28 @@ fn convert_nodes_directives(
     }
 }
 
+// the `IfGroup` before the comments at the end of the children, which an `Elif` or `Else` is appended to
+//
+// The comments between the branches are moved into the group, so they stay before the next branch.
+fn last_if_group(parent: &mut TmplElement) -> Option<&mut TmplElement> {
+    let index = parent
+        .children
+        .iter()
+        .rposition(|x| !matches!(x, TmplNode::Comment(_)))?;
+    match &parent.children[index] {
+        TmplNode::Element(x) if matches!(x.virtual_type, TmplVirtualType::IfGroup) => {}
+        _ => return None,
+    }
+    let comments: Vec<TmplNode> = parent.children.drain(index + 1..).collect();
+    match &mut parent.children[index] {
+        TmplNode::Element(group) => {
+            group.children.extend(comments);
+            Some(group)
+        }
+        _ => unreachable!(),
+    }
+}
+
 pub(crate) fn prepare_expr_in_tree(tree: &mut TmplTree) {
     let scope_names = tree
         .scripts
@@ -455,11 +496,8 @@ fn prepare_attr_value(
 ) {
     match v {
         TmplAttrValue::Static(_) => {}
-        TmplAttrValue::Dynamic {
-            expr,
-            binding_map_keys,
-        } => {
-            *binding_map_keys = expr.get_binding_map_keys(bmc, scope_names, should_disable);
+        TmplAttrValue::Dynamic { expr, meta, .. } => {
+            meta.binding_map_keys = expr.get_binding_map_keys(bmc, scope_names, should_disable);
         }
     }
 }
@@ -474,13 +512,12 @@ fn prepare_node_expr_in_tree(
         match node {
             TmplNode::TextNode(ref mut text_node) => match text_node {
                 TmplTextNode::Static(_) => {}
-                TmplTextNode::Dynamic {
-                    expr,
-                    binding_map_keys,
-                } => {
-                    *binding_map_keys = expr.get_binding_map_keys(bmc, scope_names, should_disable);
+                TmplTextNode::Dynamic { expr, meta, .. } => {
+                    meta.binding_map_keys =
+                        expr.get_binding_map_keys(bmc, scope_names, should_disable);
                 }
             },
+            TmplNode::Comment(_) => {}
             TmplNode::Element(ref mut elem) => {
                 let should_disable = match &elem.virtual_type {
                     TmplVirtualType::None => should_disable,
@@ -528,14 +565,19 @@ fn prepare_node_expr_in_tree(
                     }
                 }
                 if elem.slot_values.len() > 0 {
-                    let s_len = scope_names.len();
-                    let mut s = scope_names.clone();
+                    let mut s = new_scope_names
+                        .take()
+                        .unwrap_or_else(|| scope_names.clone());
+                    let s_len = s.len();
                     for (index, (_, provide_name)) in elem.slot_values.iter_mut().enumerate() {
                         let new_provide_name = format!("${}", s_len + index);
                         s.push(std::mem::replace(provide_name, new_provide_name));
                     }
                     new_scope_names = Some(s);
                 }
+                if let Some(s) = &new_scope_names {
+                    elem.source_scope_names = s[scope_names.len()..].to_vec();
+                }
                 let scope_names_ref = new_scope_names.as_ref().unwrap_or(scope_names);
                 for attr in elem.attrs.iter_mut() {
                     prepare_attr_value(&mut attr.value, bmc, scope_names_ref, should_disable);
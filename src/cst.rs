@@ -0,0 +1,574 @@
+//! A lossless syntax tree of a template file.
+//!
+//! Unlike `TmplTree`, the nodes keep the byte ranges of their source text,
+//! so that an edit changes only the text it touches,
+//! leaving the whitespace, quoting style, entities and attribute order intact.
+
+use std::ops::Range;
+
+use pest::iterators::{Pair, Pairs};
+
+use crate::{
+    parser::{parse_pairs, TmplParseError},
+    Rule,
+};
+
+/// A lossless syntax tree, parsed with `TmplCst::parse`.
+#[derive(Debug)]
+pub struct TmplCst {
+    source: String,
+    nodes: Vec<CstNode>,
+}
+
+#[derive(Debug)]
+pub enum CstNode {
+    Element(CstElement),
+    /// The raw text, including whitespace and `{{}}`.
+    Text(Range<usize>),
+    /// The whole `<!-- -->`.
+    Comment(Range<usize>),
+}
+
+#[derive(Debug)]
+pub struct CstElement {
+    tag_name: String,
+    name_range: Range<usize>,
+    start_tag: Range<usize>,
+    end_tag: Option<Range<usize>>,
+    end_name_range: Option<Range<usize>>,
+    attrs: Vec<CstAttr>,
+    children: Vec<CstNode>,
+}
+
+#[derive(Debug)]
+pub struct CstAttr {
+    name: String,
+    name_range: Range<usize>,
+    range: Range<usize>,
+    value: Option<CstAttrValue>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CstQuote {
+    Double,
+    Single,
+    None,
+}
+
+#[derive(Debug)]
+pub struct CstAttrValue {
+    quote: CstQuote,
+    /// The range including the quotes.
+    range: Range<usize>,
+    /// The range of the raw text between the quotes.
+    inner_range: Range<usize>,
+}
+
+/// A replacement of a byte range of the source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TmplTextEdit {
+    pub range: Range<usize>,
+    pub new_text: String,
+}
+
+impl TmplCst {
+    pub fn parse(source: &str) -> Result<Self, TmplParseError> {
+        let mut pairs = parse_pairs(source)?;
+        let main_pair = pairs.next().unwrap();
+        let mut segment = main_pair.into_inner().next().unwrap().into_inner();
+        let mut nodes = vec![];
+        parse_segment(&mut nodes, &mut segment);
+        if let Some(pair) = segment.peek() {
+            let span = pair.as_span();
+            return Err(TmplParseError {
+                message: String::from("Unexpected segment"),
+                start_pos: span.start_pos().line_col(),
+                end_pos: span.end_pos().line_col(),
+            });
+        }
+        Ok(Self {
+            source: source.to_string(),
+            nodes,
+        })
+    }
+
+    /// The source text, which is not changed by edits.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn nodes(&self) -> &[CstNode] {
+        &self.nodes
+    }
+
+    /// All elements in depth-first pre-order.
+    pub fn elements(&self) -> Vec<&CstElement> {
+        fn collect<'a>(nodes: &'a [CstNode], ret: &mut Vec<&'a CstElement>) {
+            for node in nodes {
+                if let CstNode::Element(elem) = node {
+                    ret.push(elem);
+                    collect(&elem.children, ret);
+                }
+            }
+        }
+        let mut ret = vec![];
+        collect(&self.nodes, &mut ret);
+        ret
+    }
+
+    /// The element and the attribute whose value contains the byte offset, including the ends of the value.
+    pub fn attr_at(&self, offset: usize) -> Option<(&CstElement, &CstAttr)> {
+        self.elements().into_iter().find_map(|elem| {
+            let attr = elem.attrs.iter().find(|attr| {
+                attr.value
+                    .as_ref()
+                    .is_some_and(|v| v.inner_range.start <= offset && offset <= v.inner_range.end)
+            })?;
+            Some((elem, attr))
+        })
+    }
+
+    /// Get the source text of a range.
+    pub fn text(&self, range: &Range<usize>) -> &str {
+        &self.source[range.clone()]
+    }
+
+    /// Start an edit on this tree.
+    pub fn edit(&self) -> TmplCstEdit<'_> {
+        TmplCstEdit {
+            cst: self,
+            edits: vec![],
+        }
+    }
+}
+
+impl CstElement {
+    pub fn tag_name(&self) -> &str {
+        &self.tag_name
+    }
+
+    /// The range of the start tag, from `<` to `>`.
+    pub fn start_tag(&self) -> Range<usize> {
+        self.start_tag.clone()
+    }
+
+    /// The range of the end tag, which is `None` for self-closing tags.
+    pub fn end_tag(&self) -> Option<Range<usize>> {
+        self.end_tag.clone()
+    }
+
+    pub fn attrs(&self) -> &[CstAttr] {
+        &self.attrs
+    }
+
+    pub fn attr(&self, name: &str) -> Option<&CstAttr> {
+        self.attrs.iter().find(|x| x.name == name)
+    }
+
+    pub fn children(&self) -> &[CstNode] {
+        &self.children
+    }
+}
+
+impl CstAttr {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The range of the whole attribute, from the name to the end of the value.
+    pub fn range(&self) -> Range<usize> {
+        self.range.clone()
+    }
+
+    pub fn value(&self) -> Option<&CstAttrValue> {
+        self.value.as_ref()
+    }
+}
+
+impl CstAttrValue {
+    pub fn quote(&self) -> CstQuote {
+        self.quote
+    }
+
+    pub fn range(&self) -> Range<usize> {
+        self.range.clone()
+    }
+
+    /// The range of the raw value without quotes.
+    pub fn inner_range(&self) -> Range<usize> {
+        self.inner_range.clone()
+    }
+}
+
+/// A set of edits on a `TmplCst`, applied with `TmplCstEdit::apply`.
+///
+/// The edits are collected as text replacements,
+/// so the edited nodes must be from the same tree and must not overlap.
+pub struct TmplCstEdit<'a> {
+    cst: &'a TmplCst,
+    edits: Vec<TmplTextEdit>,
+}
+
+impl<'a> TmplCstEdit<'a> {
+    fn replace(&mut self, range: Range<usize>, new_text: String) {
+        self.edits.push(TmplTextEdit { range, new_text });
+    }
+
+    /// Set the raw value of an attribute.
+    ///
+    /// The quote style is kept unless the value contains the quote character,
+    /// in which case the other quote is used.
+    /// If the value contains both quotes, the quote outside `{{ }}` is escaped as `&quot;` or `&#39;`,
+    /// and it is an error if both quotes are used in the expressions.
+    /// An unquoted value is quoted with `"` if the new value is empty or contains whitespace or quotes.
+    pub fn set_attr_value(&mut self, attr: &CstAttr, value: &str) -> Result<(), String> {
+        let quote = attr
+            .value
+            .as_ref()
+            .map(|x| x.quote)
+            .unwrap_or(CstQuote::Double);
+        let (quote, inner) = quote_attr_value(value, quote)?;
+        match &attr.value {
+            Some(v) if v.quote == quote => {
+                self.replace(v.inner_range.clone(), inner);
+            }
+            Some(v) => {
+                self.replace(v.range.clone(), quoted(quote, &inner));
+            }
+            None => {
+                let end = attr.name_range.end;
+                self.replace(end..end, format!("={}", quoted(quote, &inner)));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn rename_attr(&mut self, attr: &CstAttr, name: &str) {
+        self.replace(attr.name_range.clone(), name.to_string());
+    }
+
+    /// Remove an attribute with the whitespace before it.
+    pub fn remove_attr(&mut self, attr: &CstAttr) {
+        let source = self.cst.source.as_bytes();
+        let mut start = attr.range.start;
+        while start > 0 && source[start - 1].is_ascii_whitespace() {
+            start -= 1;
+        }
+        self.replace(start..attr.range.end, String::new());
+    }
+
+    /// Insert an attribute after the last attribute of the element.
+    ///
+    /// The value is quoted like `set_attr_value` with `"` preferred,
+    /// and a `None` value inserts a boolean attribute.
+    pub fn insert_attr(
+        &mut self,
+        elem: &CstElement,
+        name: &str,
+        value: Option<&str>,
+    ) -> Result<(), String> {
+        let pos = match elem.attrs.last() {
+            Some(attr) => attr.range.end,
+            None => elem.name_range.end,
+        };
+        let new_text = match value {
+            Some(value) => {
+                let (quote, inner) = quote_attr_value(value, CstQuote::Double)?;
+                format!(" {}={}", name, quoted(quote, &inner))
+            }
+            None => format!(" {}", name),
+        };
+        self.replace(pos..pos, new_text);
+        Ok(())
+    }
+
+    /// Rename the tag in both the start tag and the end tag.
+    pub fn rename_tag(&mut self, elem: &CstElement, name: &str) {
+        self.replace(elem.name_range.clone(), name.to_string());
+        if let Some(range) = &elem.end_name_range {
+            self.replace(range.clone(), name.to_string());
+        }
+    }
+
+    /// The collected edits, sorted by position.
+    pub fn edits(&self) -> Vec<TmplTextEdit> {
+        let mut edits = self.edits.clone();
+        edits.sort_by_key(|x| (x.range.start, x.range.end));
+        edits
+    }
+
+    /// Apply the edits on the source text.
+    ///
+    /// Returns an error if any two edits overlap.
+    pub fn apply(&self) -> Result<String, String> {
+        let source = &self.cst.source;
+        let mut ret = String::new();
+        let mut cur = 0..0;
+        for edit in self.edits() {
+            if edit.range.start < cur.end {
+                return Err(format!(
+                    "the edit at {}..{} overlaps the edit at {}..{}",
+                    edit.range.start, edit.range.end, cur.start, cur.end
+                ));
+            }
+            ret += &source[cur.end..edit.range.start];
+            ret += &edit.new_text;
+            cur = edit.range;
+        }
+        ret += &source[cur.end..];
+        Ok(ret)
+    }
+}
+
+fn span_range(pair: &Pair<'_, Rule>) -> Range<usize> {
+    let span = pair.as_span();
+    span.start()..span.end()
+}
+
+fn parse_attr(pair: Pair<'_, Rule>) -> CstAttr {
+    let range = span_range(&pair);
+    let mut pairs = pair.into_inner();
+    let name = pairs.next().unwrap();
+    let value = pairs.next().map(|pair| {
+        let range = span_range(&pair);
+        let quote = match pair.as_str().chars().next() {
+            Some('"') => CstQuote::Double,
+            Some('\'') => CstQuote::Single,
+            _ => CstQuote::None,
+        };
+        let inner_range = span_range(&pair.into_inner().next().unwrap());
+        CstAttrValue {
+            quote,
+            range,
+            inner_range,
+        }
+    });
+    CstAttr {
+        name: name.as_str().to_string(),
+        name_range: span_range(&name),
+        range,
+        value,
+    }
+}
+
+// the same nesting rules as `parse_segment::parse_segment`,
+// in which an unmatched end tag closes the current element
+fn parse_segment(target: &mut Vec<CstNode>, pairs: &mut Pairs<'_, Rule>) {
+    while let Some(pair) = pairs.peek() {
+        match pair.as_rule() {
+            Rule::text_node => {
+                target.push(CstNode::Text(span_range(&pair)));
+                pairs.next();
+            }
+            Rule::tag => {
+                let range = span_range(&pair);
+                let mut tag_pairs = pair.into_inner();
+                let pair = match tag_pairs.next() {
+                    Some(x) => x,
+                    None => {
+                        pairs.next();
+                        continue;
+                    }
+                };
+                match pair.as_rule() {
+                    Rule::comment => {
+                        target.push(CstNode::Comment(range));
+                        pairs.next();
+                    }
+                    Rule::wxs_script_tag_begin => {
+                        let mut attrs = vec![];
+                        let mut children = vec![];
+                        let mut start_tag = range.clone();
+                        let mut end_tag = None;
+                        for pair in tag_pairs.next().unwrap().into_inner() {
+                            match pair.as_rule() {
+                                Rule::attr => attrs.push(parse_attr(pair)),
+                                Rule::wxs_script_body => {
+                                    let body = span_range(&pair);
+                                    start_tag = range.start..body.start;
+                                    end_tag = Some(body.end..range.end);
+                                    children.push(CstNode::Text(body));
+                                }
+                                _ => unreachable!(),
+                            }
+                        }
+                        let name_range = span_range(&pair);
+                        let end_name_range = end_tag.as_ref().map(|x| x.start + 2..x.start + 5);
+                        target.push(CstNode::Element(CstElement {
+                            tag_name: "wxs".to_string(),
+                            name_range,
+                            start_tag,
+                            end_tag,
+                            end_name_range,
+                            attrs,
+                            children,
+                        }));
+                        pairs.next();
+                    }
+                    Rule::tag_begin => {
+                        let mut inner = pair.into_inner();
+                        let name = inner.next().unwrap();
+                        let tag_name = name.as_str().to_string();
+                        let attrs = inner.map(parse_attr).collect();
+                        let mut elem = CstElement {
+                            tag_name,
+                            name_range: span_range(&name),
+                            start_tag: range,
+                            end_tag: None,
+                            end_name_range: None,
+                            attrs,
+                            children: vec![],
+                        };
+                        pairs.next();
+                        if tag_pairs.next().is_none() {
+                            parse_segment(&mut elem.children, pairs);
+                            if let Some(pair) = pairs.peek() {
+                                let range = span_range(&pair);
+                                let mut inner = pair.into_inner().next().unwrap().into_inner();
+                                let name = inner.next().unwrap();
+                                if name.as_str() == elem.tag_name {
+                                    elem.end_tag = Some(range);
+                                    elem.end_name_range = Some(span_range(&name));
+                                    pairs.next();
+                                }
+                            }
+                        }
+                        target.push(CstNode::Element(elem));
+                    }
+                    Rule::tag_end => return,
+                    _ => unreachable!(),
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+// the static text and the `{{ }}` expressions of a raw attribute value, with whether it is an expression
+fn attr_value_parts(value: &str) -> Vec<(&str, bool)> {
+    let mut ret = vec![];
+    let mut rest = value;
+    while let Some(start) = rest.find("{{") {
+        let end = match rest[start..].find("}}") {
+            Some(x) => start + x + 2,
+            None => break,
+        };
+        ret.push((&rest[..start], false));
+        ret.push((&rest[start..end], true));
+        rest = &rest[end..];
+    }
+    ret.push((rest, false));
+    ret
+}
+
+fn quote_char(quote: CstQuote) -> Option<char> {
+    match quote {
+        CstQuote::Double => Some('"'),
+        CstQuote::Single => Some('\''),
+        CstQuote::None => None,
+    }
+}
+
+// choose the quote of a raw attribute value, preferring `quote`,
+// and return the text between the quotes, in which the quote character is escaped;
+// the quotes in the expressions cannot be escaped, since the entities are not decoded in them
+pub(crate) fn quote_attr_value(value: &str, quote: CstQuote) -> Result<(CstQuote, String), String> {
+    let quote = match quote {
+        CstQuote::None
+            if value.is_empty()
+                || value.contains(|c: char| c.is_whitespace() || matches!(c, '>' | '"' | '\'')) =>
+        {
+            CstQuote::Double
+        }
+        x => x,
+    };
+    let other = match quote {
+        CstQuote::Double => CstQuote::Single,
+        CstQuote::Single => CstQuote::Double,
+        CstQuote::None => CstQuote::None,
+    };
+    let parts = attr_value_parts(value);
+    let contains = |q: CstQuote| quote_char(q).is_some_and(|c| value.contains(c));
+    let in_exprs = |q: CstQuote| {
+        quote_char(q).is_some_and(|c| parts.iter().any(|(s, is_expr)| *is_expr && s.contains(c)))
+    };
+    let candidates = if contains(quote) && !contains(other) {
+        [other, quote]
+    } else {
+        [quote, other]
+    };
+    let quote = candidates
+        .into_iter()
+        .find(|q| !in_exprs(*q))
+        .ok_or_else(|| format!("both quotes are used in the expressions of `{}`", value))?;
+    let inner = parts
+        .iter()
+        .map(|(s, is_expr)| match (quote, is_expr) {
+            (CstQuote::Double, false) => s.replace('"', "&quot;"),
+            (CstQuote::Single, false) => s.replace('\'', "&#39;"),
+            _ => s.to_string(),
+        })
+        .collect();
+    Ok((quote, inner))
+}
+
+fn quoted(quote: CstQuote, inner: &str) -> String {
+    match quote {
+        CstQuote::Double => format!("\"{}\"", inner),
+        CstQuote::Single => format!("'{}'", inner),
+        CstQuote::None => inner.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_edits_with_minimal_changes() {
+        let source = "<view  class='a'\n  hidden data-x=1 >\n  <text id=\"t\">&lt;{{ a }}</text><!-- c --></view>\n";
+        let cst = TmplCst::parse(source).unwrap();
+        let mut edit = cst.edit();
+        assert_eq!(edit.apply().unwrap(), source);
+
+        let elements = cst.elements();
+        assert_eq!(elements.len(), 2);
+        let (view, text) = (elements[0], elements[1]);
+        assert_eq!(cst.text(&view.attrs()[0].range()), "class='a'");
+        edit.set_attr_value(view.attr("class").unwrap(), "b c")
+            .unwrap();
+        edit.set_attr_value(view.attr("data-x").unwrap(), "2")
+            .unwrap();
+        edit.remove_attr(view.attr("hidden").unwrap());
+        edit.rename_tag(text, "span");
+        edit.rename_attr(text.attr("id").unwrap(), "key");
+        edit.insert_attr(text, "title", Some("{{ t }}")).unwrap();
+        assert_eq!(
+            edit.apply().unwrap(),
+            "<view  class='b c' data-x=2 >\n  <span key=\"t\" title=\"{{ t }}\">&lt;{{ a }}</span><!-- c --></view>\n"
+        );
+        assert_eq!(edit.edits().len(), 7);
+        edit.rename_attr(view.attr("hidden").unwrap(), "hide");
+        assert_eq!(
+            edit.apply(),
+            Err("the edit at 19..25 overlaps the edit at 16..25".to_string())
+        );
+
+        let cst = TmplCst::parse(r#"<view a="1" b='2' c=3 />"#).unwrap();
+        let view = cst.elements()[0];
+        let mut edit = cst.edit();
+        let a = view.attr("a").unwrap();
+        edit.set_attr_value(a, r#"say "hi""#).unwrap();
+        edit.set_attr_value(view.attr("b").unwrap(), r#"it's {{ "x" }}"#)
+            .unwrap();
+        edit.set_attr_value(view.attr("c").unwrap(), "it's")
+            .unwrap();
+        edit.insert_attr(view, "d", Some(r#""{{ a ? 'x' : 1 }}""#))
+            .unwrap();
+        assert_eq!(
+            edit.apply().unwrap(),
+            r#"<view a='say "hi"' b='it&#39;s {{ "x" }}' c="it's" d="&quot;{{ a ? 'x' : 1 }}&quot;" />"#
+        );
+        assert!(edit.set_attr_value(a, r#"{{ a ? "x" : 'y' }}"#).is_err());
+    }
+}
@@ -0,0 +1,393 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde_json::{json, Value};
+
+use crate::{
+    element::{TmplAttrValue, TmplElement, TmplNode, TmplTextNode, TmplVirtualType},
+    expr::TmplExpr,
+    tree::TmplTree,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+enum DataPathSegment {
+    Field(String),
+    Index(i32),
+    Any,
+}
+
+/// The data paths read by a template.
+///
+/// A path is written like `user.profile.name`, `list[0]` or `list[*].id`,
+/// in which `[*]` means any item of the list.
+#[derive(Debug, Default)]
+pub struct TmplDataPaths {
+    pub paths: BTreeSet<String>,
+    pub sub_templates: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl TmplDataPaths {
+    pub fn to_json(&self) -> Value {
+        let sub_templates: serde_json::Map<String, Value> = self
+            .sub_templates
+            .iter()
+            .map(|(name, paths)| (name.to_string(), json!(paths)))
+            .collect();
+        json!({
+            "paths": self.paths,
+            "subTemplates": sub_templates,
+        })
+    }
+}
+
+/// Collect the full data paths read by the template.
+///
+/// The paths in sub templates (`<template name>`) are relative to the `data` given by the callers,
+/// so they are collected separately.
+pub fn collect_data_paths(tree: &TmplTree) -> TmplDataPaths {
+    // script modules are not in data
+    let scopes: Vec<Option<Vec<DataPathSegment>>> = tree.scripts.iter().map(|_| None).collect();
+    let mut ret = TmplDataPaths::default();
+    collect_in_children(&tree.root, &scopes, &mut ret.paths);
+    for (name, tmpl) in tree.sub_templates.iter() {
+        let mut paths = BTreeSet::new();
+        collect_in_children(tmpl, &scopes, &mut paths);
+        ret.sub_templates.insert(name.to_string(), paths);
+    }
+    ret
+}
+
+fn render_path(path: &[DataPathSegment]) -> String {
+    let mut ret = String::new();
+    for seg in path.iter() {
+        match seg {
+            DataPathSegment::Field(x) => {
+                if !ret.is_empty() {
+                    ret.push('.');
+                }
+                ret += x;
+            }
+            DataPathSegment::Index(x) => ret += &format!("[{}]", x),
+            DataPathSegment::Any => ret += "[*]",
+        }
+    }
+    ret
+}
+
+fn resolve_path(
+    expr: &TmplExpr,
+    scopes: &[Option<Vec<DataPathSegment>>],
+) -> Option<Vec<DataPathSegment>> {
+    match expr {
+        TmplExpr::Ident(x) => Some(vec![DataPathSegment::Field(x.to_string())]),
+        TmplExpr::ScopeIndex(i) => scopes.get(*i).cloned().flatten(),
+        TmplExpr::StaticMember(x, name) => resolve_path(x, scopes).map(|mut p| {
+            p.push(DataPathSegment::Field(name.to_string()));
+            p
+        }),
+        TmplExpr::DynamicMember(x, y) => resolve_path(x, scopes).map(|mut p| {
+            p.push(match &**y {
+                TmplExpr::LitStr(name) => DataPathSegment::Field(name.to_string()),
+                TmplExpr::LitInt(i) => DataPathSegment::Index(*i),
+                _ => DataPathSegment::Any,
+            });
+            p
+        }),
+        _ => None,
+    }
+}
+
+fn collect_in_attr_value(
+    v: &TmplAttrValue,
+    scopes: &[Option<Vec<DataPathSegment>>],
+    out: &mut BTreeSet<String>,
+) {
+    match v {
+        TmplAttrValue::Static(_) => {}
+        TmplAttrValue::Dynamic { expr, .. } => collect_in_expr(expr, scopes, out),
+    }
+}
+
+fn collect_in_children(
+    parent: &TmplElement,
+    scopes: &[Option<Vec<DataPathSegment>>],
+    out: &mut BTreeSet<String>,
+) {
+    for node in parent.children.iter() {
+        match node {
+            TmplNode::TextNode(text_node) => match text_node {
+                TmplTextNode::Static(_) => {}
+                TmplTextNode::Dynamic { expr, .. } => collect_in_expr(expr, scopes, out),
+            },
+            TmplNode::Comment(_) => {}
+            TmplNode::Element(elem) => collect_in_element(elem, scopes, out),
+        }
+    }
+}
+
+// collect the paths in the directives of the element, returning the scopes for its content
+fn enter_element(
+    elem: &TmplElement,
+    scopes: &[Option<Vec<DataPathSegment>>],
+    out: &mut BTreeSet<String>,
+) -> Option<Vec<Option<Vec<DataPathSegment>>>> {
+    let mut new_scopes = None;
+    match &elem.virtual_type {
+        TmplVirtualType::None => {}
+        TmplVirtualType::Pure => {}
+        TmplVirtualType::IfGroup => {}
+        TmplVirtualType::If { cond } => collect_in_attr_value(cond, scopes, out),
+        TmplVirtualType::Elif { cond } => collect_in_attr_value(cond, scopes, out),
+        TmplVirtualType::Else => {}
+        TmplVirtualType::For { list, key, .. } => {
+            collect_in_attr_value(list, scopes, out);
+            let item_path = match list {
+                TmplAttrValue::Dynamic { expr, .. } => resolve_path(expr, scopes).map(|mut p| {
+                    p.push(DataPathSegment::Any);
+                    p
+                }),
+                TmplAttrValue::Static(_) => None,
+            };
+            if let (Some(item_path), Some(key)) = (&item_path, key) {
+                let mut p = item_path.clone();
+                if key != "*this" {
+                    p.push(DataPathSegment::Field(key.to_string()));
+                }
+                out.insert(render_path(&p));
+            }
+            let mut s = scopes.to_vec();
+            s.push(item_path);
+            s.push(None);
+            new_scopes = Some(s);
+        }
+        TmplVirtualType::TemplateRef { target, data } => {
+            collect_in_attr_value(target, scopes, out);
+            collect_in_attr_value(data, scopes, out);
+        }
+        TmplVirtualType::Include { .. } => {}
+        TmplVirtualType::Slot { name, props } => {
+            collect_in_attr_value(name, scopes, out);
+            if let Some(props) = props {
+                for attr in props.iter() {
+                    collect_in_attr_value(&attr.value, scopes, out);
+                }
+            }
+        }
+    }
+    if !elem.slot_values.is_empty() {
+        let mut s = new_scopes.take().unwrap_or_else(|| scopes.to_vec());
+        for _ in elem.slot_values.iter() {
+            s.push(None);
+        }
+        new_scopes = Some(s);
+    }
+    new_scopes
+}
+
+fn collect_in_element(
+    elem: &TmplElement,
+    scopes: &[Option<Vec<DataPathSegment>>],
+    out: &mut BTreeSet<String>,
+) {
+    let new_scopes = enter_element(elem, scopes, out);
+    let scopes_ref = new_scopes.as_deref().unwrap_or(scopes);
+    for attr in elem.attrs.iter() {
+        collect_in_attr_value(&attr.value, scopes_ref, out);
+    }
+    if let Some(slot) = elem.slot.as_ref() {
+        collect_in_attr_value(slot, scopes_ref, out);
+    }
+    collect_in_children(elem, scopes_ref, out);
+}
+
+/// Collect the full data paths read by the element whose start tag begins at `start_pos`,
+/// including its `wx:if`, `wx:for` and content.
+///
+/// The paths in a sub template are relative to its `data`.
+pub fn collect_data_paths_at(tree: &TmplTree, start_pos: (usize, usize)) -> BTreeSet<String> {
+    let scopes: Vec<Option<Vec<DataPathSegment>>> = tree.scripts.iter().map(|_| None).collect();
+    let mut ret = BTreeSet::new();
+    let mut roots = vec![&tree.root];
+    roots.extend(tree.sub_templates.values());
+    for root in roots {
+        if collect_at(root, &scopes, start_pos, &mut ret) {
+            break;
+        }
+    }
+    ret
+}
+
+// the directive wrappers share the position of the element, and the outermost one is found first
+fn collect_at(
+    parent: &TmplElement,
+    scopes: &[Option<Vec<DataPathSegment>>],
+    start_pos: (usize, usize),
+    out: &mut BTreeSet<String>,
+) -> bool {
+    for node in parent.children.iter() {
+        let elem = match node {
+            TmplNode::Element(x) => x,
+            _ => continue,
+        };
+        if elem.start_pos == start_pos {
+            collect_in_element(elem, scopes, out);
+            return true;
+        }
+        let new_scopes = enter_element(elem, scopes, &mut BTreeSet::new());
+        if collect_at(
+            elem,
+            new_scopes.as_deref().unwrap_or(scopes),
+            start_pos,
+            out,
+        ) {
+            return true;
+        }
+    }
+    false
+}
+
+fn collect_in_expr(
+    expr: &TmplExpr,
+    scopes: &[Option<Vec<DataPathSegment>>],
+    out: &mut BTreeSet<String>,
+) {
+    match expr {
+        TmplExpr::ScopeIndex(_)
+        | TmplExpr::Ident(_)
+        | TmplExpr::StaticMember(_, _)
+        | TmplExpr::DynamicMember(_, _) => {
+            if let Some(p) = resolve_path(expr, scopes) {
+                if !p.is_empty() {
+                    out.insert(render_path(&p));
+                }
+            }
+            // the member keys and a non-path base are read separately
+            let mut cur = expr;
+            loop {
+                match cur {
+                    TmplExpr::StaticMember(x, _) => cur = x,
+                    TmplExpr::DynamicMember(x, y) => {
+                        collect_in_expr(y, scopes, out);
+                        cur = x;
+                    }
+                    TmplExpr::Ident(_) | TmplExpr::ScopeIndex(_) => break,
+                    x => {
+                        collect_in_expr(x, scopes, out);
+                        break;
+                    }
+                }
+            }
+        }
+        TmplExpr::ToStringWithoutUndefined(x) => collect_in_expr(x, scopes, out),
+
+        TmplExpr::LitUndefined => {}
+        TmplExpr::LitNull => {}
+        TmplExpr::LitStr(_) => {}
+        TmplExpr::LitInt(_) => {}
+        TmplExpr::LitFloat(_) => {}
+        TmplExpr::LitBool(_) => {}
+        TmplExpr::LitObj(x) => {
+            for x in x.iter() {
+                collect_in_expr(&x.1, scopes, out);
+            }
+        }
+        TmplExpr::LitArr(x) => {
+            for x in x.iter() {
+                collect_in_expr(x, scopes, out);
+            }
+        }
+
+        TmplExpr::FuncCall(x, y) => {
+            collect_in_expr(x, scopes, out);
+            for y in y.iter() {
+                collect_in_expr(y, scopes, out);
+            }
+        }
+
+        TmplExpr::Reverse(x)
+        | TmplExpr::BitReverse(x)
+        | TmplExpr::Positive(x)
+        | TmplExpr::Negative(x) => collect_in_expr(x, scopes, out),
+
+        TmplExpr::Multiply(x, y)
+        | TmplExpr::Divide(x, y)
+        | TmplExpr::Mod(x, y)
+        | TmplExpr::Plus(x, y)
+        | TmplExpr::Minus(x, y)
+        | TmplExpr::Lt(x, y)
+        | TmplExpr::Gt(x, y)
+        | TmplExpr::Lte(x, y)
+        | TmplExpr::Gte(x, y)
+        | TmplExpr::Eq(x, y)
+        | TmplExpr::Ne(x, y)
+        | TmplExpr::EqFull(x, y)
+        | TmplExpr::NeFull(x, y)
+        | TmplExpr::BitAnd(x, y)
+        | TmplExpr::BitXor(x, y)
+        | TmplExpr::BitOr(x, y)
+        | TmplExpr::LogicAnd(x, y)
+        | TmplExpr::LogicOr(x, y) => {
+            collect_in_expr(x, scopes, out);
+            collect_in_expr(y, scopes, out);
+        }
+
+        TmplExpr::Cond(x, y, z) => {
+            collect_in_expr(x, scopes, out);
+            collect_in_expr(y, scopes, out);
+            collect_in_expr(z, scopes, out);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_tmpl;
+
+    #[test]
+    fn it_collects_data_paths() {
+        let tree = parse_tmpl(
+            r#"
+                <wxs module="utils" src="./utils.wxs" />
+                <view class="{{ user.profile.name }}">{{ utils.format(list[0].title) }}</view>
+                <view wx:for="{{ groups }}" wx:for-item="group" wx:key="id">
+                    <text wx:for="{{ group.members }}">{{ item.name }}{{ index }}{{ map[item.key] }}</text>
+                </view>
+                <template name="card">{{ title }}</template>
+            "#,
+        )
+        .unwrap();
+        let ret = collect_data_paths(&tree);
+        assert_eq!(
+            ret.paths.into_iter().collect::<Vec<_>>(),
+            vec![
+                "groups",
+                "groups[*].id",
+                "groups[*].members",
+                "groups[*].members[*].key",
+                "groups[*].members[*].name",
+                "list[0].title",
+                "map[*]",
+                "user.profile.name",
+            ]
+        );
+        assert_eq!(
+            ret.sub_templates["card"].iter().collect::<Vec<_>>(),
+            vec!["title"]
+        );
+    }
+
+    #[test]
+    fn it_collects_data_paths_at() {
+        let tree = parse_tmpl(
+            r#"<view class="{{ a }}">
+<view wx:for="{{ groups }}" wx:key="id"><text>{{ item.name }}</text></view>
+</view>"#,
+        )
+        .unwrap();
+        let paths: Vec<String> = collect_data_paths_at(&tree, (2, 1)).into_iter().collect();
+        assert_eq!(paths, vec!["groups", "groups[*].id", "groups[*].name"]);
+        let paths: Vec<String> = collect_data_paths_at(&tree, (2, 41)).into_iter().collect();
+        assert_eq!(paths, vec!["groups[*].name"]);
+        assert_eq!(collect_data_paths_at(&tree, (1, 1)).len(), 4);
+    }
+}
@@ -0,0 +1,71 @@
+use std::fmt::{Debug, Display};
+
+/// The start and end positions, as `(line, column)`, of a piece of source.
+pub(crate) type TmplPosRange = ((usize, usize), (usize, usize));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TmplDiagnosticLevel {
+    Error,
+    Warning,
+}
+
+/// A problem found in a template after it has been parsed successfully.
+///
+/// Unlike `TmplParseError`, a diagnostic does not stop the compilation.
+/// The `code` is a stable identifier which can be used to filter diagnostics.
+#[derive(Clone, PartialEq)]
+pub struct TmplDiagnostic {
+    pub level: TmplDiagnosticLevel,
+    pub code: String,
+    pub message: String,
+    pub path: String,
+    pub start_pos: (usize, usize),
+    pub end_pos: (usize, usize),
+}
+
+impl TmplDiagnostic {
+    pub(crate) fn new(
+        level: TmplDiagnosticLevel,
+        code: &str,
+        message: String,
+        path: &str,
+        start_pos: (usize, usize),
+        end_pos: (usize, usize),
+    ) -> Self {
+        Self {
+            level,
+            code: code.to_string(),
+            message,
+            path: path.to_string(),
+            start_pos,
+            end_pos,
+        }
+    }
+}
+
+impl Debug for TmplDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let level = match self.level {
+            TmplDiagnosticLevel::Error => "error",
+            TmplDiagnosticLevel::Warning => "warning",
+        };
+        write!(
+            f,
+            "{}: {}[{}] (from line {} column {} to line {} column {}) : {}",
+            self.path,
+            level,
+            self.code,
+            self.start_pos.0,
+            self.start_pos.1,
+            self.end_pos.0,
+            self.end_pos.1,
+            self.message
+        )
+    }
+}
+
+impl Display for TmplDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
@@ -0,0 +1,256 @@
+use std::borrow::Cow;
+
+use crate::{
+    diagnostic::TmplDiagnostic,
+    display_debug::TmplPrinter,
+    tree::TmplTree,
+    utils::{camel_to_dash, dash_to_camel},
+};
+
+/// The template dialects of mini-program platforms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TmplDialect {
+    /// WXML of WeChat, with `wx:if` and `bind:tap`.
+    #[default]
+    Wechat,
+    /// AXML of Alipay, with `a:if` and `onTap`.
+    Alipay,
+    /// Swan of Baidu, with `s-if` and the directive values written without `{{}}`.
+    Swan,
+    /// TTML of ByteDance, with `tt:if`.
+    Ttml,
+}
+
+impl TmplDialect {
+    /// Get a dialect by the platform name or the file extension, such as `alipay` or `axml`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "wechat" | "wxml" => Some(Self::Wechat),
+            "alipay" | "axml" => Some(Self::Alipay),
+            "baidu" | "swan" => Some(Self::Swan),
+            "bytedance" | "ttml" => Some(Self::Ttml),
+            _ => None,
+        }
+    }
+
+    pub fn template_ext(&self) -> &'static str {
+        match self {
+            Self::Wechat => "wxml",
+            Self::Alipay => "axml",
+            Self::Swan => "swan",
+            Self::Ttml => "ttml",
+        }
+    }
+
+    pub fn script_ext(&self) -> &'static str {
+        match self {
+            Self::Wechat => "wxs",
+            Self::Alipay | Self::Swan | Self::Ttml => "sjs",
+        }
+    }
+
+    pub(crate) fn directive_prefix(&self) -> &'static str {
+        match self {
+            Self::Wechat => "wx:",
+            Self::Alipay => "a:",
+            Self::Swan => "s-",
+            Self::Ttml => "tt:",
+        }
+    }
+
+    // map an attribute name of the dialect to the WXML one, e.g. `a:if` to `wx:if`
+    pub(crate) fn normalize_attr_name<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        if *self == Self::Wechat {
+            return Cow::Borrowed(name);
+        }
+        if let Some(x) = name.strip_prefix(self.directive_prefix()) {
+            return Cow::Owned(format!("wx:{}", x));
+        }
+        if *self == Self::Alipay {
+            let (capture, x) = match name.strip_prefix("capture-") {
+                Some(x) => ("capture-", x),
+                None => ("", name),
+            };
+            let event = |prefix: &str| {
+                x.strip_prefix(prefix)
+                    .filter(|x| x.starts_with(|c: char| c.is_ascii_uppercase()))
+            };
+            if let Some(x) = event("on") {
+                return Cow::Owned(format!("{}bind:{}", capture, wx_event_name(x)));
+            }
+            if let Some(x) = event("catch") {
+                return Cow::Owned(format!("{}catch:{}", capture, wx_event_name(x)));
+            }
+        }
+        Cow::Borrowed(name)
+    }
+}
+
+// the builtin events as WXML and AXML event names,
+// in which an AXML event mapped from several WXML events is listed first with its WXML equivalent
+const ALIPAY_EVENTS: &[(&str, &str)] = &[
+    ("tap", "Tap"),
+    ("longpress", "LongTap"),
+    ("longtap", "LongTap"),
+    ("touchstart", "TouchStart"),
+    ("touchmove", "TouchMove"),
+    ("touchend", "TouchEnd"),
+    ("touchcancel", "TouchCancel"),
+    ("transitionend", "TransitionEnd"),
+    ("animationstart", "AnimationStart"),
+    ("animationiteration", "AnimationIteration"),
+    ("animationend", "AnimationEnd"),
+    ("animationfinish", "AnimationEnd"),
+    ("input", "Input"),
+    ("focus", "Focus"),
+    ("blur", "Blur"),
+    ("confirm", "Confirm"),
+    ("change", "Change"),
+    ("changing", "Changing"),
+    ("columnchange", "ColumnChange"),
+    ("linechange", "LineChange"),
+    ("keyboardheightchange", "KeyboardHeightChange"),
+    ("submit", "Submit"),
+    ("reset", "Reset"),
+    ("scroll", "Scroll"),
+    ("scrolltoupper", "ScrollToUpper"),
+    ("scrolltolower", "ScrollToLower"),
+    ("refresherrefresh", "RefresherRefresh"),
+    ("transition", "Transition"),
+    ("load", "Load"),
+    ("error", "Error"),
+    ("play", "Play"),
+    ("pause", "Pause"),
+    ("ended", "Ended"),
+    ("timeupdate", "TimeUpdate"),
+    ("waiting", "Waiting"),
+    ("fullscreenchange", "FullScreenChange"),
+    ("markertap", "MarkerTap"),
+    ("callouttap", "CalloutTap"),
+    ("controltap", "ControlTap"),
+    ("regionchange", "RegionChange"),
+    ("close", "Close"),
+    ("cancel", "Cancel"),
+    ("success", "Success"),
+    ("fail", "Fail"),
+    ("complete", "Complete"),
+];
+
+// the AXML event name like `LongTap` of a WXML event name like `longpress`,
+// in which the events of custom components are converted to Pascal case, e.g. `ItemTap` for `item-tap`
+pub(crate) fn alipay_event_name(name: &str) -> String {
+    if let Some((_, x)) = ALIPAY_EVENTS.iter().find(|(x, _)| *x == name) {
+        return x.to_string();
+    }
+    let x = dash_to_camel(name);
+    let mut chars = x.chars();
+    match chars.next() {
+        Some(c) => c.to_ascii_uppercase().to_string() + chars.as_str(),
+        None => String::new(),
+    }
+}
+
+// the reverse of `alipay_event_name`
+fn wx_event_name(name: &str) -> String {
+    if let Some((x, _)) = ALIPAY_EVENTS.iter().find(|(_, x)| *x == name) {
+        return x.to_string();
+    }
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) => camel_to_dash(&(c.to_ascii_lowercase().to_string() + chars.as_str())),
+        None => String::new(),
+    }
+}
+
+/// Print the template in a dialect.
+///
+/// The constructs without an equivalent in the dialect are reported as `unsupported-in-dialect`
+/// warnings, and are dropped or printed as the closest equivalent.
+pub fn print_tmpl_as(tree: &TmplTree, dialect: TmplDialect) -> (String, Vec<TmplDiagnostic>) {
+    let mut printer = TmplPrinter::new(tree, dialect);
+    let mut out = String::new();
+    printer.write_tree(tree, &mut out);
+    (out, printer.reporter.diagnostics)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{parse_tmpl, parse_tmpl_with_options, TmplParseOptions};
+
+    #[test]
+    fn it_prints_dialects() {
+        let tree = parse_tmpl(
+            r#"<view wx:for="{{ list }}" wx:key="id" wx:if="{{ item.a }}" bind:tap="onTap" catch:longpress="onLong" mut-bind:touchstart="onStart" bindscrolltolower="onMore" bind:item-tap="onItem" /><template is="card" data="{{ a: 1 }}" />"#,
+        )
+        .unwrap();
+
+        let (code, diagnostics) = print_tmpl_as(&tree, TmplDialect::Alipay);
+        assert_eq!(
+            code,
+            r#"<view a:for="{{list}}" a:key="id" a:if="{{item.a}}" onTap="onTap" catchLongTap="onLong" onTouchStart="onStart" onScrollToLower="onMore" onItemTap="onItem"></view><template is="card" data="{{a:1}}"></template>"#
+        );
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, "unsupported-in-dialect");
+
+        let (code, _) = print_tmpl_as(&tree, TmplDialect::Swan);
+        assert_eq!(
+            code,
+            r#"<view s-for="list" s-key="id" s-if="item.a" bind:tap="onTap" catch:longpress="onLong" bind:touchstart="onStart" bind:scrolltolower="onMore" bind:item-tap="onItem"></view><template is="card" data="{{{a:1}}}"></template>"#
+        );
+
+        let (code, _) = print_tmpl_as(&tree, TmplDialect::Ttml);
+        assert!(code.starts_with(r#"<view tt:for="{{list}}""#));
+    }
+
+    #[test]
+    fn it_parses_dialects() {
+        let parse = |dialect, src: &str| {
+            let options = TmplParseOptions {
+                dialect,
+                ..Default::default()
+            };
+            parse_tmpl_with_options(src, &options).unwrap().to_string()
+        };
+        let expected = r#"<view wx:for="{{list}}" wx:for-item="x" wx:for-index="i" wx:key="id" wx:if="{{x.a}}" bind:tap="onTap" catch:longpress="onLong" bind:tap="onTap2"></view>"#;
+        assert_eq!(
+            parse(
+                TmplDialect::Alipay,
+                r#"<scroll-view onScrollToLower="onMore" catchTouchStart="onStart" onItemTap="onItem" />"#
+            ),
+            r#"<scroll-view bind:scrolltolower="onMore" catch:touchstart="onStart" bind:item-tap="onItem"></scroll-view>"#
+        );
+        assert_eq!(
+            parse(
+                TmplDialect::Alipay,
+                r#"<view a:for="{{ list }}" a:for-item="x" a:for-index="i" a:key="id" a:if="{{ x.a }}" onTap="onTap" catchLongTap="onLong" bindtap="onTap2" />"#
+            ),
+            expected
+        );
+        assert_eq!(
+            parse(
+                TmplDialect::Swan,
+                r#"<view s-for="x, i in list trackBy x.id" s-if="x.a" bind:tap="onTap" catch:longpress="onLong" bindtap="onTap2" />"#
+            ),
+            expected
+        );
+        assert_eq!(
+            parse(
+                TmplDialect::Ttml,
+                r#"<view tt:for="{{ list }}" tt:for-item="x" tt:for-index="i" tt:key="id" tt:if="{{ x.a }}" bindtap="onTap" catchlongpress="onLong" bind:tap="onTap2" />"#
+            ),
+            expected
+        );
+        assert_eq!(
+            parse(
+                TmplDialect::Swan,
+                r#"<input value="{= v =}" aria:label="a" />"#
+            ),
+            r#"<input model:value="{{v}}" aria:label="a"></input>"#
+        );
+        assert_eq!(
+            parse(TmplDialect::Swan, r#"<view s-if=a=="x"||b=='}}' />"#),
+            r#"<view wx:if="{{a=='x'||b=='}}'}}"></view>"#
+        );
+    }
+}
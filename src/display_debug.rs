@@ -1,496 +1,789 @@
 use crate::{
+    diagnostic::TmplPosRange,
+    dialect::{alipay_event_name, TmplDialect},
     element::{
-        TmplAttr, TmplAttrKind, TmplAttrValue, TmplElement, TmplNode, TmplTextNode, TmplVirtualType,
+        TmplAttr, TmplAttrKind, TmplAttrValue, TmplComment, TmplElement, TmplNode, TmplScript,
+        TmplTextNode, TmplVirtualType,
     },
-    escape::{escape_html_text, gen_lit_str},
+    escape::{escape_html_text, gen_wxml_lit_str},
     expr::{TmplExpr, TmplExprLevel},
+    lowering::{is_free_name, UnsupportedReporter},
+    parse_segment::keeps_raw_entities,
+    parser::TmplWhitespaceMode,
     tree::TmplTree,
-    TmplGroup,
+    utils::camel_to_dash,
 };
 use std::fmt;
 
-// impl fmt::Debug for TmplGroup {
-//     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-//         for (name, tree) in self.trees.iter() {
-//             writeln!(f, "{}", name)?;
-//             writeln!(f, "{}", tree)?;
-//         }
-//         Ok(())
-//     }
-// }
-
-// impl fmt::Display for TmplTree {
-//     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-//         let import_strings: Vec<String> = self
-//             .imports
-//             .iter()
-//             .map(|x| format!(r#"<import src="{}"></import>"#, escape_html_text(x)))
-//             .collect();
-//         let sub_template_strings: Vec<String> = self
-//             .sub_templates
-//             .iter()
-//             .map(|(k, v)| {
-//                 let children_strings: Vec<String> =
-//                     v.children.iter().map(|c| format!("{}", c)).collect();
-//                 format!(
-//                     r#"<template name="{}">{}</template>"#,
-//                     escape_html_text(k),
-//                     children_strings.join("")
-//                 )
-//             })
-//             .collect();
-//         let children_strings: Vec<String> = self
-//             .root
-//             .children
-//             .iter()
-//             .map(|c| format!("{}", c))
-//             .collect();
-//         write!(
-//             f,
-//             "{}{}{}",
-//             import_strings.join(""),
-//             sub_template_strings.join(""),
-//             children_strings.join("")
-//         )
-//     }
-// }
-
-// impl fmt::Display for TmplNode {
-//     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-//         match self {
-//             TmplNode::TextNode(n) => write!(f, "{}", n),
-//             TmplNode::Element(n) => write!(f, "{}", n),
-//         }
-//     }
-// }
-
-// impl fmt::Display for TmplTextNode {
-//     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-//         match self {
-//             TmplTextNode::Static(v) => {
-//                 write!(f, "{}", escape_html_text(v))
-//             }
-//             TmplTextNode::Dynamic { expr, .. } => {
-//                 write!(f, "{{{{{}}}}}", expr)
-//             }
-//         }
-//     }
-// }
-
-// impl fmt::Display for TmplElement {
-//     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-//         let virtual_string: String = match &self.virtual_type {
-//             TmplVirtualType::None => "".into(),
-//             TmplVirtualType::Pure => "".into(),
-//             TmplVirtualType::IfGroup => {
-//                 let children_strings: Vec<String> =
-//                     self.children.iter().map(|c| format!("{}", c)).collect();
-//                 return write!(f, "{}", children_strings.join(""));
-//             }
-//             TmplVirtualType::If { cond } => format!(r#" wx:if={}"#, cond),
-//             TmplVirtualType::Elif { cond } => format!(r#" wx:elif={}"#, cond),
-//             TmplVirtualType::Else => " wx:else".into(),
-//             TmplVirtualType::For {
-//                 list,
-//                 item_name,
-//                 index_name,
-//                 key,
-//             } => {
-//                 let list = format!(r#" wx:for={}"#, list);
-//                 let item = format!(r#" wx:for-item="{}""#, escape_html_text(item_name));
-//                 let index = format!(r#" wx:for-index="{}""#, escape_html_text(index_name));
-//                 let key = if let Some(key) = key {
-//                     format!(r#" wx:key="{}""#, escape_html_text(key))
-//                 } else {
-//                     String::new()
-//                 };
-//                 list + &item + &index + &key
-//             }
-//             TmplVirtualType::TemplateRef { target, data } => {
-//                 format!(r#" is={} data={}"#, target, data)
-//             }
-//             TmplVirtualType::Include { path } => format!(r#" src="{}""#, escape_html_text(path)),
-//             TmplVirtualType::Slot { name, props } => {
-//                 let name = format!(r#" name={}"#, name);
-//                 let props = if let Some(props) = props {
-//                     let prop_strings: Vec<String> =
-//                         props.iter().map(|prop| format!(" {}", prop)).collect();
-//                     prop_strings.join("")
-//                 } else {
-//                     String::new()
-//                 };
-//                 name + &props
-//             }
-//         };
-//         let attr_strings: Vec<String> =
-//             self.attrs.iter().map(|attr| format!(" {}", attr)).collect();
-//         let children_strings: Vec<String> =
-//             self.children.iter().map(|c| format!("{}", c)).collect();
-//         let generics_seg = {
-//             self.generics
-//                 .as_ref()
-//                 .map(|list| {
-//                     list.iter()
-//                         .map(|(k, v)| format!(" generic:{}={}", k, v))
-//                         .collect::<Vec<_>>()
-//                         .join("")
-//                 })
-//                 .unwrap_or_default()
-//         };
-//         let slot_props_seg = if self.slot_values.len() > 0 {
-//             let props: Vec<String> = self
-//                 .slot_values
-//                 .iter()
-//                 .map(|(capture_name, provide_name)| {
-//                     format!(r#" slot:{}="{}""#, capture_name, provide_name)
-//                 })
-//                 .collect();
-//             props.join("")
-//         } else {
-//             String::new()
-//         };
-//         let extra_attr_seg = {
-//             self.extra_attr
-//                 .as_ref()
-//                 .map(|list| {
-//                     list.iter()
-//                         .map(|(k, v)| format!(" extra-attr:{}={}", k, v))
-//                         .collect::<Vec<_>>()
-//                         .join("")
-//                 })
-//                 .unwrap_or_default()
-//         };
-//         write!(
-//             f,
-//             "<{}{}{}{}{}{}>{}</{}>",
-//             &self.tag_name,
-//             virtual_string,
-//             attr_strings.join(""),
-//             generics_seg,
-//             slot_props_seg,
-//             extra_attr_seg,
-//             children_strings.join(""),
-//             &self.tag_name
-//         )
-//     }
-// }
-
-// impl fmt::Display for TmplExpr {
-//     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-//         write!(f, "{}", self.to_expr_string(TmplExprLevel::Comma, false))
-//     }
-// }
-
-// impl TmplExpr {
-//     pub(crate) fn to_expr_string(&self, allow_level: TmplExprLevel, is_js_target: bool) -> String {
-//         if self.level() > allow_level {
-//             return format!(
-//                 "({})",
-//                 self.to_expr_string(TmplExprLevel::Comma, is_js_target)
-//             );
-//         }
-//         match self {
-//             TmplExpr::ScopeIndex(index) => {
-//                 if is_js_target {
-//                     format!("S({})", index)
-//                 } else {
-//                     format!("${}", index)
-//                 }
-//             }
-//             TmplExpr::Ident(x) => {
-//                 if is_js_target {
-//                     format!("D.{}", x)
-//                 } else {
-//                     format!("{}", x)
-//                 }
-//             }
-//             TmplExpr::ToStringWithoutUndefined(x) => {
-//                 format!("Y({})", x.to_expr_string(TmplExprLevel::Cond, is_js_target))
-//             }
-
-//             TmplExpr::LitUndefined => "undefined".to_string(),
-//             TmplExpr::LitNull => "null".to_string(),
-//             TmplExpr::LitStr(x) => gen_lit_str(x),
-//             TmplExpr::LitInt(x) => {
-//                 format!("{}", x)
-//             }
-//             TmplExpr::LitFloat(x) => {
-//                 format!("{}", x)
-//             }
-//             TmplExpr::LitBool(x) => {
-//                 format!("{}", x)
-//             }
-//             TmplExpr::LitObj(x) => {
-//                 let mut r = String::from("{}");
-//                 let mut s: Vec<String> = vec![];
-//                 for x in x.iter() {
-//                     let v_string = x.1.to_expr_string(TmplExprLevel::Cond, is_js_target);
-//                     match &x.0 {
-//                         Some(k) => s.push(format!("{}:{}", &k, v_string)),
-//                         None => {
-//                             if is_js_target {
-//                                 if s.len() > 0 {
-//                                     r = format!(
-//                                         "Object.assign({},{{{}}},{})",
-//                                         r,
-//                                         s.join(","),
-//                                         v_string
-//                                     );
-//                                     s.truncate(0);
-//                                 } else {
-//                                     r = format!("Object.assign({},{})", r, v_string);
-//                                 }
-//                             } else {
-//                                 s.push(format!("...{}", v_string))
-//                             }
-//                         }
-//                     }
-//                 }
-//                 let merged_s = format!("{{{}}}", s.join(","));
-//                 if r.len() > 2 {
-//                     if s.len() > 0 {
-//                         format!("Object.assign({},{})", r, merged_s)
-//                     } else {
-//                         r
-//                     }
-//                 } else {
-//                     merged_s
-//                 }
-//             }
-//             TmplExpr::LitArr(x) => {
-//                 let s: Vec<String> = x
-//                     .iter()
-//                     .map(|x| x.to_expr_string(TmplExprLevel::Cond, is_js_target))
-//                     .collect();
-//                 format!("[{}]", s.join(","))
-//             }
-
-//             TmplExpr::StaticMember(x, y) => {
-//                 format!(
-//                     "X({}).{}",
-//                     x.to_expr_string(TmplExprLevel::Cond, is_js_target),
-//                     y
-//                 )
-//             }
-//             TmplExpr::DynamicMember(x, y) => {
-//                 format!(
-//                     "X({})[{}]",
-//                     x.to_expr_string(TmplExprLevel::Cond, is_js_target),
-//                     y.to_expr_string(TmplExprLevel::Cond, is_js_target)
-//                 )
-//             }
-//             TmplExpr::FuncCall(x, y) => {
-//                 let s: Vec<String> = y
-//                     .iter()
-//                     .map(|x| x.to_expr_string(TmplExprLevel::Cond, is_js_target))
-//                     .collect();
-//                 format!(
-//                     "{}({})",
-//                     x.to_expr_string(TmplExprLevel::Member, is_js_target),
-//                     s.join(",")
-//                 )
-//             }
-
-//             TmplExpr::Reverse(x) => {
-//                 format!("!{}", x.to_expr_string(TmplExprLevel::Unary, is_js_target))
-//             }
-//             TmplExpr::BitReverse(x) => {
-//                 format!("~{}", x.to_expr_string(TmplExprLevel::Unary, is_js_target))
-//             }
-//             TmplExpr::Positive(x) => {
-//                 format!("+{}", x.to_expr_string(TmplExprLevel::Unary, is_js_target))
-//             }
-//             TmplExpr::Negative(x) => {
-//                 format!("-{}", x.to_expr_string(TmplExprLevel::Unary, is_js_target))
-//             }
-
-//             TmplExpr::Multiply(x, y) => {
-//                 format!(
-//                     "{}*{}",
-//                     x.to_expr_string(TmplExprLevel::Multiply, is_js_target),
-//                     y.to_expr_string(TmplExprLevel::Unary, is_js_target)
-//                 )
-//             }
-//             TmplExpr::Divide(x, y) => {
-//                 format!(
-//                     "{}/{}",
-//                     x.to_expr_string(TmplExprLevel::Multiply, is_js_target),
-//                     y.to_expr_string(TmplExprLevel::Unary, is_js_target)
-//                 )
-//             }
-//             TmplExpr::Mod(x, y) => {
-//                 format!(
-//                     "{}%{}",
-//                     x.to_expr_string(TmplExprLevel::Multiply, is_js_target),
-//                     y.to_expr_string(TmplExprLevel::Unary, is_js_target)
-//                 )
-//             }
-//             TmplExpr::Plus(x, y) => {
-//                 format!(
-//                     "{}+{}",
-//                     x.to_expr_string(TmplExprLevel::Plus, is_js_target),
-//                     y.to_expr_string(TmplExprLevel::Multiply, is_js_target)
-//                 )
-//             }
-//             TmplExpr::Minus(x, y) => {
-//                 format!(
-//                     "{}-{}",
-//                     x.to_expr_string(TmplExprLevel::Plus, is_js_target),
-//                     y.to_expr_string(TmplExprLevel::Multiply, is_js_target)
-//                 )
-//             }
-
-//             TmplExpr::Lt(x, y) => {
-//                 format!(
-//                     "{}<{}",
-//                     x.to_expr_string(TmplExprLevel::Comparison, is_js_target),
-//                     y.to_expr_string(TmplExprLevel::Plus, is_js_target)
-//                 )
-//             }
-//             TmplExpr::Gt(x, y) => {
-//                 format!(
-//                     "{}>{}",
-//                     x.to_expr_string(TmplExprLevel::Comparison, is_js_target),
-//                     y.to_expr_string(TmplExprLevel::Plus, is_js_target)
-//                 )
-//             }
-//             TmplExpr::Lte(x, y) => {
-//                 format!(
-//                     "{}<={}",
-//                     x.to_expr_string(TmplExprLevel::Comparison, is_js_target),
-//                     y.to_expr_string(TmplExprLevel::Plus, is_js_target)
-//                 )
-//             }
-//             TmplExpr::Gte(x, y) => {
-//                 format!(
-//                     "{}>={}",
-//                     x.to_expr_string(TmplExprLevel::Comparison, is_js_target),
-//                     y.to_expr_string(TmplExprLevel::Plus, is_js_target)
-//                 )
-//             }
-//             TmplExpr::Eq(x, y) => {
-//                 format!(
-//                     "{}=={}",
-//                     x.to_expr_string(TmplExprLevel::Eq, is_js_target),
-//                     y.to_expr_string(TmplExprLevel::Comparison, is_js_target)
-//                 )
-//             }
-//             TmplExpr::Ne(x, y) => {
-//                 format!(
-//                     "{}!={}",
-//                     x.to_expr_string(TmplExprLevel::Eq, is_js_target),
-//                     y.to_expr_string(TmplExprLevel::Comparison, is_js_target)
-//                 )
-//             }
-//             TmplExpr::EqFull(x, y) => {
-//                 format!(
-//                     "{}==={}",
-//                     x.to_expr_string(TmplExprLevel::Eq, is_js_target),
-//                     y.to_expr_string(TmplExprLevel::Comparison, is_js_target)
-//                 )
-//             }
-//             TmplExpr::NeFull(x, y) => {
-//                 format!(
-//                     "{}!=={}",
-//                     x.to_expr_string(TmplExprLevel::Eq, is_js_target),
-//                     y.to_expr_string(TmplExprLevel::Comparison, is_js_target)
-//                 )
-//             }
-
-//             TmplExpr::BitAnd(x, y) => {
-//                 format!(
-//                     "{}&{}",
-//                     x.to_expr_string(TmplExprLevel::BitAnd, is_js_target),
-//                     y.to_expr_string(TmplExprLevel::Eq, is_js_target)
-//                 )
-//             }
-//             TmplExpr::BitXor(x, y) => {
-//                 format!(
-//                     "{}^{}",
-//                     x.to_expr_string(TmplExprLevel::BitXor, is_js_target),
-//                     y.to_expr_string(TmplExprLevel::BitAnd, is_js_target)
-//                 )
-//             }
-//             TmplExpr::BitOr(x, y) => {
-//                 format!(
-//                     "{}|{}",
-//                     x.to_expr_string(TmplExprLevel::BitOr, is_js_target),
-//                     y.to_expr_string(TmplExprLevel::BitXor, is_js_target)
-//                 )
-//             }
-//             TmplExpr::LogicAnd(x, y) => {
-//                 format!(
-//                     "{}&&{}",
-//                     x.to_expr_string(TmplExprLevel::LogicAnd, is_js_target),
-//                     y.to_expr_string(TmplExprLevel::BitOr, is_js_target)
-//                 )
-//             }
-//             TmplExpr::LogicOr(x, y) => {
-//                 format!(
-//                     "{}||{}",
-//                     x.to_expr_string(TmplExprLevel::LogicOr, is_js_target),
-//                     y.to_expr_string(TmplExprLevel::LogicAnd, is_js_target)
-//                 )
-//             }
-
-//             TmplExpr::Cond(x, y, z) => {
-//                 format!(
-//                     "{}?{}:{}",
-//                     x.to_expr_string(TmplExprLevel::LogicOr, is_js_target),
-//                     y.to_expr_string(TmplExprLevel::Cond, is_js_target),
-//                     z.to_expr_string(TmplExprLevel::Cond, is_js_target)
-//                 )
-//             }
-//         }
-//     }
-// }
-
-// impl fmt::Display for TmplAttr {
-//     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-//         let s = match &self.kind {
-//             TmplAttrKind::WxDirective { name } => format!("wx:{}", name),
-//             TmplAttrKind::Generic { name } => format!("generic:{}", name),
-//             TmplAttrKind::Slot => format!("slot"),
-//             TmplAttrKind::SlotProperty { name } => format!("{}", name),
-//             TmplAttrKind::Id => format!("id"),
-//             TmplAttrKind::Class => format!("class"),
-//             TmplAttrKind::Style => format!("style"),
-//             TmplAttrKind::PropertyOrExternalClass { name } => format!("{}", name),
-//             TmplAttrKind::ModelProperty { name } => format!("model:{}", name),
-//             TmplAttrKind::ChangeProperty { name } => format!("change:{}", name),
-//             TmplAttrKind::WorkletProperty { name } => format!("worklet:{}", name),
-//             TmplAttrKind::Data { name } => format!("data:{}", name),
-//             TmplAttrKind::Event {
-//                 capture,
-//                 catch,
-//                 mut_bind,
-//                 name,
-//             } => {
-//                 let capture_prefix = if *capture { "capture-" } else { "" };
-//                 let main_prefix = if *catch {
-//                     "catch"
-//                 } else if *mut_bind {
-//                     "mut-bind"
-//                 } else {
-//                     "bind"
-//                 };
-//                 format!("{}{}-{}", capture_prefix, main_prefix, name)
-//             }
-//             TmplAttrKind::Mark { name } => format!("mark:{}", name),
-//         };
-//         write!(f, "{}={}", &s, &self.value)
-//     }
-// }
-
-// impl fmt::Display for TmplAttrValue {
-//     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-//         match self {
-//             TmplAttrValue::Static(v) => {
-//                 write!(f, "\"{}\"", escape_html_text(v))
-//             }
-//             TmplAttrValue::Dynamic { expr, .. } => {
-//                 write!(f, "\"{{{{{}}}}}\"", expr)
-//             }
-//         }
-//     }
-// }
+// Print the tree back to WXML.
+//
+// The `wx:if` and `wx:for` wrappers generated while converting directives are merged back
+// into the element they wrap, and `ScopeIndex` is printed as the wxs module name or the scope name in the source.
+impl fmt::Display for TmplTree {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut out = String::new();
+        TmplPrinter::new(self, TmplDialect::Wechat).write_tree(self, &mut out);
+        write!(f, "{}", out)
+    }
+}
+
+impl fmt::Display for TmplNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut out = String::new();
+        TmplPrinter::default().write_node(self, &mut out);
+        write!(f, "{}", out)
+    }
+}
+
+impl fmt::Display for TmplElement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut out = String::new();
+        TmplPrinter::default().write_element(self, "", &mut out);
+        write!(f, "{}", out)
+    }
+}
+
+impl fmt::Display for TmplAttr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut out = String::new();
+        TmplPrinter::default().write_attr(self, ((0, 0), (0, 0)), &mut out);
+        write!(f, "{}", out.trim_start())
+    }
+}
+
+impl fmt::Display for TmplAttrValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut out = String::new();
+        TmplPrinter::default().write_attr_value(self, &mut out);
+        write!(f, "{}", out)
+    }
+}
+
+impl fmt::Display for TmplExpr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = TmplPrinter::default().expr_string(self, TmplExprLevel::Comma);
+        write!(f, "{}", s)
+    }
+}
+
+// split an expression like `a{{b}}c` into the `LitStr` and `ToStringWithoutUndefined` segments
+pub(crate) fn text_segments(expr: &TmplExpr) -> Option<Vec<&TmplExpr>> {
+    fn collect_segs<'b>(expr: &'b TmplExpr, segs: &mut Vec<&'b TmplExpr>) -> bool {
+        match expr {
+            TmplExpr::Plus(x, y) => collect_segs(x, segs) && collect_segs(y, segs),
+            TmplExpr::LitStr(_) | TmplExpr::ToStringWithoutUndefined(_) => {
+                segs.push(expr);
+                true
+            }
+            _ => false,
+        }
+    }
+    let mut segs = vec![];
+    if collect_segs(expr, &mut segs)
+        && segs
+            .iter()
+            .any(|x| matches!(x, TmplExpr::ToStringWithoutUndefined(_)))
+    {
+        Some(segs)
+    } else {
+        None
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct TmplPrinter<'a> {
+    scope_names: Vec<String>,
+    dialect: TmplDialect,
+    whitespace: TmplWhitespaceMode,
+    // whether the static text being written is kept undecoded, so it should not be escaped
+    raw_text: bool,
+    pub(crate) reporter: UnsupportedReporter<'a>,
+    // print the data fields as the members of the variable, e.g. `data.a` for `a`
+    pub(crate) data_var: Option<&'a str>,
+    // the identifiers of the printed code which the scope names must not shadow,
+    // or `None` if the scope names are always printed as written in the source
+    pub(crate) reserved_names: Option<&'a [&'a str]>,
+    // print the interpolations in a text as `(x??'')` in JavaScript,
+    // which are empty for `undefined` and `null` like WXML
+    pub(crate) js_interpolation: bool,
+}
+
+impl<'a> TmplPrinter<'a> {
+    pub(crate) fn new(tree: &'a TmplTree, dialect: TmplDialect) -> Self {
+        let scope_names = tree
+            .scripts
+            .iter()
+            .map(|x| x.module_name().to_string())
+            .collect();
+        Self {
+            scope_names,
+            dialect,
+            whitespace: tree.whitespace,
+            raw_text: false,
+            reporter: UnsupportedReporter::new(
+                "unsupported-in-dialect",
+                dialect.template_ext(),
+                &tree.path,
+            ),
+            data_var: None,
+            reserved_names: None,
+            js_interpolation: false,
+        }
+    }
+
+    // add the scope names used in the element, which are printed as written in the source
+    // unless they are not free in the printed code, and return the length to restore with `leave_scope`
+    pub(crate) fn enter_scope(&mut self, elem: &TmplElement) -> usize {
+        let len = self.scope_names.len();
+        for (i, name) in elem.source_scope_names.iter().enumerate() {
+            let name = match self.reserved_names {
+                Some(reserved) if !is_free_name(name, reserved) => format!("${}", len + i),
+                _ => name.clone(),
+            };
+            self.scope_names.push(name);
+        }
+        len
+    }
+
+    // the printed names of the scopes added since `enter_scope` returned `len`
+    pub(crate) fn entered_scope_names(&self, len: usize) -> &[String] {
+        &self.scope_names[len..]
+    }
+
+    pub(crate) fn leave_scope(&mut self, len: usize) {
+        self.scope_names.truncate(len);
+    }
+
+    // the path of an imported or included template, with the extension of the dialect
+    fn tmpl_path(&self, path: &str) -> String {
+        match self.dialect {
+            TmplDialect::Wechat => path.to_string(),
+            x => format!("{}.{}", path, x.template_ext()),
+        }
+    }
+
+    pub(crate) fn write_tree(&mut self, tree: &TmplTree, out: &mut String) {
+        for import in tree.imports.iter() {
+            *out += &format!(
+                r#"<import src="{}"></import>"#,
+                escape_html_text(&self.tmpl_path(import))
+            );
+        }
+        for script in tree.scripts.iter() {
+            let ext = self.dialect.script_ext();
+            match script {
+                TmplScript::Inline {
+                    module_name,
+                    content,
+                    ..
+                } => {
+                    let module_name = escape_html_text(module_name);
+                    *out += &match self.dialect {
+                        TmplDialect::Wechat => {
+                            format!(r#"<wxs module="{}">{}</wxs>"#, module_name, content)
+                        }
+                        TmplDialect::Alipay => {
+                            self.reporter.report("inline <wxs>", script.pos());
+                            continue;
+                        }
+                        TmplDialect::Swan => format!(
+                            r#"<import-sjs module="{}">{}</import-sjs>"#,
+                            module_name, content
+                        ),
+                        TmplDialect::Ttml => {
+                            format!(r#"<sjs module="{}">{}</sjs>"#, module_name, content)
+                        }
+                    };
+                }
+                TmplScript::GlobalRef {
+                    module_name,
+                    rel_path,
+                    ..
+                } => {
+                    let module_name = escape_html_text(module_name);
+                    let src = escape_html_text(rel_path);
+                    *out += &match self.dialect {
+                        TmplDialect::Wechat => format!(
+                            r#"<wxs module="{}" src="{}.{}"></wxs>"#,
+                            module_name, src, ext
+                        ),
+                        TmplDialect::Alipay => format!(
+                            r#"<import-sjs name="{}" from="{}.{}"></import-sjs>"#,
+                            module_name, src, ext
+                        ),
+                        TmplDialect::Swan => format!(
+                            r#"<import-sjs module="{}" src="{}.{}"></import-sjs>"#,
+                            module_name, src, ext
+                        ),
+                        TmplDialect::Ttml => format!(
+                            r#"<sjs module="{}" src="{}.{}"></sjs>"#,
+                            module_name, src, ext
+                        ),
+                    };
+                }
+            }
+        }
+        let mut names: Vec<&String> = tree.sub_templates.keys().collect();
+        names.sort();
+        for name in names {
+            *out += &format!(r#"<template name="{}">"#, escape_html_text(name));
+            self.write_children(&tree.sub_templates[name], out);
+            *out += "</template>";
+        }
+        self.write_children(&tree.root, out);
+    }
+
+    fn write_children(&mut self, elem: &TmplElement, out: &mut String) {
+        for child in elem.children.iter() {
+            self.write_node(child, out);
+        }
+    }
+
+    pub(crate) fn write_node(&mut self, node: &TmplNode, out: &mut String) {
+        match node {
+            TmplNode::TextNode(n) => self.write_text_node(n, out),
+            TmplNode::Element(n) => self.write_element(n, "", out),
+            TmplNode::Comment(n) => self.write_comment(n, out),
+        }
+    }
+
+    fn write_comment(&self, comment: &TmplComment, out: &mut String) {
+        *out += &format!("<!--{}-->", comment.content);
+    }
+
+    fn write_text_node(&self, text_node: &TmplTextNode, out: &mut String) {
+        match text_node {
+            TmplTextNode::Static(v) if self.raw_text => *out += v,
+            TmplTextNode::Static(v) => *out += &escape_html_text(v),
+            TmplTextNode::Dynamic { expr, .. } => self.write_text(expr, self.raw_text, out),
+        }
+    }
+
+    // write an expression as the content of a text node or an attribute value,
+    // so `a{{b}}` is printed as it is rather than `{{'a'+b}}`
+    fn write_text(&self, expr: &TmplExpr, raw: bool, out: &mut String) {
+        if let Some(segs) = text_segments(expr) {
+            for seg in segs {
+                match seg {
+                    TmplExpr::LitStr(s) if raw => *out += s,
+                    TmplExpr::LitStr(s) => *out += &escape_html_text(s),
+                    TmplExpr::ToStringWithoutUndefined(x) => {
+                        *out += &format!("{{{{{}}}}}", self.expr_string(x, TmplExprLevel::Cond))
+                    }
+                    _ => unreachable!(),
+                }
+            }
+            return;
+        }
+        let s = match expr {
+            TmplExpr::LitObj(x) if !x.is_empty() => self.obj_body_string(x),
+            x => self.expr_string(x, TmplExprLevel::Cond),
+        };
+        *out += &format!("{{{{{}}}}}", s);
+    }
+
+    // `wx:if` and `wx:for` on non-block elements are converted into `<block>` wrappers
+    // at the same position, which are printed as directives on the wrapped element
+    pub(crate) fn single_wrapped_child<'b>(
+        &self,
+        elem: &'b TmplElement,
+    ) -> Option<&'b TmplElement> {
+        if elem.tag_name != "block" || elem.children.len() != 1 {
+            return None;
+        }
+        match &elem.children[0] {
+            TmplNode::Element(child) if child.start_pos == elem.start_pos => Some(child),
+            _ => None,
+        }
+    }
+
+    // write a ` wx:name="value"` directive, in which the value has no `{{}}` in Swan
+    fn write_directive(&self, name: &str, value: Option<&TmplAttrValue>, out: &mut String) {
+        *out += " ";
+        *out += self.dialect.directive_prefix();
+        *out += name;
+        let value = match value {
+            None => return,
+            Some(x) => x,
+        };
+        *out += "=";
+        if self.dialect != TmplDialect::Swan {
+            self.write_attr_value(value, out);
+            return;
+        }
+        let s = match value {
+            TmplAttrValue::Static(s) => gen_wxml_lit_str(s),
+            TmplAttrValue::Dynamic { expr, .. } => self.expr_string(expr, TmplExprLevel::Cond),
+        };
+        *out += &format!("\"{}\"", s);
+    }
+
+    pub(crate) fn write_element(&mut self, elem: &TmplElement, directives: &str, out: &mut String) {
+        let pos = (elem.start_pos, elem.end_pos);
+        let mut virtual_string = directives.to_string();
+        match &elem.virtual_type {
+            TmplVirtualType::None => {}
+            TmplVirtualType::Pure => {}
+            TmplVirtualType::IfGroup => {
+                if let Some(child) = self.single_wrapped_child(elem) {
+                    self.write_element(child, directives, out);
+                } else {
+                    self.write_children(elem, out);
+                }
+                return;
+            }
+            TmplVirtualType::If { cond } => {
+                self.write_directive("if", Some(cond), &mut virtual_string);
+            }
+            TmplVirtualType::Elif { cond } => {
+                self.write_directive("elif", Some(cond), &mut virtual_string);
+            }
+            TmplVirtualType::Else => self.write_directive("else", None, &mut virtual_string),
+            TmplVirtualType::For {
+                list,
+                item_name,
+                index_name,
+                key,
+            } => {
+                self.write_directive("for", Some(list), &mut virtual_string);
+                let prefix = self.dialect.directive_prefix();
+                let item_name = elem.source_scope_names.first().unwrap_or(item_name);
+                let index_name = elem.source_scope_names.get(1).unwrap_or(index_name);
+                if item_name != "item" {
+                    virtual_string +=
+                        &format!(r#" {}for-item="{}""#, prefix, escape_html_text(item_name));
+                }
+                if index_name != "index" {
+                    virtual_string +=
+                        &format!(r#" {}for-index="{}""#, prefix, escape_html_text(index_name));
+                }
+                if let Some(key) = key {
+                    virtual_string += &format!(r#" {}key="{}""#, prefix, escape_html_text(key));
+                }
+            }
+            TmplVirtualType::TemplateRef { target, data } => {
+                virtual_string += " is=";
+                self.write_attr_value(target, &mut virtual_string);
+                match data {
+                    TmplAttrValue::Dynamic { expr, .. } => match &**expr {
+                        TmplExpr::LitObj(x) if x.is_empty() => {}
+                        // Swan requires the braces of the object
+                        TmplExpr::LitObj(_) if self.dialect == TmplDialect::Swan => {
+                            let s = self.expr_string(expr, TmplExprLevel::Cond);
+                            virtual_string += &format!(r#" data="{{{{{}}}}}""#, s);
+                        }
+                        _ => {
+                            virtual_string += " data=";
+                            self.write_attr_value(data, &mut virtual_string);
+                        }
+                    },
+                    TmplAttrValue::Static(_) => {
+                        virtual_string += " data=";
+                        self.write_attr_value(data, &mut virtual_string);
+                    }
+                }
+            }
+            TmplVirtualType::Include { path } => {
+                virtual_string += &format!(r#" src="{}""#, escape_html_text(&self.tmpl_path(path)))
+            }
+            TmplVirtualType::Slot { name, props } => {
+                if !matches!(name, TmplAttrValue::Static(x) if x.is_empty()) {
+                    virtual_string += " name=";
+                    self.write_attr_value(name, &mut virtual_string);
+                }
+                if let Some(props) = props {
+                    for prop in props.iter() {
+                        self.write_attr(prop, pos, &mut virtual_string);
+                    }
+                }
+            }
+        }
+        let scope_len = self.enter_scope(elem);
+        let source_provide_names = elem
+            .source_scope_names
+            .len()
+            .checked_sub(elem.slot_values.len())
+            .map(|start| &elem.source_scope_names[start..]);
+        let mut slot_props_string = String::new();
+        for (i, (capture_name, provide_name)) in elem.slot_values.iter().enumerate() {
+            let provide_name = source_provide_names.map_or(provide_name, |x| &x[i]);
+            let capture_name = camel_to_dash(capture_name);
+            if self.dialect != TmplDialect::Wechat {
+                self.reporter.report(&format!("slot:{}", capture_name), pos);
+            } else if capture_name == *provide_name {
+                slot_props_string += &format!(" slot:{}", capture_name);
+            } else {
+                slot_props_string += &format!(
+                    r#" slot:{}="{}""#,
+                    capture_name,
+                    escape_html_text(provide_name)
+                );
+            }
+        }
+        let wrapped_child = match &elem.virtual_type {
+            TmplVirtualType::If { .. }
+            | TmplVirtualType::Elif { .. }
+            | TmplVirtualType::Else
+            | TmplVirtualType::For { .. } => self.single_wrapped_child(elem),
+            _ => None,
+        };
+        match wrapped_child {
+            Some(child) => self.write_element(child, &(virtual_string + &slot_props_string), out),
+            None => self.write_tag(elem, &virtual_string, &slot_props_string, out),
+        }
+        self.leave_scope(scope_len);
+    }
+
+    fn write_tag(
+        &mut self,
+        elem: &TmplElement,
+        virtual_string: &str,
+        slot_props_string: &str,
+        out: &mut String,
+    ) {
+        let pos = (elem.start_pos, elem.end_pos);
+        let mut attr_string = String::new();
+        for attr in elem.attrs.iter() {
+            self.write_attr(attr, pos, &mut attr_string);
+        }
+        if let Some(slot) = &elem.slot {
+            attr_string += " slot=";
+            self.write_attr_value(slot, &mut attr_string);
+        }
+        if let Some(generics) = &elem.generics {
+            let mut list: Vec<(&String, &String)> = generics.iter().collect();
+            list.sort();
+            for (k, v) in list {
+                if self.dialect != TmplDialect::Wechat {
+                    self.reporter.report(&format!("generic:{}", k), pos);
+                    continue;
+                }
+                attr_string += &format!(r#" generic:{}="{}""#, k, escape_html_text(v));
+            }
+        }
+        *out += &format!(
+            "<{}{}{}{}>",
+            &elem.tag_name, virtual_string, attr_string, slot_props_string
+        );
+        let raw_text = keeps_raw_entities(elem, self.whitespace);
+        let raw_text = std::mem::replace(&mut self.raw_text, raw_text);
+        self.write_children(elem, out);
+        self.raw_text = raw_text;
+        *out += &format!("</{}>", &elem.tag_name);
+    }
+
+    fn event_name(&mut self, kind: &TmplAttrKind, pos: TmplPosRange) -> String {
+        let (capture, catch, mut_bind, name) = match kind {
+            TmplAttrKind::Event {
+                capture,
+                catch,
+                mut_bind,
+                name,
+            } => (*capture, *catch, *mut_bind, name),
+            _ => unreachable!(),
+        };
+        let capture_prefix = if capture { "capture-" } else { "" };
+        let main_prefix = if catch {
+            "catch"
+        } else if mut_bind {
+            "mut-bind"
+        } else {
+            "bind"
+        };
+        let wx_name = format!("{}{}:{}", capture_prefix, main_prefix, name);
+        match self.dialect {
+            TmplDialect::Wechat => wx_name,
+            TmplDialect::Alipay => {
+                if capture || mut_bind {
+                    self.reporter.report(&wx_name, pos);
+                }
+                let name = alipay_event_name(name);
+                format!("{}{}", if catch { "catch" } else { "on" }, name)
+            }
+            TmplDialect::Swan | TmplDialect::Ttml => {
+                if mut_bind {
+                    self.reporter.report(&wx_name, pos);
+                }
+                let main_prefix = if catch { "catch" } else { "bind" };
+                format!("{}{}:{}", capture_prefix, main_prefix, name)
+            }
+        }
+    }
+
+    // write an attribute with a leading space
+    pub(crate) fn write_attr(&mut self, attr: &TmplAttr, pos: TmplPosRange, out: &mut String) {
+        let is_wechat = self.dialect == TmplDialect::Wechat;
+        let name = match &attr.kind {
+            TmplAttrKind::WxDirective { name } => {
+                format!("{}{}", self.dialect.directive_prefix(), name)
+            }
+            TmplAttrKind::Generic { name } => format!("generic:{}", name),
+            TmplAttrKind::Slot => "slot".to_string(),
+            TmplAttrKind::SlotProperty { name } => format!("slot:{}", camel_to_dash(name)),
+            TmplAttrKind::Id => "id".to_string(),
+            TmplAttrKind::Class => "class".to_string(),
+            TmplAttrKind::Style => "style".to_string(),
+            TmplAttrKind::PropertyOrExternalClass { name } => name.to_string(),
+            TmplAttrKind::ModelProperty { name } => match self.dialect {
+                TmplDialect::Wechat => format!("model:{}", name),
+                TmplDialect::Swan => {
+                    if let TmplAttrValue::Dynamic { expr, .. } = &attr.value {
+                        let s = self.expr_string(expr, TmplExprLevel::Cond);
+                        *out += &format!(r#" {}="{{= {} =}}""#, name, s);
+                        return;
+                    }
+                    name.to_string()
+                }
+                _ => {
+                    self.reporter.report(&format!("model:{}", name), pos);
+                    name.to_string()
+                }
+            },
+            TmplAttrKind::ChangeProperty { name } => format!("change:{}", name),
+            TmplAttrKind::WorkletProperty { name } => format!("worklet:{}", name),
+            TmplAttrKind::Data { name } if is_wechat => format!("data:{}", name),
+            TmplAttrKind::Data { name } => format!("data-{}", camel_to_dash(name)),
+            TmplAttrKind::Event { .. } => self.event_name(&attr.kind, pos),
+            TmplAttrKind::Mark { name } => format!("mark:{}", name),
+        };
+        // the directives are printed with the prefix of the dialect
+        if !is_wechat
+            && !matches!(attr.kind, TmplAttrKind::WxDirective { .. })
+            && self.reporter.report_wxml_only_attr(&attr.kind, pos)
+        {
+            return;
+        }
+        *out += " ";
+        *out += &name;
+        if let TmplAttrValue::Dynamic { expr, .. } = &attr.value {
+            if let TmplExpr::LitBool(true) = &**expr {
+                return;
+            }
+        }
+        *out += "=";
+        self.write_attr_value(&attr.value, out);
+    }
+
+    pub(crate) fn write_attr_value(&self, value: &TmplAttrValue, out: &mut String) {
+        *out += "\"";
+        match value {
+            TmplAttrValue::Static(v) => *out += &escape_html_text(v),
+            TmplAttrValue::Dynamic { expr, .. } => self.write_text(expr, false, out),
+        }
+        *out += "\"";
+    }
+
+    fn obj_body_string(&self, items: &[(Option<String>, TmplExpr)]) -> String {
+        let s: Vec<String> = items
+            .iter()
+            .map(|(k, v)| {
+                let v_string = self.expr_string(v, TmplExprLevel::Cond);
+                match k {
+                    Some(k) => {
+                        let is_ident = k.chars().enumerate().all(|(i, c)| {
+                            c.is_ascii_alphabetic()
+                                || c == '_'
+                                || c == '$'
+                                || i > 0 && c.is_ascii_digit()
+                        }) && !k.is_empty();
+                        if is_ident {
+                            format!("{}:{}", k, v_string)
+                        } else {
+                            format!("{}:{}", gen_wxml_lit_str(k), v_string)
+                        }
+                    }
+                    None => format!("...{}", v_string),
+                }
+            })
+            .collect();
+        s.join(",")
+    }
+
+    pub(crate) fn expr_string(&self, expr: &TmplExpr, allow_level: TmplExprLevel) -> String {
+        if expr.level() > allow_level {
+            return format!("({})", self.expr_string(expr, TmplExprLevel::Comma));
+        }
+        let binary = |x: &TmplExpr, op: &str, y: &TmplExpr, level: TmplExprLevel| {
+            let right_level = match level {
+                TmplExprLevel::Multiply => TmplExprLevel::Unary,
+                TmplExprLevel::Plus => TmplExprLevel::Multiply,
+                TmplExprLevel::Comparison => TmplExprLevel::Plus,
+                TmplExprLevel::Eq => TmplExprLevel::Comparison,
+                TmplExprLevel::BitAnd => TmplExprLevel::Eq,
+                TmplExprLevel::BitXor => TmplExprLevel::BitAnd,
+                TmplExprLevel::BitOr => TmplExprLevel::BitXor,
+                TmplExprLevel::LogicAnd => TmplExprLevel::BitOr,
+                TmplExprLevel::LogicOr => TmplExprLevel::LogicAnd,
+                _ => unreachable!(),
+            };
+            format!(
+                "{}{}{}",
+                self.expr_string(x, level),
+                op,
+                self.expr_string(y, right_level)
+            )
+        };
+        match expr {
+            TmplExpr::ScopeIndex(index) => match self.scope_names.get(*index) {
+                Some(name) => name.to_string(),
+                None => format!("${}", index),
+            },
+            TmplExpr::Ident(x) => match self.data_var {
+                Some(data_var) => format!("{}.{}", data_var, x),
+                None => x.to_string(),
+            },
+            TmplExpr::ToStringWithoutUndefined(x) if self.js_interpolation => {
+                format!("({}??'')", self.expr_string(x, TmplExprLevel::BitOr))
+            }
+            TmplExpr::ToStringWithoutUndefined(x) => self.expr_string(x, allow_level),
+
+            TmplExpr::LitUndefined => "undefined".to_string(),
+            TmplExpr::LitNull => "null".to_string(),
+            TmplExpr::LitStr(x) => gen_wxml_lit_str(x),
+            TmplExpr::LitInt(x) => format!("{}", x),
+            TmplExpr::LitFloat(x) => format!("{}", x),
+            TmplExpr::LitBool(x) => format!("{}", x),
+            TmplExpr::LitObj(x) => format!("{{{}}}", self.obj_body_string(x)),
+            TmplExpr::LitArr(x) => {
+                let s: Vec<String> = x
+                    .iter()
+                    .map(|x| self.expr_string(x, TmplExprLevel::Cond))
+                    .collect();
+                format!("[{}]", s.join(","))
+            }
+
+            TmplExpr::StaticMember(x, y) => {
+                format!("{}.{}", self.expr_string(x, TmplExprLevel::Member), y)
+            }
+            TmplExpr::DynamicMember(x, y) => format!(
+                "{}[{}]",
+                self.expr_string(x, TmplExprLevel::Member),
+                self.expr_string(y, TmplExprLevel::Cond)
+            ),
+            TmplExpr::FuncCall(x, y) => {
+                let s: Vec<String> = y
+                    .iter()
+                    .map(|x| self.expr_string(x, TmplExprLevel::Cond))
+                    .collect();
+                format!(
+                    "{}({})",
+                    self.expr_string(x, TmplExprLevel::Member),
+                    s.join(",")
+                )
+            }
+
+            TmplExpr::Reverse(x) => format!("!{}", self.expr_string(x, TmplExprLevel::Unary)),
+            TmplExpr::BitReverse(x) => format!("~{}", self.expr_string(x, TmplExprLevel::Unary)),
+            TmplExpr::Positive(x) => format!("+{}", self.expr_string(x, TmplExprLevel::Unary)),
+            TmplExpr::Negative(x) => format!("-{}", self.expr_string(x, TmplExprLevel::Unary)),
+
+            TmplExpr::Multiply(x, y) => binary(x, "*", y, TmplExprLevel::Multiply),
+            TmplExpr::Divide(x, y) => binary(x, "/", y, TmplExprLevel::Multiply),
+            TmplExpr::Mod(x, y) => binary(x, "%", y, TmplExprLevel::Multiply),
+            TmplExpr::Plus(x, y) => binary(x, "+", y, TmplExprLevel::Plus),
+            TmplExpr::Minus(x, y) => binary(x, "-", y, TmplExprLevel::Plus),
+
+            TmplExpr::Lt(x, y) => binary(x, "<", y, TmplExprLevel::Comparison),
+            TmplExpr::Gt(x, y) => binary(x, ">", y, TmplExprLevel::Comparison),
+            TmplExpr::Lte(x, y) => binary(x, "<=", y, TmplExprLevel::Comparison),
+            TmplExpr::Gte(x, y) => binary(x, ">=", y, TmplExprLevel::Comparison),
+            TmplExpr::Eq(x, y) => binary(x, "==", y, TmplExprLevel::Eq),
+            TmplExpr::Ne(x, y) => binary(x, "!=", y, TmplExprLevel::Eq),
+            TmplExpr::EqFull(x, y) => binary(x, "===", y, TmplExprLevel::Eq),
+            TmplExpr::NeFull(x, y) => binary(x, "!==", y, TmplExprLevel::Eq),
+
+            TmplExpr::BitAnd(x, y) => binary(x, "&", y, TmplExprLevel::BitAnd),
+            TmplExpr::BitXor(x, y) => binary(x, "^", y, TmplExprLevel::BitXor),
+            TmplExpr::BitOr(x, y) => binary(x, "|", y, TmplExprLevel::BitOr),
+            TmplExpr::LogicAnd(x, y) => binary(x, "&&", y, TmplExprLevel::LogicAnd),
+            TmplExpr::LogicOr(x, y) => binary(x, "||", y, TmplExprLevel::LogicOr),
+
+            TmplExpr::Cond(x, y, z) => format!(
+                "{}?{}:{}",
+                self.expr_string(x, TmplExprLevel::LogicOr),
+                self.expr_string(y, TmplExprLevel::Cond),
+                self.expr_string(z, TmplExprLevel::Cond)
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::{
+        parse_tmpl, parse_tmpl_with_options, TmplParseOptions, TmplWhitespaceMode,
+    };
+
+    #[test]
+    fn it_prints_tree() {
+        let tree = parse_tmpl(
+            r#"<wxs module="utils" src="./utils.wxs" /><view wx:for="{{ list }}" wx:for-item="row" wx:key="id" wx:if="{{ row.a > 1 }}" class="a {{ utils.c(row) }}" bind:tap="onTap" hidden>{{ (index + 1) * 2 }}</view><block wx:else>{{ { a: 'x"y' } }}</block>"#,
+        )
+        .unwrap();
+        let printed = tree.to_string();
+        assert_eq!(
+            printed,
+            r#"<wxs module="utils" src="./utils.wxs"></wxs><view wx:for="{{list}}" wx:for-item="row" wx:key="id" wx:if="{{row.a>1}}" class="a {{utils.c(row)}}" bind:tap="onTap" hidden>{{(index+1)*2}}</view><block wx:else>{{a:'x"y'}}</block>"#
+        );
+        assert_eq!(parse_tmpl(&printed).unwrap().to_string(), printed);
+        let src = r#"<view wx:for="{{list}}" wx:for-index="i" slot:row slot:item-index="j">{{row[i]+j}}</view>"#;
+        assert_eq!(parse_tmpl(src).unwrap().to_string(), src);
+    }
+
+    #[test]
+    fn it_prints_comments() {
+        let options = TmplParseOptions {
+            preserve_comments: true,
+            ..Default::default()
+        };
+        let src = r#"<!-- header --><view wx:if="{{a}}"></view><!-- b --><view wx:elif="{{b}}"></view><!-- c --><view wx:else></view><!-- d -->"#;
+        let tree = parse_tmpl_with_options(src, &options).unwrap();
+        assert_eq!(tree.to_string(), src);
+        let tree = parse_tmpl("<!-- header --><view />").unwrap();
+        assert_eq!(tree.to_string(), "<view></view>");
+    }
+
+    #[test]
+    fn it_prints_whitespace_modes() {
+        let src = "<view>\n  a  {{ b }}\n  c\n</view>\n<view>x</view> <text space=\"nbsp\"> 1 &lt; 2 </text>";
+        let print = |whitespace| {
+            let options = TmplParseOptions {
+                whitespace,
+                ..Default::default()
+            };
+            parse_tmpl_with_options(src, &options).unwrap().to_string()
+        };
+        assert_eq!(
+            print(TmplWhitespaceMode::RemoveBlank),
+            "<view>\n  a  {{b}}\n  c\n</view><view>x</view><text space=\"nbsp\"> 1 &lt; 2 </text>"
+        );
+        assert_eq!(
+            print(TmplWhitespaceMode::Preserve),
+            "<view>\n  a  {{b}}\n  c\n</view>\n<view>x</view> <text space=\"nbsp\"> 1 &lt; 2 </text>"
+        );
+        assert_eq!(
+            print(TmplWhitespaceMode::Collapse),
+            "<view>a {{b}} c</view> <view>x</view> <text space=\"nbsp\">1 &lt; 2</text>"
+        );
+        assert_eq!(
+            print(TmplWhitespaceMode::TextAware),
+            "<view>a {{b}} c</view> <view>x</view> <text space=\"nbsp\">\u{a0}1\u{a0}&lt;\u{a0}2\u{a0}</text>"
+        );
+
+        let options = TmplParseOptions {
+            whitespace: TmplWhitespaceMode::TextAware,
+            ..Default::default()
+        };
+        let src = r#"<text decode="false">&lt;</text><text decode="{{false}}">&lt;</text><text decode>&lt;</text>"#;
+        let tree = parse_tmpl_with_options(src, &options).unwrap();
+        let texts: Vec<_> = tree
+            .root()
+            .children()
+            .iter()
+            .map(|x| x.as_element().unwrap().children()[0].as_text_node())
+            .map(|x| x.unwrap().as_static().unwrap())
+            .collect();
+        assert_eq!(texts, ["&lt;", "&lt;", "<"]);
+        assert_eq!(tree.to_string(), src);
+    }
+}
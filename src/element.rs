@@ -1,7 +1,13 @@
 use std::collections::HashMap;
 
-use crate::{binding_map::BindingMapKeys, expr::TmplExpr, utils::dash_to_camel};
+use crate::{
+    binding_map::BindingMapKeys, diagnostic::TmplPosRange, expr::TmplExpr, utils::dash_to_camel,
+};
 
+/// An element in the template tree.
+///
+/// Besides the real elements, the `wx:` directives and some special tags are represented as virtual elements,
+/// which are described by the `virtual_type`.
 #[derive(Debug)]
 pub struct TmplElement {
     pub(crate) virtual_type: TmplVirtualType,
@@ -12,18 +18,31 @@ pub struct TmplElement {
     pub(crate) extra_attr: Option<HashMap<String, String>>,
     pub(crate) slot: Option<TmplAttrValue>,
     pub(crate) slot_values: Vec<(String, String)>,
+    // the `wx:for` item and index names and the `slot:` provide names as written in the source,
+    // in the order of their scope indexes
+    pub(crate) source_scope_names: Vec<String>,
+    pub(crate) start_pos: (usize, usize),
+    pub(crate) end_pos: (usize, usize),
 }
 
+/// The kind of an element.
 #[derive(Debug)]
-pub(crate) enum TmplVirtualType {
+#[non_exhaustive]
+pub enum TmplVirtualType {
+    /// A real element.
     None,
+    /// A `<block>` without any directive.
     Pure,
+    /// A `wx:for` list, containing the content to repeat.
+    ///
+    /// The scope names are replaced by `$N`, which is the name of `TmplExpr::ScopeIndex(N)`.
     For {
         list: TmplAttrValue,
         item_name: String,
         index_name: String,
         key: Option<String>,
     },
+    /// A group of `If`, `Elif` and `Else` children, of which at most one is rendered.
     IfGroup,
     If {
         cond: TmplAttrValue,
@@ -32,27 +51,35 @@ pub(crate) enum TmplVirtualType {
         cond: TmplAttrValue,
     },
     Else,
+    /// A `<template is>` reference.
     TemplateRef {
         target: TmplAttrValue,
         data: TmplAttrValue,
     },
+    /// An `<include src>`, with `src` in which the `.wxml` extension is stripped.
     Include {
         path: String,
     },
+    /// A `<slot>`, with its slot properties.
     Slot {
         name: TmplAttrValue,
         props: Option<Vec<TmplAttr>>,
     },
 }
 
+/// An attribute of an element.
 #[derive(Debug)]
-pub(crate) struct TmplAttr {
+pub struct TmplAttr {
     pub(crate) kind: TmplAttrKind,
     pub(crate) value: TmplAttrValue,
 }
 
+/// The kind of an attribute, classified by its name prefix.
+///
+/// The names of `data-*`, `model:`, `change:`, `worklet:` and `slot:` attributes are converted to camel case.
 #[derive(Debug)]
-pub(crate) enum TmplAttrKind {
+#[non_exhaustive]
+pub enum TmplAttrKind {
     WxDirective {
         name: String,
     },
@@ -92,39 +119,90 @@ pub(crate) enum TmplAttrKind {
     },
 }
 
+/// The value of an attribute, which is an expression if it contains any `{{}}`.
+///
+/// An attribute without value is the dynamic `true` value.
 #[derive(Debug)]
-pub(crate) enum TmplAttrValue {
+#[non_exhaustive]
+pub enum TmplAttrValue {
     Static(String),
     Dynamic {
         expr: Box<TmplExpr>,
-        binding_map_keys: Option<BindingMapKeys>,
+        meta: TmplExprMeta,
     },
 }
 
+/// The source span and the compiler data of a dynamic value, which are read through the value.
+#[derive(Debug, Default)]
+pub struct TmplExprMeta {
+    pub(crate) binding_map_keys: Option<BindingMapKeys>,
+    // from the first `{{` to the last `}}`, or `(0, 0)` if the expression is not written in the source
+    pub(crate) start_pos: (usize, usize),
+    pub(crate) end_pos: (usize, usize),
+}
+
+impl TmplExprMeta {
+    pub(crate) fn new(pos: TmplPosRange) -> Self {
+        Self {
+            binding_map_keys: None,
+            start_pos: pos.0,
+            end_pos: pos.1,
+        }
+    }
+
+    fn pos(&self) -> Option<TmplPosRange> {
+        if self.start_pos.0 > 0 {
+            Some((self.start_pos, self.end_pos))
+        } else {
+            None
+        }
+    }
+}
+
+/// A `<!-- -->` comment.
+#[derive(Debug, Clone)]
+pub struct TmplComment {
+    pub(crate) content: String,
+    pub(crate) start_pos: (usize, usize),
+    pub(crate) end_pos: (usize, usize),
+}
+
+/// A `<wxs>` module, either inline or referencing a `.wxs` file.
+///
+/// The positions are the start tag of the `<wxs>`, or `(0, 0)` for the modules added by the API.
 #[derive(Debug)]
-pub(crate) enum TmplScript {
+pub enum TmplScript {
     Inline {
         module_name: String,
         content: String,
+        start_pos: (usize, usize),
+        end_pos: (usize, usize),
     },
     GlobalRef {
         module_name: String,
         rel_path: String,
+        start_pos: (usize, usize),
+        end_pos: (usize, usize),
     },
 }
 
 #[derive(Debug)]
-pub(crate) enum TmplNode {
+#[non_exhaustive]
+pub enum TmplNode {
     TextNode(TmplTextNode),
     Element(TmplElement),
+    /// Only exists when the template is parsed with `TmplParseOptions::preserve_comments`.
+    Comment(TmplComment),
 }
 
+/// A text node, which is an expression if it contains any `{{}}`.
 #[derive(Debug)]
-pub(crate) enum TmplTextNode {
+#[non_exhaustive]
+pub enum TmplTextNode {
     Static(String),
     Dynamic {
         expr: Box<TmplExpr>,
-        binding_map_keys: Option<BindingMapKeys>,
+        meta: TmplExprMeta,
     },
 }
 
@@ -139,14 +217,113 @@ impl TmplElement {
             extra_attr: None,
             slot: None,
             slot_values: Vec::with_capacity(0),
+            source_scope_names: Vec::with_capacity(0),
+            start_pos: (0, 0),
+            end_pos: (0, 0),
         }
     }
 
-    pub(crate) fn tag_name_is(&self, tag_name: &str) -> bool {
+    pub fn tag_name(&self) -> &str {
+        &self.tag_name
+    }
+
+    pub fn virtual_type(&self) -> &TmplVirtualType {
+        &self.virtual_type
+    }
+
+    /// The attributes, excluding the ones consumed by the directives, generics and slots.
+    pub fn attrs(&self) -> &[TmplAttr] {
+        &self.attrs
+    }
+
+    pub fn children(&self) -> &[TmplNode] {
+        &self.children
+    }
+
+    /// The `generic:` attributes, keyed by the lower case generic names.
+    pub fn generics(&self) -> Option<&HashMap<String, String>> {
+        self.generics.as_ref()
+    }
+
+    /// The `slot` attribute.
+    pub fn slot(&self) -> Option<&TmplAttrValue> {
+        self.slot.as_ref()
+    }
+
+    /// The `slot:` attributes as `(capture_name, provide_name)` pairs.
+    pub fn slot_values(&self) -> &[(String, String)] {
+        &self.slot_values
+    }
+
+    /// The `wx:for` item and index names or the `slot:` provide names as written in the source,
+    /// which are `$N` in the expressions and in `TmplVirtualType::For`, in the order of their scope indexes.
+    pub fn source_scope_names(&self) -> &[String] {
+        &self.source_scope_names
+    }
+
+    /// The `(line, column)` where the start tag begins.
+    ///
+    /// The virtual elements generated by directives share the position of the original element.
+    pub fn start_pos(&self) -> (usize, usize) {
+        self.start_pos
+    }
+
+    /// The `(line, column)` where the start tag ends.
+    pub fn end_pos(&self) -> (usize, usize) {
+        self.end_pos
+    }
+
+    /// Iterate over the child elements, skipping text nodes.
+    pub fn child_elements(&self) -> impl Iterator<Item = &TmplElement> {
+        self.children.iter().filter_map(|x| x.as_element())
+    }
+
+    /// Iterate over all descendant elements in depth-first pre-order.
+    pub fn descendants(&self) -> TmplDescendants<'_> {
+        TmplDescendants {
+            stack: vec![self.children.iter()],
+        }
+    }
+
+    /// Whether the element renders the same content for any data.
+    ///
+    /// It is a real element or a `<block>` without directives, which binds no events,
+    /// and all its attributes and descendants are static.
+    /// The literal expressions, such as the value of an attribute without value, are considered static.
+    pub fn is_static(&self) -> bool {
+        matches!(
+            self.virtual_type,
+            TmplVirtualType::None | TmplVirtualType::Pure
+        ) && self.slot.iter().all(|x| x.is_static())
+            && self.slot_values.is_empty()
+            && self
+                .attrs
+                .iter()
+                .all(|x| !matches!(x.kind, TmplAttrKind::Event { .. }) && x.value.is_static())
+            && self.children.iter().all(|x| x.is_static())
+    }
+
+    pub fn tag_name_is(&self, tag_name: &str) -> bool {
         self.tag_name == tag_name
     }
 
-    pub(crate) fn add_attr(&mut self, name: &str, value: TmplAttrValue) {
+    pub fn set_tag_name(&mut self, tag_name: &str) {
+        self.tag_name = String::from(tag_name);
+    }
+
+    pub fn attrs_mut(&mut self) -> &mut Vec<TmplAttr> {
+        &mut self.attrs
+    }
+
+    pub fn children_mut(&mut self) -> &mut Vec<TmplNode> {
+        &mut self.children
+    }
+
+    /// Add an attribute, classifying it by its name prefix.
+    ///
+    /// Since `wx:` directives are converted while parsing,
+    /// the directives added after parsing are kept as plain attributes.
+    pub fn add_attr(&mut self, name: &str, value: TmplAttrValue) {
         let kind = if let Some((prefix, name)) = name.split_once(":") {
             let name = name.to_string();
             match prefix {
@@ -206,9 +383,10 @@ impl TmplElement {
                     name,
                 },
                 "mark" => TmplAttrKind::Mark { name },
-                _ => {
-                    return;
-                }
+                // keep the unknown prefixes as it is, e.g. `aria:label`
+                _ => TmplAttrKind::PropertyOrExternalClass {
+                    name: format!("{}:{}", prefix, name),
+                },
             }
         } else {
             match name {
@@ -217,6 +395,15 @@ impl TmplElement {
                 "class" => TmplAttrKind::Class,
                 "style" => TmplAttrKind::Style,
 
+                name if event_without_colon(name).is_some() => {
+                    let (capture, catch, mut_bind, name) = event_without_colon(name).unwrap();
+                    TmplAttrKind::Event {
+                        capture,
+                        catch,
+                        mut_bind,
+                        name: name.to_string(),
+                    }
+                }
                 name if name.starts_with("data-") => {
                     let camel_name = dash_to_camel(&name[5..].to_ascii_lowercase());
                     TmplAttrKind::Data { name: camel_name }
@@ -239,7 +426,44 @@ impl TmplElement {
     }
 }
 
+// parse the event names without the colon, e.g. `bindtap` and `capture-catchtouchstart`
+fn event_without_colon(name: &str) -> Option<(bool, bool, bool, &str)> {
+    let (capture, name) = match name.strip_prefix("capture-") {
+        Some(x) => (true, x),
+        None => (false, name),
+    };
+    let (catch, mut_bind, name) = if let Some(x) = name.strip_prefix("bind") {
+        (false, false, x)
+    } else if let Some(x) = name.strip_prefix("mut-bind") {
+        (false, true, x)
+    } else if let Some(x) = name.strip_prefix("catch") {
+        (true, false, x)
+    } else {
+        return None;
+    };
+    if name.is_empty() {
+        return None;
+    }
+    Some((capture, catch, mut_bind, name))
+}
+
 impl TmplAttr {
+    pub fn kind(&self) -> &TmplAttrKind {
+        &self.kind
+    }
+
+    pub fn value(&self) -> &TmplAttrValue {
+        &self.value
+    }
+
+    pub fn kind_mut(&mut self) -> &mut TmplAttrKind {
+        &mut self.kind
+    }
+
+    pub fn value_mut(&mut self) -> &mut TmplAttrValue {
+        &mut self.value
+    }
+
     pub(crate) fn is_property(&self, n: &str) -> bool {
         match &self.kind {
             TmplAttrKind::PropertyOrExternalClass { name } if name.as_str() == n => true,
@@ -255,7 +479,68 @@ impl TmplAttr {
     }
 }
 
+impl TmplAttrKind {
+    /// The name after the prefix, or `None` for `slot`, `id`, `class` and `style`.
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            TmplAttrKind::WxDirective { name }
+            | TmplAttrKind::Generic { name }
+            | TmplAttrKind::SlotProperty { name }
+            | TmplAttrKind::PropertyOrExternalClass { name }
+            | TmplAttrKind::ModelProperty { name }
+            | TmplAttrKind::ChangeProperty { name }
+            | TmplAttrKind::WorkletProperty { name }
+            | TmplAttrKind::Data { name }
+            | TmplAttrKind::Mark { name }
+            | TmplAttrKind::Event { name, .. } => Some(name),
+            TmplAttrKind::Slot | TmplAttrKind::Id | TmplAttrKind::Class | TmplAttrKind::Style => {
+                None
+            }
+        }
+    }
+}
+
 impl TmplAttrValue {
+    pub fn as_static(&self) -> Option<&str> {
+        match self {
+            TmplAttrValue::Static(s) => Some(s),
+            TmplAttrValue::Dynamic { .. } => None,
+        }
+    }
+
+    pub fn as_expr(&self) -> Option<&TmplExpr> {
+        match self {
+            TmplAttrValue::Static(_) => None,
+            TmplAttrValue::Dynamic { expr, .. } => Some(expr),
+        }
+    }
+
+    /// Whether the value is static or a literal expression.
+    pub fn is_static(&self) -> bool {
+        match self {
+            TmplAttrValue::Static(_) => true,
+            TmplAttrValue::Dynamic { expr, .. } => expr.is_literal(),
+        }
+    }
+
+    /// The `(line, column)` range of the expression, from the first `{{` to the last `}}`.
+    ///
+    /// It is `None` for a static value or an expression which is not written in the source,
+    /// such as the value of an attribute without value.
+    pub fn expr_pos(&self) -> Option<((usize, usize), (usize, usize))> {
+        match self {
+            TmplAttrValue::Static(_) => None,
+            TmplAttrValue::Dynamic { meta, .. } => meta.pos(),
+        }
+    }
+
+    pub(crate) fn new_dynamic(expr: Box<TmplExpr>, pos: TmplPosRange) -> Self {
+        Self::Dynamic {
+            expr,
+            meta: TmplExprMeta::new(pos),
+        }
+    }
+
     pub(crate) fn static_value(self) -> String {
         match self {
             TmplAttrValue::Static(s) => s,
@@ -264,15 +549,138 @@ impl TmplAttrValue {
     }
 }
 
+impl TmplComment {
+    /// The text between `<!--` and `-->`.
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+
+    pub fn start_pos(&self) -> (usize, usize) {
+        self.start_pos
+    }
+
+    pub fn end_pos(&self) -> (usize, usize) {
+        self.end_pos
+    }
+}
+
+impl TmplScript {
+    pub fn module_name(&self) -> &str {
+        match self {
+            TmplScript::Inline { module_name, .. } => module_name,
+            TmplScript::GlobalRef { module_name, .. } => module_name,
+        }
+    }
+
+    pub(crate) fn pos(&self) -> TmplPosRange {
+        match self {
+            TmplScript::Inline {
+                start_pos, end_pos, ..
+            }
+            | TmplScript::GlobalRef {
+                start_pos, end_pos, ..
+            } => (*start_pos, *end_pos),
+        }
+    }
+}
+
+impl TmplNode {
+    /// Whether the node renders the same content for any data, see `TmplElement::is_static`.
+    pub fn is_static(&self) -> bool {
+        match self {
+            TmplNode::Element(x) => x.is_static(),
+            TmplNode::TextNode(x) => x.is_static(),
+            TmplNode::Comment(_) => true,
+        }
+    }
+
+    pub fn as_element(&self) -> Option<&TmplElement> {
+        match self {
+            TmplNode::Element(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    pub fn as_text_node(&self) -> Option<&TmplTextNode> {
+        match self {
+            TmplNode::TextNode(x) => Some(x),
+            _ => None,
+        }
+    }
+
+    pub fn as_comment(&self) -> Option<&TmplComment> {
+        match self {
+            TmplNode::Comment(x) => Some(x),
+            _ => None,
+        }
+    }
+}
+
+/// The iterator returned by `TmplElement::descendants`.
+pub struct TmplDescendants<'a> {
+    stack: Vec<std::slice::Iter<'a, TmplNode>>,
+}
+
+impl<'a> Iterator for TmplDescendants<'a> {
+    type Item = &'a TmplElement;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(iter) = self.stack.last_mut() {
+            match iter.next() {
+                Some(TmplNode::Element(elem)) => {
+                    self.stack.push(elem.children.iter());
+                    return Some(elem);
+                }
+                Some(_) => {}
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+        None
+    }
+}
+
 impl TmplTextNode {
+    pub fn as_static(&self) -> Option<&str> {
+        match self {
+            TmplTextNode::Static(s) => Some(s),
+            TmplTextNode::Dynamic { .. } => None,
+        }
+    }
+
+    pub fn as_expr(&self) -> Option<&TmplExpr> {
+        match self {
+            TmplTextNode::Static(_) => None,
+            TmplTextNode::Dynamic { expr, .. } => Some(expr),
+        }
+    }
+
+    /// Whether the text is static or a literal expression.
+    pub fn is_static(&self) -> bool {
+        match self {
+            TmplTextNode::Static(_) => true,
+            TmplTextNode::Dynamic { expr, .. } => expr.is_literal(),
+        }
+    }
+
+    /// The `(line, column)` range of the expression, from the first `{{` to the last `}}`,
+    /// or `None` for a static text.
+    pub fn expr_pos(&self) -> Option<((usize, usize), (usize, usize))> {
+        match self {
+            TmplTextNode::Static(_) => None,
+            TmplTextNode::Dynamic { meta, .. } => meta.pos(),
+        }
+    }
+
     pub(crate) fn new_static(content: String) -> Self {
         Self::Static(content)
     }
 
-    pub(crate) fn new_dynamic(expr: Box<TmplExpr>) -> Self {
+    pub(crate) fn new_dynamic(expr: Box<TmplExpr>, pos: TmplPosRange) -> Self {
         Self::Dynamic {
             expr,
-            binding_map_keys: None,
+            meta: TmplExprMeta::new(pos),
         }
     }
 }
@@ -3,7 +3,7 @@ use std::borrow::Cow;
 
 pub(crate) fn escape_html_text(s: &str) -> Cow<'_, str> {
     lazy_static! {
-        static ref REGEX: Regex = Regex::new("[<>&]").unwrap();
+        static ref REGEX: Regex = Regex::new("[<>&\"]").unwrap();
     }
     REGEX.replace_all(s, |caps: &Captures| match &caps[0] {
         "<" => "&lt;".to_owned(),
@@ -17,3 +17,20 @@ pub(crate) fn escape_html_text(s: &str) -> Cow<'_, str> {
 pub(crate) fn gen_lit_str(s: &str) -> String {
     format!("{:?}", s)
 }
+
+// a single-quoted string literal, which can be put in a double-quoted attribute
+pub(crate) fn gen_wxml_lit_str(s: &str) -> String {
+    let mut ret = String::from("'");
+    for c in s.chars() {
+        match c {
+            '\\' => ret += "\\\\",
+            '\'' => ret += "\\'",
+            '\n' => ret += "\\n",
+            '\r' => ret += "\\r",
+            '\t' => ret += "\\t",
+            c => ret.push(c),
+        }
+    }
+    ret.push('\'');
+    ret
+}
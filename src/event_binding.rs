@@ -0,0 +1,163 @@
+use serde_json::{json, Value};
+
+use crate::{
+    dialect::TmplDialect,
+    display_debug::TmplPrinter,
+    element::{TmplAttrKind, TmplAttrValue, TmplElement},
+    expr::TmplExprLevel,
+    tree::TmplTree,
+    visitor::{walk_element, Visitor},
+};
+
+/// The handler of an event binding.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TmplEventHandler {
+    /// A method name, like `bind:tap="onTap"`.
+    Method(String),
+    /// An expression evaluated to the method name, like `bind:tap="{{ handler }}"`,
+    /// printed with the scope names in the source.
+    Dynamic(String),
+}
+
+/// An event binding such as `catch:tap="onTap"` in a template.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TmplEventBinding {
+    pub event_name: String,
+    pub handler: TmplEventHandler,
+    /// Bound in the capture phase rather than the bubble phase.
+    pub capture: bool,
+    pub catch: bool,
+    pub mut_bind: bool,
+    /// The tag name of the element.
+    pub tag_name: String,
+    /// The `<template name>` containing the element, if any.
+    pub sub_template: Option<String>,
+    pub start_pos: (usize, usize),
+    pub end_pos: (usize, usize),
+}
+
+impl TmplEventBinding {
+    /// The attribute name in WXML, e.g. `capture-catch:tap`.
+    pub fn attr_name(&self) -> String {
+        let capture_prefix = if self.capture { "capture-" } else { "" };
+        let main_prefix = if self.catch {
+            "catch"
+        } else if self.mut_bind {
+            "mut-bind"
+        } else {
+            "bind"
+        };
+        format!("{}{}:{}", capture_prefix, main_prefix, self.event_name)
+    }
+
+    pub fn to_json(&self) -> Value {
+        let (handler, dynamic) = match &self.handler {
+            TmplEventHandler::Method(x) => (x, false),
+            TmplEventHandler::Dynamic(x) => (x, true),
+        };
+        json!({
+            "event": self.event_name,
+            "handler": handler,
+            "dynamic": dynamic,
+            "phase": if self.capture { "capture" } else { "bubble" },
+            "catch": self.catch,
+            "mutBind": self.mut_bind,
+            "tagName": self.tag_name,
+            "subTemplate": self.sub_template,
+            "start": [self.start_pos.0, self.start_pos.1],
+            "end": [self.end_pos.0, self.end_pos.1],
+        })
+    }
+}
+
+struct EventCollector<'a> {
+    printer: TmplPrinter<'a>,
+    sub_template: Option<String>,
+    bindings: Vec<TmplEventBinding>,
+}
+
+impl<'a> Visitor for EventCollector<'a> {
+    fn visit_sub_template(&mut self, name: &str, elem: &TmplElement) {
+        self.sub_template = Some(name.to_string());
+        self.visit_element(elem);
+        self.sub_template = None;
+    }
+
+    fn visit_element(&mut self, elem: &TmplElement) {
+        let scope_len = self.printer.enter_scope(elem);
+        for attr in elem.attrs.iter() {
+            if let TmplAttrKind::Event {
+                capture,
+                catch,
+                mut_bind,
+                name,
+            } = &attr.kind
+            {
+                let handler = match &attr.value {
+                    TmplAttrValue::Static(x) => TmplEventHandler::Method(x.to_string()),
+                    TmplAttrValue::Dynamic { expr, .. } => TmplEventHandler::Dynamic(
+                        self.printer.expr_string(expr, TmplExprLevel::Comma),
+                    ),
+                };
+                self.bindings.push(TmplEventBinding {
+                    event_name: name.to_string(),
+                    handler,
+                    capture: *capture,
+                    catch: *catch,
+                    mut_bind: *mut_bind,
+                    tag_name: elem.tag_name.to_string(),
+                    sub_template: self.sub_template.clone(),
+                    start_pos: elem.start_pos,
+                    end_pos: elem.end_pos,
+                });
+            }
+        }
+        walk_element(self, elem);
+        self.printer.leave_scope(scope_len);
+    }
+}
+
+/// Collect every event binding in the template, including the ones in sub templates,
+/// ordered by positions.
+pub fn collect_event_bindings(tree: &TmplTree) -> Vec<TmplEventBinding> {
+    let mut collector = EventCollector {
+        printer: TmplPrinter::new(tree, TmplDialect::Wechat),
+        sub_template: None,
+        bindings: vec![],
+    };
+    collector.visit_tree(tree);
+    let mut bindings = collector.bindings;
+    bindings.sort_by_key(|x| x.start_pos);
+    bindings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_tmpl;
+
+    #[test]
+    fn it_collects_event_bindings() {
+        let tree = parse_tmpl(
+            r#"<template name="t"><button capture-catch:tap="onT" /></template>
+<view wx:for="{{ list }}" wx:for-item="row" bind:tap="onTap" mut-bind:touchstart="{{ row.h }}" />"#,
+        )
+        .unwrap();
+        let bindings = collect_event_bindings(&tree);
+        assert_eq!(bindings.len(), 3);
+        assert_eq!(bindings[0].attr_name(), "capture-catch:tap");
+        assert_eq!(bindings[0].sub_template.as_deref(), Some("t"));
+        assert_eq!(bindings[0].tag_name, "button");
+        assert_eq!(
+            bindings[1].handler,
+            TmplEventHandler::Method("onTap".to_string())
+        );
+        assert_eq!(bindings[1].start_pos, (2, 1));
+        assert_eq!(
+            bindings[2].handler,
+            TmplEventHandler::Dynamic("row.h".to_string())
+        );
+        assert_eq!(bindings[2].to_json()["phase"], "bubble");
+        assert_eq!(bindings[2].to_json()["mutBind"], true);
+    }
+}
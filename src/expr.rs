@@ -1,7 +1,17 @@
-use crate::binding_map::{BindingMapCollector, BindingMapKeys};
-
+use crate::{
+    binding_map::{BindingMapCollector, BindingMapKeys},
+    visitor::{walk_expr_mut, VisitorMut},
+};
+
+/// An expression in `{{}}`.
+///
+/// Since the expressions are prepared after parsing, the names of `<wxs>` modules,
+/// `wx:for` items and indexes and slot values are resolved as `ScopeIndex`,
+/// and the other identifiers are the fields of the data.
 #[derive(Debug)]
-pub(crate) enum TmplExpr {
+#[non_exhaustive]
+pub enum TmplExpr {
+    /// The N-th name in the scope: the `<wxs>` modules first, then the names provided by outer elements.
     ScopeIndex(usize),
     Ident(String),
     ToStringWithoutUndefined(Box<TmplExpr>),
@@ -49,7 +59,7 @@ pub(crate) enum TmplExpr {
 }
 
 #[derive(Debug, PartialEq, PartialOrd)]
-pub(crate) enum TmplExprLevel {
+pub enum TmplExprLevel {
     Lit = 0,
     Member = 1,
     Unary = 2,
@@ -67,6 +77,19 @@ pub(crate) enum TmplExprLevel {
 }
 
 impl TmplExpr {
+    // whether the expression is a literal of a primitive value
+    pub(crate) fn is_literal(&self) -> bool {
+        matches!(
+            self,
+            TmplExpr::LitUndefined
+                | TmplExpr::LitNull
+                | TmplExpr::LitStr(_)
+                | TmplExpr::LitInt(_)
+                | TmplExpr::LitFloat(_)
+                | TmplExpr::LitBool(_)
+        )
+    }
+
     pub(crate) fn level(&self) -> TmplExprLevel {
         match self {
             TmplExpr::ScopeIndex(_) => TmplExprLevel::Member,
@@ -133,145 +156,35 @@ impl TmplExpr {
         should_disable: bool,
         bmk: &mut BindingMapKeys,
     ) {
-        match self {
-            TmplExpr::ScopeIndex(_) => {}
-            TmplExpr::Ident(x) => {
-                if let Some(n) = scope_names.iter().rposition(|n| n == x) {
-                    *self = TmplExpr::ScopeIndex(n);
-                } else if should_disable {
-                    bmc.disable_field(x);
-                } else if let Some(index) = bmc.add_field(x) {
-                    bmk.add(x, index);
-                }
-            }
-            TmplExpr::ToStringWithoutUndefined(x) => {
-                x.get_binding_map_keys_rec(bmc, scope_names, should_disable, bmk);
-            }
-
-            TmplExpr::LitUndefined => {}
-            TmplExpr::LitNull => {}
-            TmplExpr::LitStr(_) => {}
-            TmplExpr::LitInt(_) => {}
-            TmplExpr::LitFloat(_) => {}
-            TmplExpr::LitBool(_) => {}
-            TmplExpr::LitObj(x) => {
-                for x in x.iter_mut() {
-                    x.1.get_binding_map_keys_rec(bmc, scope_names, should_disable, bmk);
-                }
-            }
-            TmplExpr::LitArr(x) => {
-                for x in x.iter_mut() {
-                    x.get_binding_map_keys_rec(bmc, scope_names, should_disable, bmk);
-                }
-            }
-
-            TmplExpr::StaticMember(x, _) => {
-                x.get_binding_map_keys_rec(bmc, scope_names, should_disable, bmk);
-            }
-            TmplExpr::DynamicMember(x, y) => {
-                x.get_binding_map_keys_rec(bmc, scope_names, should_disable, bmk);
-                y.get_binding_map_keys_rec(bmc, scope_names, should_disable, bmk);
-            }
-            TmplExpr::FuncCall(x, y) => {
-                x.get_binding_map_keys_rec(bmc, scope_names, should_disable, bmk);
-                for y in y.iter_mut() {
-                    y.get_binding_map_keys_rec(bmc, scope_names, should_disable, bmk);
-                }
-            }
-
-            TmplExpr::Reverse(x) => {
-                x.get_binding_map_keys_rec(bmc, scope_names, should_disable, bmk);
-            }
-            TmplExpr::BitReverse(x) => {
-                x.get_binding_map_keys_rec(bmc, scope_names, should_disable, bmk);
-            }
-            TmplExpr::Positive(x) => {
-                x.get_binding_map_keys_rec(bmc, scope_names, should_disable, bmk);
-            }
-            TmplExpr::Negative(x) => {
-                x.get_binding_map_keys_rec(bmc, scope_names, should_disable, bmk);
-            }
-
-            TmplExpr::Multiply(x, y) => {
-                x.get_binding_map_keys_rec(bmc, scope_names, should_disable, bmk);
-                y.get_binding_map_keys_rec(bmc, scope_names, should_disable, bmk);
-            }
-            TmplExpr::Divide(x, y) => {
-                x.get_binding_map_keys_rec(bmc, scope_names, should_disable, bmk);
-                y.get_binding_map_keys_rec(bmc, scope_names, should_disable, bmk);
-            }
-            TmplExpr::Mod(x, y) => {
-                x.get_binding_map_keys_rec(bmc, scope_names, should_disable, bmk);
-                y.get_binding_map_keys_rec(bmc, scope_names, should_disable, bmk);
-            }
-            TmplExpr::Plus(x, y) => {
-                x.get_binding_map_keys_rec(bmc, scope_names, should_disable, bmk);
-                y.get_binding_map_keys_rec(bmc, scope_names, should_disable, bmk);
-            }
-            TmplExpr::Minus(x, y) => {
-                x.get_binding_map_keys_rec(bmc, scope_names, should_disable, bmk);
-                y.get_binding_map_keys_rec(bmc, scope_names, should_disable, bmk);
-            }
-
-            TmplExpr::Lt(x, y) => {
-                x.get_binding_map_keys_rec(bmc, scope_names, should_disable, bmk);
-                y.get_binding_map_keys_rec(bmc, scope_names, should_disable, bmk);
-            }
-            TmplExpr::Gt(x, y) => {
-                x.get_binding_map_keys_rec(bmc, scope_names, should_disable, bmk);
-                y.get_binding_map_keys_rec(bmc, scope_names, should_disable, bmk);
-            }
-            TmplExpr::Lte(x, y) => {
-                x.get_binding_map_keys_rec(bmc, scope_names, should_disable, bmk);
-                y.get_binding_map_keys_rec(bmc, scope_names, should_disable, bmk);
-            }
-            TmplExpr::Gte(x, y) => {
-                x.get_binding_map_keys_rec(bmc, scope_names, should_disable, bmk);
-                y.get_binding_map_keys_rec(bmc, scope_names, should_disable, bmk);
-            }
-            TmplExpr::Eq(x, y) => {
-                x.get_binding_map_keys_rec(bmc, scope_names, should_disable, bmk);
-                y.get_binding_map_keys_rec(bmc, scope_names, should_disable, bmk);
-            }
-            TmplExpr::Ne(x, y) => {
-                x.get_binding_map_keys_rec(bmc, scope_names, should_disable, bmk);
-                y.get_binding_map_keys_rec(bmc, scope_names, should_disable, bmk);
-            }
-            TmplExpr::EqFull(x, y) => {
-                x.get_binding_map_keys_rec(bmc, scope_names, should_disable, bmk);
-                y.get_binding_map_keys_rec(bmc, scope_names, should_disable, bmk);
-            }
-            TmplExpr::NeFull(x, y) => {
-                x.get_binding_map_keys_rec(bmc, scope_names, should_disable, bmk);
-                y.get_binding_map_keys_rec(bmc, scope_names, should_disable, bmk);
-            }
+        let mut visitor = BindingMapKeysVisitor {
+            bmc,
+            scope_names,
+            should_disable,
+            bmk,
+        };
+        visitor.visit_expr_mut(self);
+    }
+}
 
-            TmplExpr::BitAnd(x, y) => {
-                x.get_binding_map_keys_rec(bmc, scope_names, should_disable, bmk);
-                y.get_binding_map_keys_rec(bmc, scope_names, should_disable, bmk);
-            }
-            TmplExpr::BitXor(x, y) => {
-                x.get_binding_map_keys_rec(bmc, scope_names, should_disable, bmk);
-                y.get_binding_map_keys_rec(bmc, scope_names, should_disable, bmk);
-            }
-            TmplExpr::BitOr(x, y) => {
-                x.get_binding_map_keys_rec(bmc, scope_names, should_disable, bmk);
-                y.get_binding_map_keys_rec(bmc, scope_names, should_disable, bmk);
-            }
-            TmplExpr::LogicAnd(x, y) => {
-                x.get_binding_map_keys_rec(bmc, scope_names, should_disable, bmk);
-                y.get_binding_map_keys_rec(bmc, scope_names, should_disable, bmk);
-            }
-            TmplExpr::LogicOr(x, y) => {
-                x.get_binding_map_keys_rec(bmc, scope_names, should_disable, bmk);
-                y.get_binding_map_keys_rec(bmc, scope_names, should_disable, bmk);
-            }
+struct BindingMapKeysVisitor<'a> {
+    bmc: &'a mut BindingMapCollector,
+    scope_names: &'a Vec<String>,
+    should_disable: bool,
+    bmk: &'a mut BindingMapKeys,
+}
 
-            TmplExpr::Cond(x, y, z) => {
-                x.get_binding_map_keys_rec(bmc, scope_names, should_disable, bmk);
-                y.get_binding_map_keys_rec(bmc, scope_names, should_disable, bmk);
-                z.get_binding_map_keys_rec(bmc, scope_names, should_disable, bmk);
-            }
-        };
+impl<'a> VisitorMut for BindingMapKeysVisitor<'a> {
+    fn visit_expr_mut(&mut self, expr: &mut TmplExpr) {
+        if let TmplExpr::Ident(x) = expr {
+            if let Some(n) = self.scope_names.iter().rposition(|n| n == x) {
+                *expr = TmplExpr::ScopeIndex(n);
+            } else if self.should_disable {
+                self.bmc.disable_field(x);
+            } else if let Some(index) = self.bmc.add_field(x) {
+                self.bmk.add(x, index);
+            }
+            return;
+        }
+        walk_expr_mut(self, expr);
     }
 }
@@ -1,12 +1,29 @@
 use std::collections::HashMap;
 
-use crate::{parse_tmpl, tree::TmplTree, TmplParseError};
+use crate::{
+    complete::{complete_in, TmplCompletion},
+    component_config::{check_components, TmplComponentConfig},
+    component_property::TmplComponentProperties,
+    data_path::collect_data_paths,
+    event_binding::collect_event_bindings,
+    parser::{parse_tmpl_with_transform, TmplParseOptions},
+    path,
+    transform::{TmplTransform, TmplTransformContext},
+    tree::TmplTree,
+    type_check::check_data_types,
+    DataType, TmplDiagnostic, TmplParseError,
+};
 
 pub struct TmplGroup {
     pub(crate) trees: HashMap<String, TmplTree>,
     pub(crate) scripts: HashMap<String, String>,
     pub(crate) has_scripts: bool,
     pub(crate) extra_runtime_string: String,
+    pub(crate) transforms: Vec<Box<dyn TmplTransform>>,
+    pub(crate) data_types: HashMap<String, DataType>,
+    pub(crate) parse_options: TmplParseOptions,
+    pub(crate) component_configs: HashMap<String, TmplComponentConfig>,
+    pub(crate) component_properties: HashMap<String, TmplComponentProperties>,
 }
 
 impl TmplGroup {
@@ -16,12 +33,44 @@ impl TmplGroup {
             scripts: HashMap::new(),
             has_scripts: false,
             extra_runtime_string: String::new(),
+            transforms: vec![],
+            data_types: HashMap::new(),
+            parse_options: TmplParseOptions::default(),
+            component_configs: HashMap::new(),
+            component_properties: HashMap::new(),
         }
     }
 
+    /// Set the parser options, which apply to the templates added after it.
+    pub fn set_parse_options(&mut self, options: TmplParseOptions) {
+        self.parse_options = options;
+    }
+
+    /// Add a transform plugin, which applies to the templates added after it.
+    ///
+    /// The transforms run in the order they are added.
+    pub fn add_transform(&mut self, transform: impl TmplTransform + 'static) {
+        self.transforms.push(Box::new(transform));
+    }
+
     pub fn add_tmpl(&mut self, path: &str, tmpl_str: &str) -> Result<(), TmplParseError> {
-        let mut tmpl = parse_tmpl(tmpl_str)?;
-        tmpl.path = path.to_string();
+        let transforms = &mut self.transforms;
+        let mut tmpl = parse_tmpl_with_transform(tmpl_str, &self.parse_options, |tree| {
+            tree.path = path.to_string();
+            for diagnostic in tree.diagnostics.iter_mut() {
+                diagnostic.path = path.to_string();
+            }
+            for transform in transforms.iter_mut() {
+                let mut ctx = TmplTransformContext::new(path);
+                transform.transform(tree, &mut ctx);
+                tree.diagnostics.append(&mut ctx.diagnostics);
+            }
+        })?;
+        let diagnostics = std::mem::take(&mut tmpl.diagnostics);
+        tmpl.diagnostics = diagnostics
+            .into_iter()
+            .filter(|x| !tmpl.is_suppressed(x))
+            .collect();
         if tmpl.get_inline_script_module_name().len() > 0 {
             self.has_scripts = true;
         }
@@ -47,4 +96,114 @@ impl TmplGroup {
     pub fn contains_template(&self, path: &str) -> bool {
         self.trees.contains_key(path)
     }
+
+    pub fn get_tree(&self, path: &str) -> Option<&TmplTree> {
+        self.trees.get(path)
+    }
+
+    /// Iterate over the templates in the group, in no particular order.
+    pub fn trees(&self) -> impl Iterator<Item = &TmplTree> {
+        self.trees.values()
+    }
+
+    // the template path defining the `<template name>` visible in the template `path`,
+    // which is itself or one of its imports
+    pub(crate) fn find_template(&self, path: &str, name: &str) -> Option<String> {
+        let tree = self.trees.get(path)?;
+        if tree.sub_templates.contains_key(name) {
+            return Some(path.to_string());
+        }
+        tree.imports
+            .iter()
+            .rev()
+            .map(|x| path::resolve(path, x))
+            .find(|x| {
+                self.trees
+                    .get(x)
+                    .is_some_and(|x| x.sub_templates.contains_key(name))
+            })
+    }
+
+    /// Check the expressions in the template `path` against the type of its page `data`.
+    ///
+    /// Returns `None` if the template does not exist.
+    pub fn check_data_types(&self, path: &str, data: &DataType) -> Option<Vec<TmplDiagnostic>> {
+        self.trees
+            .get(path)
+            .map(|tree| check_data_types(tree, data))
+    }
+
+    /// Set the type of the page `data` of the template `path`, which is checked when linting.
+    pub fn set_data_type(&mut self, path: &str, data: DataType) {
+        self.data_types.insert(path.to_string(), data);
+    }
+
+    /// Set the `.json` config of the page or component `path`, which is checked when linting.
+    ///
+    /// The config of `app` applies to every template, like the global `usingComponents` in `app.json`.
+    pub fn set_component_config(&mut self, path: &str, config: TmplComponentConfig) {
+        self.component_configs.insert(path.to_string(), config);
+    }
+
+    /// Set the property definitions of the component `path`,
+    /// which are checked against the attributes of the component tags.
+    pub fn set_component_properties(&mut self, path: &str, properties: TmplComponentProperties) {
+        self.component_properties
+            .insert(path.to_string(), properties);
+    }
+
+    /// Check the component tags, the `generic:` attributes and the component properties
+    /// in the template `path` against its `.json` config.
+    ///
+    /// Returns `None` if the template or its config does not exist.
+    pub fn check_components(&self, path: &str) -> Option<Vec<TmplDiagnostic>> {
+        let tree = self.trees.get(path)?;
+        let config = self.component_configs.get(path)?;
+        Some(check_components(self, tree, config))
+    }
+
+    /// Get the completion candidates at the byte `offset` of the `source` of the template `path`,
+    /// including the imported templates, the registered components and the template paths.
+    ///
+    /// Returns `None` if the template does not exist. See `complete` for details.
+    pub fn complete(&self, path: &str, source: &str, offset: usize) -> Option<TmplCompletion> {
+        let tree = self.trees.get(path)?;
+        Some(complete_in(Some(self), tree, source, offset))
+    }
+
+    /// Get the data paths read by each template, as a JSON object keyed by template paths.
+    pub fn data_paths_json(&self) -> String {
+        let ret: serde_json::Map<String, serde_json::Value> = self
+            .trees
+            .iter()
+            .map(|(path, tree)| (path.to_string(), collect_data_paths(tree).to_json()))
+            .collect();
+        serde_json::Value::Object(ret).to_string()
+    }
+
+    /// Get the event bindings in each template, as a JSON object keyed by template paths.
+    pub fn event_bindings_json(&self) -> String {
+        let ret: serde_json::Map<String, serde_json::Value> = self
+            .trees
+            .iter()
+            .map(|(path, tree)| {
+                let bindings = collect_event_bindings(tree)
+                    .iter()
+                    .map(|x| x.to_json())
+                    .collect();
+                (path.to_string(), serde_json::Value::Array(bindings))
+            })
+            .collect();
+        serde_json::Value::Object(ret).to_string()
+    }
+
+    /// Get the diagnostics reported while adding the templates, ordered by template paths.
+    pub fn diagnostics(&self) -> Vec<&TmplDiagnostic> {
+        let mut paths: Vec<&String> = self.trees.keys().collect();
+        paths.sort();
+        paths
+            .into_iter()
+            .flat_map(|path| self.trees[path].diagnostics.iter())
+            .collect()
+    }
 }
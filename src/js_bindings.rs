@@ -16,3 +16,35 @@ pub fn parse_tmpl(tmpl_str: &str) -> Result<String, JsError> {
 
     Ok(ret_str)
 }
+
+#[wasm_bindgen(js_name="getDataPaths")]
+pub fn get_data_paths(tmpl_str: &str) -> Result<String, JsError> {
+    let tmpl = crate::parser::parse_tmpl(tmpl_str)?;
+    Ok(crate::collect_data_paths(&tmpl).to_json().to_string())
+}
+
+#[wasm_bindgen(js_name="getEventBindings")]
+pub fn get_event_bindings(tmpl_str: &str) -> Result<String, JsError> {
+    let tmpl = crate::parser::parse_tmpl(tmpl_str)?;
+    let bindings: Vec<_> = crate::collect_event_bindings(&tmpl).iter().map(|x| x.to_json()).collect();
+    Ok(serde_json::Value::Array(bindings).to_string())
+}
+
+// the offsets in js strings count UTF-16 code units
+#[wasm_bindgen(js_name="getCompletions")]
+pub fn get_completions(tmpl_str: &str, offset: usize) -> String {
+    let tree = crate::parser::parse_tmpl(tmpl_str).unwrap_or_else(|_| crate::tree::TmplTree::new());
+    let mut byte_offset = tmpl_str.len();
+    let mut utf16 = 0;
+    for (i, c) in tmpl_str.char_indices() {
+        if utf16 >= offset {
+            byte_offset = i;
+            break;
+        }
+        utf16 += c.len_utf16();
+    }
+    let ret = crate::complete(&tree, tmpl_str, byte_offset);
+    let mut json = ret.to_json();
+    json["start"] = tmpl_str[..ret.start].encode_utf16().count().into();
+    json.to_string()
+}
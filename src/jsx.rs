@@ -0,0 +1,472 @@
+use std::collections::HashMap;
+
+use crate::{
+    diagnostic::{TmplDiagnostic, TmplPosRange},
+    dialect::TmplDialect,
+    display_debug::{text_segments, TmplPrinter},
+    element::{
+        TmplAttr, TmplAttrKind, TmplAttrValue, TmplElement, TmplNode, TmplTextNode, TmplVirtualType,
+    },
+    escape::{escape_html_text, gen_wxml_lit_str},
+    expr::{TmplExpr, TmplExprLevel},
+    lowering::{
+        component_name, component_names, event_prop, for_key, is_ident, style_object,
+        UnsupportedReporter, MAP_LIST,
+    },
+    tree::TmplTree,
+    utils::camel_to_dash,
+};
+
+/// Print the template as React render functions in JSX, or TSX if `typescript` is set.
+///
+/// The root is exported as the default `render(data)` function,
+/// and each `<template name>` is exported as a function component.
+/// The data fields and the event handlers are read from `data`.
+/// The constructs without an equivalent in JSX are reported as `unsupported-in-jsx` warnings.
+pub fn print_tmpl_as_jsx(tree: &TmplTree, typescript: bool) -> (String, Vec<TmplDiagnostic>) {
+    let mut printer = JsxPrinter {
+        printer: TmplPrinter::new(tree, TmplDialect::Wechat),
+        reporter: UnsupportedReporter::new("unsupported-in-jsx", "JSX", &tree.path),
+        uses_fragment: false,
+        uses_map_list: false,
+        components: component_names(tree),
+    };
+    printer.printer.data_var = Some("data");
+    printer.printer.reserved_names = Some(&["data", "e", "mapList"]);
+    printer.printer.js_interpolation = true;
+    printer.reporter.report_imports_and_scripts(tree);
+    let param = if typescript { "data: any" } else { "data" };
+    let mut out = String::new();
+    let mut names: Vec<&String> = tree.sub_templates.keys().collect();
+    names.sort();
+    for name in names {
+        let body = printer.children_expr(&tree.sub_templates[name]);
+        out += &format!(
+            "export function {}({}) {{\n  return {};\n}}\n\n",
+            printer.components[name.as_str()],
+            param,
+            body
+        );
+    }
+    out += &format!(
+        "export default function render({}) {{\n  return {};\n}}\n",
+        param,
+        printer.children_expr(&tree.root)
+    );
+    if printer.uses_map_list {
+        out += &if typescript {
+            MAP_LIST.replacen(
+                "(list, f)",
+                "(list: any, f: (item: any, index: any) => any): any[]",
+                1,
+            )
+        } else {
+            MAP_LIST.to_string()
+        };
+    }
+    if printer.uses_fragment {
+        out = format!("import {{ Fragment }} from \"react\";\n\n{}", out);
+    }
+    (out, printer.reporter.diagnostics)
+}
+
+// escape the JSX text, in which `{` and `}` start expressions
+fn jsx_text(s: &str) -> String {
+    let mut ret = String::new();
+    for c in escape_html_text(s).chars() {
+        match c {
+            '{' => ret += "{'{'}",
+            '}' => ret += "{'}'}",
+            c => ret.push(c),
+        }
+    }
+    ret
+}
+
+struct JsxPrinter<'a> {
+    printer: TmplPrinter<'a>,
+    reporter: UnsupportedReporter<'a>,
+    uses_fragment: bool,
+    uses_map_list: bool,
+    // the component names of the sub templates
+    components: HashMap<&'a str, String>,
+}
+
+impl<'a> JsxPrinter<'a> {
+    fn expr(&self, expr: &TmplExpr, level: TmplExprLevel) -> String {
+        self.printer.expr_string(expr, level)
+    }
+
+    fn value_expr(&self, value: &TmplAttrValue, level: TmplExprLevel) -> String {
+        match value {
+            TmplAttrValue::Static(s) => gen_wxml_lit_str(s),
+            TmplAttrValue::Dynamic { expr, .. } => self.expr(expr, level),
+        }
+    }
+
+    // the JSX expression of the children, e.g. a single element or a fragment
+    fn children_expr(&mut self, elem: &TmplElement) -> String {
+        match elem.children.as_slice() {
+            [] => "null".to_string(),
+            [TmplNode::Element(child)] => self.node_expr(child),
+            _ => {
+                let mut out = "<>".to_string();
+                self.write_children(elem, &mut out);
+                out += "</>";
+                out
+            }
+        }
+    }
+
+    fn write_children(&mut self, elem: &TmplElement, out: &mut String) {
+        for child in elem.children.iter() {
+            match child {
+                TmplNode::TextNode(TmplTextNode::Static(s)) => *out += &jsx_text(s),
+                TmplNode::TextNode(TmplTextNode::Dynamic { expr, .. }) => {
+                    match text_segments(expr) {
+                        Some(segs) => {
+                            for seg in segs {
+                                match seg {
+                                    TmplExpr::LitStr(s) => *out += &jsx_text(s),
+                                    TmplExpr::ToStringWithoutUndefined(x) => {
+                                        *out +=
+                                            &format!("{{{}}}", self.expr(x, TmplExprLevel::Cond))
+                                    }
+                                    _ => unreachable!(),
+                                }
+                            }
+                        }
+                        None => *out += &format!("{{{}}}", self.expr(expr, TmplExprLevel::Cond)),
+                    }
+                }
+                TmplNode::Comment(x) => {
+                    if !x.content.contains("*/") {
+                        *out += &format!("{{/*{}*/}}", x.content);
+                    }
+                }
+                TmplNode::Element(x) => {
+                    let s = self.node_expr(x);
+                    if s.starts_with('<') {
+                        *out += &s;
+                    } else if s != "null" {
+                        *out += &format!("{{{}}}", s);
+                    }
+                }
+            }
+        }
+    }
+
+    // a branch of a ternary expression
+    fn branch_expr(&mut self, elem: &TmplElement) -> String {
+        let s = self.children_expr(elem);
+        if s.starts_with('<') || s == "null" {
+            s
+        } else {
+            format!("({})", s)
+        }
+    }
+
+    // the JSX expression of an element, which may be a ternary expression or a `.map` call
+    fn node_expr(&mut self, elem: &TmplElement) -> String {
+        let pos = (elem.start_pos, elem.end_pos);
+        match &elem.virtual_type {
+            TmplVirtualType::None | TmplVirtualType::Pure => self.element_expr(elem, None),
+            TmplVirtualType::IfGroup => {
+                let mut out = String::new();
+                let mut has_else = false;
+                for branch in elem.children.iter().filter_map(|x| x.as_element()) {
+                    match &branch.virtual_type {
+                        TmplVirtualType::If { cond } | TmplVirtualType::Elif { cond } => {
+                            out += &format!(
+                                "{} ? {} : ",
+                                self.value_expr(cond, TmplExprLevel::LogicOr),
+                                self.branch_expr(branch)
+                            );
+                        }
+                        _ => {
+                            out += &self.branch_expr(branch);
+                            has_else = true;
+                            break;
+                        }
+                    }
+                }
+                if !has_else {
+                    out += "null";
+                }
+                out
+            }
+            TmplVirtualType::If { cond } | TmplVirtualType::Elif { cond } => format!(
+                "{} ? {} : null",
+                self.value_expr(cond, TmplExprLevel::LogicOr),
+                self.branch_expr(elem)
+            ),
+            TmplVirtualType::Else => self.children_expr(elem),
+            TmplVirtualType::For {
+                list,
+                item_name,
+                index_name,
+                key,
+            } => {
+                let list = self.value_expr(list, TmplExprLevel::Cond);
+                let scope_len = self.printer.enter_scope(elem);
+                let names = self.printer.entered_scope_names(scope_len);
+                let item_name = names.first().unwrap_or(item_name).clone();
+                let index_name = names.get(1).unwrap_or(index_name).clone();
+                let key = for_key(key.as_deref(), &item_name, &index_name);
+                let body = match elem.children.as_slice() {
+                    [TmplNode::Element(child)]
+                        if matches!(
+                            child.virtual_type,
+                            TmplVirtualType::None | TmplVirtualType::Pure
+                        ) =>
+                    {
+                        self.element_expr(child, Some(&key))
+                    }
+                    _ => {
+                        self.uses_fragment = true;
+                        let mut out = format!("<Fragment key={{{}}}>", key);
+                        self.write_children(elem, &mut out);
+                        out += "</Fragment>";
+                        out
+                    }
+                };
+                self.printer.leave_scope(scope_len);
+                self.uses_map_list = true;
+                format!(
+                    "mapList({}, ({}, {}) => {})",
+                    list, item_name, index_name, body
+                )
+            }
+            TmplVirtualType::TemplateRef { target, data } => {
+                let name = match target {
+                    TmplAttrValue::Static(name) => match self.components.get(name.as_str()) {
+                        Some(x) => x.clone(),
+                        None => component_name(name),
+                    },
+                    TmplAttrValue::Dynamic { .. } => {
+                        self.reporter
+                            .report("<template is> with a dynamic name", pos);
+                        return "null".to_string();
+                    }
+                };
+                match data {
+                    TmplAttrValue::Dynamic { expr, .. } => match &**expr {
+                        TmplExpr::LitObj(x) if x.is_empty() => format!("<{} />", name),
+                        _ => format!(
+                            "<{} {{...{}}} />",
+                            name,
+                            self.expr(expr, TmplExprLevel::Cond)
+                        ),
+                    },
+                    TmplAttrValue::Static(_) => {
+                        self.reporter.report("<template is> with static data", pos);
+                        format!("<{} />", name)
+                    }
+                }
+            }
+            TmplVirtualType::Include { path } => {
+                self.reporter
+                    .report(&format!(r#"<include src="{}">"#, path), pos);
+                "null".to_string()
+            }
+            TmplVirtualType::Slot { name, props } => {
+                let is_default = matches!(name, TmplAttrValue::Static(x) if x.is_empty());
+                if !is_default || props.as_ref().map(|x| x.len()).unwrap_or(0) > 0 {
+                    self.reporter
+                        .report("<slot> with a name or properties", pos);
+                    return "null".to_string();
+                }
+                "data.children".to_string()
+            }
+        }
+    }
+
+    fn element_expr(&mut self, elem: &TmplElement, key: Option<&str>) -> String {
+        let pos = (elem.start_pos, elem.end_pos);
+        if let TmplVirtualType::Pure = elem.virtual_type {
+            let mut out = match key {
+                Some(key) => {
+                    self.uses_fragment = true;
+                    format!("<Fragment key={{{}}}>", key)
+                }
+                None => "<>".to_string(),
+            };
+            self.write_children(elem, &mut out);
+            out += if key.is_some() { "</Fragment>" } else { "</>" };
+            return out;
+        }
+        let mut attr_string = String::new();
+        if let Some(key) = key {
+            attr_string += &format!(" key={{{}}}", key);
+        }
+        for attr in elem.attrs.iter() {
+            self.write_attr(attr, &elem.tag_name, pos, &mut attr_string);
+        }
+        match &elem.slot {
+            None => {}
+            Some(TmplAttrValue::Static(x)) => {
+                attr_string += &format!(r#" slot="{}""#, escape_html_text(x))
+            }
+            Some(x) => {
+                attr_string += &format!(" slot={{{}}}", self.value_expr(x, TmplExprLevel::Cond))
+            }
+        }
+        for (capture_name, _) in elem.slot_values.iter() {
+            self.reporter
+                .report(&format!("slot:{}", camel_to_dash(capture_name)), pos);
+        }
+        if let Some(generics) = &elem.generics {
+            let mut list: Vec<&String> = generics.keys().collect();
+            list.sort();
+            for k in list {
+                self.reporter.report(&format!("generic:{}", k), pos);
+            }
+        }
+        if elem.children.is_empty() {
+            return format!("<{}{} />", elem.tag_name, attr_string);
+        }
+        let mut out = format!("<{}{}>", elem.tag_name, attr_string);
+        self.write_children(elem, &mut out);
+        out += &format!("</{}>", elem.tag_name);
+        out
+    }
+
+    // write an attribute with a leading space
+    fn write_attr(&mut self, attr: &TmplAttr, tag_name: &str, pos: TmplPosRange, out: &mut String) {
+        let name = match &attr.kind {
+            TmplAttrKind::Id => "id".to_string(),
+            TmplAttrKind::Class => "className".to_string(),
+            TmplAttrKind::Style => {
+                match style_object(&self.printer, &attr.value) {
+                    Some(x) => *out += &format!(" style={{{}}}", x),
+                    None => self
+                        .reporter
+                        .report("style with dynamic property names", pos),
+                }
+                return;
+            }
+            TmplAttrKind::Slot => "slot".to_string(),
+            TmplAttrKind::PropertyOrExternalClass { name } => name.to_string(),
+            TmplAttrKind::Data { name } => format!("data-{}", camel_to_dash(name)),
+            TmplAttrKind::ModelProperty { name } => {
+                self.reporter.report(&format!("model:{}", name), pos);
+                name.to_string()
+            }
+            TmplAttrKind::Event {
+                capture,
+                catch,
+                mut_bind,
+                name,
+            } => {
+                if *mut_bind {
+                    self.reporter.report(&format!("mut-bind:{}", name), pos);
+                }
+                // the handler is a method name in WXML
+                let handler = match &attr.value {
+                    TmplAttrValue::Static(x) if is_ident(x) => format!("data.{}", x),
+                    TmplAttrValue::Static(x) => format!("data[{}]", gen_wxml_lit_str(x)),
+                    TmplAttrValue::Dynamic { expr, .. } => self.expr(expr, TmplExprLevel::Member),
+                };
+                let handler = if *catch {
+                    format!("(e) => {{ e.stopPropagation(); {}(e); }}", handler)
+                } else {
+                    handler
+                };
+                match event_prop(tag_name, name, *capture) {
+                    Some(prop) => *out += &format!(" {}={{{}}}", prop, handler),
+                    None => self.reporter.report(&format!("bind:{}", name), pos),
+                }
+                return;
+            }
+            // the WXML-only attributes, which are reported above
+            _ => return,
+        };
+        match &attr.value {
+            TmplAttrValue::Static(x) => *out += &format!(r#" {}="{}""#, name, escape_html_text(x)),
+            TmplAttrValue::Dynamic { expr, .. } => match &**expr {
+                TmplExpr::LitBool(true) => *out += &format!(" {}", name),
+                _ => *out += &format!(" {}={{{}}}", name, self.expr(expr, TmplExprLevel::Cond)),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_tmpl;
+
+    #[test]
+    fn it_prints_jsx() {
+        let tree = parse_tmpl(
+            r#"<template name="card-item"><text>{{ title }}</text></template><view wx:for="{{ list }}" wx:key="id" class="a {{ item.c }}" style="color: red; width: {{ w }}px" bind:tap="onTap" catch:touchstart="onStart">#{{ index }}</view><view wx:if="{{ a }}">1</view><block wx:elif="{{ b }}"><text>2</text><text>3</text></block><view wx:else hidden /><template is="card-item" data="{{ title: 'x' }}" />"#,
+        )
+        .unwrap();
+        let (code, diagnostics) = print_tmpl_as_jsx(&tree, true);
+        assert_eq!(
+            code,
+            r#"export function CardItem(data: any) {
+  return <text>{data.title}</text>;
+}
+
+export default function render(data: any) {
+  return <>{mapList(data.list, (item, index) => <view key={item.id} className={'a '+(item.c??'')} style={{color:'red',width:(data.w??'')+'px'}} onClick={data.onTap} onTouchStart={(e) => { e.stopPropagation(); data.onStart(e); }}>#{index}</view>)}{data.a ? <view>1</view> : data.b ? <><text>2</text><text>3</text></> : <view hidden />}<CardItem {...{title:'x'}} /></>;
+}
+
+function mapList(list: any, f: (item: any, index: any) => any): any[] {
+  if (Array.isArray(list)) return list.map(f);
+  if (typeof list === "string") return Array.from(list, f);
+  if (list && typeof list === "object") return Object.keys(list).map((k) => f(list[k], k));
+  return [];
+}
+"#
+        );
+        assert!(diagnostics.is_empty());
+
+        // the scope names shadowing the identifiers of the render function are renamed
+        let tree = parse_tmpl(
+            r#"<view wx:for="{{ rows }}" wx:for-item="data" wx:for-index="i" wx:key="*this">{{ data.x }}{{ i }}</view>"#,
+        )
+        .unwrap();
+        let (code, _) = print_tmpl_as_jsx(&tree, false);
+        assert!(code.contains("mapList(data.rows, ($0, i) => <view key={$0}>{$0.x}{i}</view>)"));
+
+        // the interpolations are empty for `undefined` and `null` like WXML
+        let tree = parse_tmpl(r#"<view title="{{ a || b }} x" />"#).unwrap();
+        let (code, _) = print_tmpl_as_jsx(&tree, false);
+        assert!(code.contains("<view title={((data.a||data.b)??'')+' x'} />"));
+
+        let tree = parse_tmpl(r#"<template is="{{ name }}" />"#).unwrap();
+        let (_, diagnostics) = print_tmpl_as_jsx(&tree, false);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, "unsupported-in-jsx");
+
+        let tree =
+            parse_tmpl(r#"<scroll-view bind:longpress="f" bind:scrolltolower="g" /><comp bind:item-tap="h" />"#)
+                .unwrap();
+        let (code, diagnostics) = print_tmpl_as_jsx(&tree, false);
+        assert!(code.contains("return <><scroll-view /><comp onItemTap={data.h} /></>;"));
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(
+            diagnostics[1].message,
+            "`bind:scrolltolower` has no equivalent in JSX"
+        );
+    }
+
+    #[test]
+    fn it_renames_colliding_components() {
+        let tree = parse_tmpl(
+            r#"<template name="fragment"><text>{{ a }}</text></template><template name="card-item"><text>{{ b }}</text></template><template name="cardItem"><text>{{ c }}</text></template><template is="fragment" /><template is="card-item" /><template is="cardItem" />"#,
+        )
+        .unwrap();
+        let (code, _) = print_tmpl_as_jsx(&tree, false);
+        assert!(
+            code.contains("export function Fragment$1(data) {\n  return <text>{data.a}</text>;")
+        );
+        assert!(code.contains("export function CardItem(data) {\n  return <text>{data.b}</text>;"));
+        assert!(
+            code.contains("export function CardItem$1(data) {\n  return <text>{data.c}</text>;")
+        );
+        assert!(code.contains("return <><Fragment$1 /><CardItem /><CardItem$1 /></>;"));
+    }
+}
@@ -2,21 +2,66 @@
 extern crate lazy_static;
 
 mod binding_map;
+mod builtin_component;
+mod codegen;
+mod codemod;
+mod complete;
+mod component_config;
+mod component_property;
 mod convert_tree;
+mod cst;
+mod data_path;
+mod diagnostic;
+mod dialect;
 mod display_debug;
 mod element;
 mod entities;
 mod escape;
+mod event_binding;
 mod expr;
 mod group;
 mod js_bindings;
+mod jsx;
+mod lint;
+mod lowering;
+mod lsp;
 mod parse_segment;
 mod parse_text_entity;
 mod parser;
 mod path;
+mod rename;
+mod transform;
 mod tree;
+mod type_check;
 mod utils;
+mod visitor;
+mod vue;
 
+pub use builtin_component::*;
+pub use codegen::*;
+pub use codemod::*;
+pub use complete::*;
+pub use component_config::*;
+pub use component_property::*;
+pub use cst::*;
+pub use data_path::*;
+pub use diagnostic::*;
+pub use dialect::*;
+pub use element::{
+    TmplAttr, TmplAttrKind, TmplAttrValue, TmplComment, TmplDescendants, TmplElement, TmplExprMeta,
+    TmplNode, TmplScript, TmplTextNode, TmplVirtualType,
+};
+pub use event_binding::*;
+pub use expr::TmplExpr;
 pub use group::*;
 pub use js_bindings::*;
+pub use jsx::*;
+pub use lint::*;
+pub use lsp::*;
 pub use parser::*;
+pub use rename::*;
+pub use transform::*;
+pub use tree::TmplTree;
+pub use type_check::*;
+pub use visitor::*;
+pub use vue::*;
@@ -0,0 +1,549 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    builtin_component::{builtin_component, COMMON_ATTRS},
+    diagnostic::{TmplDiagnostic, TmplDiagnosticLevel, TmplPosRange},
+    element::{TmplAttrKind, TmplAttrValue, TmplElement, TmplVirtualType},
+    expr::TmplExpr,
+    path,
+    tree::TmplTree,
+    type_check::check_data_types,
+    visitor::{walk_virtual_type, Visitor},
+    TmplGroup,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LintLevel {
+    Off,
+    Warning,
+    Error,
+}
+
+impl LintLevel {
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "off" => Some(LintLevel::Off),
+            "warn" | "warning" => Some(LintLevel::Warning),
+            "error" => Some(LintLevel::Error),
+            _ => None,
+        }
+    }
+}
+
+/// The built-in lint rules with their default levels.
+///
+/// The diagnostics of the type checker and the transform plugins can also be configured by their codes.
+pub const LINT_RULES: &[(&str, LintLevel)] = &[
+    ("unclosed-tag", LintLevel::Error),
+    ("for-without-key", LintLevel::Warning),
+    ("for-key-unknown-field", LintLevel::Error),
+    ("duplicate-id", LintLevel::Warning),
+    ("unused-template", LintLevel::Warning),
+    ("unused-import", LintLevel::Warning),
+    ("if-with-for", LintLevel::Warning),
+    ("unknown-directive", LintLevel::Error),
+    ("unknown-component", LintLevel::Error),
+    ("unknown-generic", LintLevel::Error),
+    ("unresolved-generic-default", LintLevel::Warning),
+    ("unknown-property", LintLevel::Error),
+    ("property-type-mismatch", LintLevel::Error),
+    ("model-not-assignable", LintLevel::Error),
+    ("unknown-attribute", LintLevel::Warning),
+    ("invalid-attribute-value", LintLevel::Warning),
+    ("unknown-event", LintLevel::Warning),
+    ("deprecated-component", LintLevel::Warning),
+    ("deprecated-attribute", LintLevel::Warning),
+];
+
+// the codes of the type checker diagnostics which are not in `LINT_RULES`
+const TYPE_CHECK_CODES: &[&str] = &[
+    "unknown-field",
+    "for-non-array",
+    "incompatible-comparison",
+    "not-callable",
+];
+
+#[derive(Debug, Clone, Default)]
+pub struct LintConfig {
+    rules: HashMap<String, LintLevel>,
+}
+
+impl LintConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read a config file like `{ "rules": { "for-without-key": "off", "duplicate-id": "error" } }`.
+    ///
+    /// The levels are `off`, `warning` (or `warn`) and `error`.
+    /// The rules should be the built-in rules or the codes of the type checker,
+    /// while the codes of the transform plugins are configured with `set_level`.
+    pub fn from_json(s: &str) -> Result<Self, String> {
+        let value: serde_json::Value = serde_json::from_str(s).map_err(|e| e.to_string())?;
+        let mut ret = Self::new();
+        if let Some(rules) = value.get("rules") {
+            let rules = rules
+                .as_object()
+                .ok_or_else(|| "`rules` should be an object".to_string())?;
+            for (rule, level) in rules.iter() {
+                if !LINT_RULES.iter().any(|(x, _)| x == rule)
+                    && !TYPE_CHECK_CODES.contains(&rule.as_str())
+                {
+                    return Err(format!("unknown rule `{}`", rule));
+                }
+                let level = level
+                    .as_str()
+                    .and_then(LintLevel::parse)
+                    .ok_or_else(|| format!("invalid level for rule `{}`", rule))?;
+                ret.set_level(rule, level);
+            }
+        }
+        Ok(ret)
+    }
+
+    pub fn set_level(&mut self, rule: &str, level: LintLevel) {
+        self.rules.insert(rule.to_string(), level);
+    }
+
+    pub fn level(&self, rule: &str) -> Option<LintLevel> {
+        self.rules.get(rule).cloned()
+    }
+}
+
+// read `<!-- wxml-lint for-without-key: off, duplicate-id: error -->` comments
+fn inline_config(tree: &TmplTree) -> LintConfig {
+    let mut ret = LintConfig::new();
+    for comment in tree.comments.iter() {
+        let content = comment.content.trim();
+        let items = match content.strip_prefix("wxml-lint") {
+            Some(x) if x.starts_with(char::is_whitespace) => x,
+            _ => continue,
+        };
+        for item in items.split(',') {
+            if let Some((rule, level)) = item.split_once(':') {
+                if let Some(level) = LintLevel::parse(level.trim()) {
+                    ret.set_level(rule.trim(), level);
+                }
+            }
+        }
+    }
+    ret
+}
+
+#[derive(Default)]
+struct TemplateRefs {
+    names: HashSet<String>,
+    has_dynamic: bool,
+}
+
+impl Visitor for TemplateRefs {
+    fn visit_virtual_type(&mut self, virtual_type: &TmplVirtualType) {
+        if let TmplVirtualType::TemplateRef { target, .. } = virtual_type {
+            match target {
+                TmplAttrValue::Static(name) => {
+                    self.names.insert(name.to_string());
+                }
+                TmplAttrValue::Dynamic { expr, .. } => match &**expr {
+                    TmplExpr::LitStr(name) => {
+                        self.names.insert(name.to_string());
+                    }
+                    _ => self.has_dynamic = true,
+                },
+            }
+        }
+        walk_virtual_type(self, virtual_type);
+    }
+}
+
+fn all_elements(tree: &TmplTree) -> Vec<&TmplElement> {
+    let mut names: Vec<&String> = tree.sub_templates.keys().collect();
+    names.sort();
+    let mut ret: Vec<&TmplElement> = tree.root.descendants().collect();
+    for name in names {
+        ret.extend(tree.sub_templates[name].descendants());
+    }
+    ret
+}
+
+struct Linter<'a> {
+    group: &'a TmplGroup,
+    refs: HashMap<&'a str, TemplateRefs>,
+    tree: &'a TmplTree,
+    diagnostics: Vec<TmplDiagnostic>,
+}
+
+impl<'a> Linter<'a> {
+    fn report(&mut self, code: &str, message: String, pos: TmplPosRange) {
+        self.diagnostics.push(TmplDiagnostic::new(
+            TmplDiagnosticLevel::Warning,
+            code,
+            message,
+            &self.tree.path,
+            pos.0,
+            pos.1,
+        ));
+    }
+
+    fn lint_for_without_key(&mut self) {
+        for elem in all_elements(self.tree) {
+            if let TmplVirtualType::For { key: None, .. } = &elem.virtual_type {
+                let message = "`wx:for` without `wx:key`".to_string();
+                self.report("for-without-key", message, (elem.start_pos, elem.end_pos));
+            }
+        }
+    }
+
+    fn lint_duplicate_id(&mut self) {
+        let mut names: Vec<&String> = self.tree.sub_templates.keys().collect();
+        names.sort();
+        let mut roots = vec![&self.tree.root];
+        roots.extend(names.into_iter().map(|x| &self.tree.sub_templates[x]));
+        for root in roots {
+            let mut ids = HashSet::new();
+            for elem in root.descendants() {
+                for attr in elem.attrs.iter() {
+                    if let (TmplAttrKind::Id, TmplAttrValue::Static(id)) = (&attr.kind, &attr.value)
+                    {
+                        if !id.is_empty() && !ids.insert(id.as_str()) {
+                            let message = format!("duplicate id `{}`", id);
+                            self.report("duplicate-id", message, (elem.start_pos, elem.end_pos));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn lint_unused_template(&mut self) {
+        let path = self.tree.path.as_str();
+        let mut users = vec![path];
+        for (user_path, user) in self.group.trees.iter() {
+            if user
+                .imports
+                .iter()
+                .any(|x| path::resolve(user_path, x) == path)
+            {
+                users.push(user_path);
+            }
+        }
+        let users: Vec<&TemplateRefs> =
+            users.into_iter().filter_map(|x| self.refs.get(x)).collect();
+        if users.iter().any(|x| x.has_dynamic) {
+            return;
+        }
+        let tree = self.tree;
+        let mut names: Vec<&String> = tree
+            .sub_templates
+            .keys()
+            .filter(|name| !users.iter().any(|x| x.names.contains(*name)))
+            .collect();
+        names.sort();
+        for name in names {
+            let elem = &tree.sub_templates[name];
+            let message = format!("template `{}` is never used", name);
+            self.report("unused-template", message, (elem.start_pos, elem.end_pos));
+        }
+    }
+
+    fn lint_unused_import(&mut self) {
+        let refs = match self.refs.get(self.tree.path.as_str()) {
+            Some(x) if !x.has_dynamic => x,
+            _ => return,
+        };
+        let mut unused = vec![];
+        for (index, import) in self.tree.imports.iter().enumerate() {
+            let target = path::resolve(&self.tree.path, import);
+            if let Some(imported) = self.group.trees.get(&target) {
+                if !imported
+                    .sub_templates
+                    .keys()
+                    .any(|x| refs.names.contains(x))
+                {
+                    unused.push((import.to_string(), self.tree.import_positions[index]));
+                }
+            }
+        }
+        for (import, pos) in unused {
+            let message = format!("the templates imported from `{}` are never used", import);
+            self.report("unused-import", message, pos);
+        }
+    }
+
+    // check the attributes and events of the builtin components against the catalog,
+    // skipping the tags overridden by `usingComponents`
+    fn lint_builtin_components(&mut self) {
+        let registered = |name: &str| {
+            [self.tree.path.as_str(), "app"].iter().any(|path| {
+                self.group
+                    .component_configs
+                    .get(*path)
+                    .is_some_and(|x| x.using_components().contains_key(name))
+            })
+        };
+        let mut list = vec![];
+        for elem in all_elements(self.tree) {
+            if !matches!(elem.virtual_type, TmplVirtualType::None) || registered(&elem.tag_name) {
+                continue;
+            }
+            let component = match builtin_component(&elem.tag_name) {
+                Some(x) => x,
+                None => continue,
+            };
+            let pos = (elem.start_pos, elem.end_pos);
+            if component.deprecated {
+                let message = format!("`<{}>` is deprecated", component.name);
+                list.push(("deprecated-component", message, pos));
+            }
+            for attr in elem.attrs.iter() {
+                let name = match &attr.kind {
+                    TmplAttrKind::PropertyOrExternalClass { name }
+                    | TmplAttrKind::ModelProperty { name } => name,
+                    TmplAttrKind::Event { name, .. } => {
+                        if !component.has_event(name) {
+                            let message = format!("`<{}>` has no event `{}`", component.name, name);
+                            list.push(("unknown-event", message, pos));
+                        }
+                        continue;
+                    }
+                    _ => continue,
+                };
+                if name.contains(':')
+                    || name.starts_with("aria-")
+                    || COMMON_ATTRS.contains(&name.as_str())
+                {
+                    continue;
+                }
+                let def = match component.attr(name) {
+                    Some(x) => x,
+                    None => {
+                        let message = format!("`<{}>` has no attribute `{}`", component.name, name);
+                        list.push(("unknown-attribute", message, pos));
+                        continue;
+                    }
+                };
+                if def.deprecated {
+                    let message = format!(
+                        "the attribute `{}` of `<{}>` is deprecated",
+                        def.name, component.name
+                    );
+                    list.push(("deprecated-attribute", message, pos));
+                }
+                if let TmplAttrValue::Static(value) = &attr.value {
+                    if !def.values.is_empty() && !def.values.contains(&value.as_str()) {
+                        let message = format!(
+                            "`{}` is not a valid value of `{}`, expected one of {}",
+                            value,
+                            def.name,
+                            def.values.join(", ")
+                        );
+                        list.push(("invalid-attribute-value", message, pos));
+                    }
+                }
+            }
+        }
+        for (code, message, pos) in list {
+            self.report(code, message, pos);
+        }
+    }
+}
+
+/// Run the lint rules on all templates in the group.
+///
+/// The diagnostics are ordered by template paths and positions.
+/// The ones disabled by suppression comments (see `TmplTree::is_suppressed`) are skipped.
+/// If a data type is set for a template, the diagnostics of the type checker are also included.
+pub fn lint(group: &TmplGroup, config: &LintConfig) -> Vec<TmplDiagnostic> {
+    let refs: HashMap<&str, TemplateRefs> = group
+        .trees
+        .iter()
+        .map(|(path, tree)| {
+            let mut refs = TemplateRefs::default();
+            refs.visit_tree(tree);
+            (path.as_str(), refs)
+        })
+        .collect();
+    let mut linter = Linter {
+        group,
+        refs,
+        tree: &TmplTree::new(),
+        diagnostics: vec![],
+    };
+    let mut paths: Vec<&String> = group.trees.keys().collect();
+    paths.sort();
+    let mut ret = vec![];
+    for path in paths {
+        let tree = &group.trees[path];
+        linter.tree = tree;
+        linter.diagnostics = tree.diagnostics.clone();
+        linter.lint_for_without_key();
+        linter.lint_duplicate_id();
+        linter.lint_unused_template();
+        linter.lint_unused_import();
+        linter.lint_builtin_components();
+        if let Some(data) = group.data_types.get(path) {
+            linter.diagnostics.extend(check_data_types(tree, data));
+        }
+        if let Some(diagnostics) = group.check_components(path) {
+            linter.diagnostics.extend(diagnostics);
+        }
+        let inline = inline_config(tree);
+        let mut diagnostics: Vec<TmplDiagnostic> = std::mem::take(&mut linter.diagnostics)
+            .into_iter()
+            .filter(|d| !tree.is_suppressed(d))
+            .filter_map(|mut d| {
+                let level = inline
+                    .level(&d.code)
+                    .or_else(|| config.level(&d.code))
+                    .or_else(|| {
+                        LINT_RULES
+                            .iter()
+                            .find(|(code, _)| *code == d.code)
+                            .map(|(_, level)| *level)
+                    });
+                match level {
+                    Some(LintLevel::Off) => return None,
+                    Some(LintLevel::Warning) => d.level = TmplDiagnosticLevel::Warning,
+                    Some(LintLevel::Error) => d.level = TmplDiagnosticLevel::Error,
+                    None => {}
+                }
+                Some(d)
+            })
+            .collect();
+        diagnostics.sort_by_key(|d| d.start_pos);
+        ret.append(&mut diagnostics);
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lint_codes(group: &TmplGroup, config: &LintConfig) -> Vec<String> {
+        lint(group, config)
+            .into_iter()
+            .map(|d| format!("{}:{}:{}", d.path, d.start_pos.0, d.code))
+            .collect()
+    }
+
+    #[test]
+    fn it_lints_templates() {
+        let mut group = TmplGroup::new();
+        group
+            .add_tmpl(
+                "common",
+                r#"<template name="used" /><template name="unused" />"#,
+            )
+            .unwrap();
+        group
+            .add_tmpl("other", r#"<template name="other" />"#)
+            .unwrap();
+        group
+            .add_tmpl(
+                "index",
+                r#"<import src="common" />
+<import src="other" />
+<view id="a" wx:for="{{ list }}" wx:if="{{ item }}" />
+<view id="a" wx:foreach="{{ list }}" />
+<template is="used" />"#,
+            )
+            .unwrap();
+
+        let config = LintConfig::new();
+        assert_eq!(
+            lint_codes(&group, &config),
+            vec![
+                "common:1:unused-template",
+                "index:2:unused-import",
+                "index:3:if-with-for",
+                "index:3:for-without-key",
+                "index:4:unknown-directive",
+                "index:4:duplicate-id",
+                "other:1:unused-template",
+            ]
+        );
+
+        let config = LintConfig::from_json(
+            r#"{ "rules": { "unused-template": "off", "for-without-key": "error" } }"#,
+        )
+        .unwrap();
+        let ret = lint(&group, &config);
+        assert_eq!(ret.len(), 5);
+        assert_eq!(ret[2].code, "for-without-key");
+        assert_eq!(ret[2].level, TmplDiagnosticLevel::Error);
+
+        let config = LintConfig::from_json(r#"{ "rules": { "for-without-kye": "off" } }"#);
+        assert_eq!(config.unwrap_err(), "unknown rule `for-without-kye`");
+
+        let mut group = TmplGroup::new();
+        group.add_tmpl("broken", "<view><text>ok</view>").unwrap();
+        let ret = lint(&group, &LintConfig::new());
+        assert_eq!(ret[0].code, "unclosed-tag");
+        assert_eq!(ret[0].level, TmplDiagnosticLevel::Error);
+        assert_eq!(ret[0].start_pos, (1, 7));
+    }
+
+    #[test]
+    fn it_lints_builtin_components() {
+        let mut group = TmplGroup::new();
+        group
+            .add_tmpl(
+                "index",
+                r#"<image mode="aspectfil" bind:tapp="onTap" hidden />
+<input auto-focus model:value="{{ v }}" bindinput="onInput" aria-label="x" />
+<image mode="{{ m }}" lazy-load bindload="onLoad" />
+<audio src="a.mp3" />
+<scroll-view scroll-yy />
+<picker mode="multiSelector" />"#,
+            )
+            .unwrap();
+        assert_eq!(
+            lint_codes(&group, &LintConfig::new()),
+            vec![
+                "index:1:invalid-attribute-value",
+                "index:1:unknown-event",
+                "index:2:deprecated-attribute",
+                "index:4:deprecated-component",
+                "index:5:unknown-attribute",
+            ]
+        );
+    }
+
+    #[test]
+    fn it_reads_inline_config() {
+        let mut group = TmplGroup::new();
+        group
+            .add_tmpl(
+                "index",
+                r#"<!-- wxml-lint for-without-key: off -->
+<view wx:for="{{ list }}" wx:key="id" wx:if="{{ item }}" /><view wx:for="{{ list }}" />"#,
+            )
+            .unwrap();
+        assert_eq!(
+            lint_codes(&group, &LintConfig::new()),
+            vec!["index:2:if-with-for"]
+        );
+    }
+
+    #[test]
+    fn it_suppresses_diagnostics() {
+        let mut group = TmplGroup::new();
+        group
+            .add_tmpl(
+                "index",
+                r#"<!-- wxml-disable duplicate-id -->
+<view id="a" wx:foreach="{{ list }}" />
+<!-- wxml-disable-next-line unknown-directive, for-without-key -->
+<view id="a" wx:foreach="{{ list }}" wx:for="{{ list }}" />
+<view id="a" wx:for="{{ list }}" />
+<!-- wxml-disable-next-line -->
+<view wx:for="{{ list }}" />"#,
+            )
+            .unwrap();
+        assert_eq!(group.diagnostics().len(), 1);
+        assert_eq!(
+            lint_codes(&group, &LintConfig::new()),
+            vec!["index:2:unknown-directive", "index:5:for-without-key"]
+        );
+    }
+}
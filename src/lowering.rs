@@ -0,0 +1,333 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    builtin_component::builtin_component,
+    diagnostic::{TmplDiagnostic, TmplDiagnosticLevel, TmplPosRange},
+    display_debug::{text_segments, TmplPrinter},
+    element::{TmplAttrKind, TmplAttrValue},
+    escape::gen_wxml_lit_str,
+    expr::{TmplExpr, TmplExprLevel},
+    tree::TmplTree,
+    utils::{camel_to_dash, dash_to_camel},
+};
+
+// the runtime helper to map a `wx:for` list, which is an array, a string or an object like WXML,
+// in which the characters of a string are the items, and the keys of an object are the indexes
+pub(crate) const MAP_LIST: &str = r#"
+function mapList(list, f) {
+  if (Array.isArray(list)) return list.map(f);
+  if (typeof list === "string") return Array.from(list, f);
+  if (list && typeof list === "object") return Object.keys(list).map((k) => f(list[k], k));
+  return [];
+}
+"#;
+
+/// Collect the warnings about the WXML constructs which are dropped
+/// while printing the template in another language.
+#[derive(Default)]
+pub(crate) struct UnsupportedReporter<'a> {
+    code: &'static str,
+    // the language in the messages, e.g. `JSX`
+    target: String,
+    path: &'a str,
+    pub(crate) diagnostics: Vec<TmplDiagnostic>,
+}
+
+impl<'a> UnsupportedReporter<'a> {
+    pub(crate) fn new(code: &'static str, target: impl Into<String>, path: &'a str) -> Self {
+        Self {
+            code,
+            target: target.into(),
+            path,
+            diagnostics: vec![],
+        }
+    }
+
+    pub(crate) fn report(&mut self, what: &str, pos: TmplPosRange) {
+        self.diagnostics.push(TmplDiagnostic::new(
+            TmplDiagnosticLevel::Warning,
+            self.code,
+            format!("`{}` has no equivalent in {}", what, self.target),
+            self.path,
+            pos.0,
+            pos.1,
+        ));
+    }
+
+    // the imports and the wxs modules, which are not printed by the targets without templates or scripts
+    pub(crate) fn report_imports_and_scripts(&mut self, tree: &TmplTree) {
+        for (import, pos) in tree.imports.iter().zip(tree.import_positions.iter()) {
+            self.report(&format!(r#"<import src="{}">"#, import), *pos);
+        }
+        for script in tree.scripts.iter() {
+            let what = format!(r#"<wxs module="{}">"#, script.module_name());
+            self.report(&what, script.pos());
+        }
+    }
+
+    // report the attribute if it only exists in WXML, such as `generic:` and `mark:`,
+    // and return whether it is reported
+    pub(crate) fn report_wxml_only_attr(&mut self, kind: &TmplAttrKind, pos: TmplPosRange) -> bool {
+        let what = match kind {
+            TmplAttrKind::WxDirective { name } => format!("wx:{}", name),
+            TmplAttrKind::Generic { name } => format!("generic:{}", name),
+            TmplAttrKind::SlotProperty { name } => format!("slot:{}", camel_to_dash(name)),
+            TmplAttrKind::ChangeProperty { name } => format!("change:{}", name),
+            TmplAttrKind::WorkletProperty { name } => format!("worklet:{}", name),
+            TmplAttrKind::Mark { name } => format!("mark:{}", name),
+            _ => return false,
+        };
+        self.report(&what, pos);
+        true
+    }
+}
+
+// the identifiers of the generated code which the component names must not take,
+// e.g. `Fragment` and the short names `F`, `M` and `S` in the production mode
+const RESERVED_COMPONENT_NAMES: &[&str] = &["F", "Fragment", "M", "S"];
+
+// the component names of the sub templates, in which a name taken by the generated code
+// or by a previous template is suffixed with `$N`, e.g. `Fragment$1`,
+// since the suffix never appears in the names converted from the template names
+pub(crate) fn component_names(tree: &TmplTree) -> HashMap<&str, String> {
+    let mut names: Vec<&String> = tree.sub_templates.keys().collect();
+    names.sort();
+    let mut taken: HashSet<String> = HashSet::new();
+    let mut ret = HashMap::new();
+    for name in names {
+        let base = component_name(name);
+        let mut component = base.clone();
+        let mut i = 0;
+        while RESERVED_COMPONENT_NAMES.contains(&component.as_str()) || taken.contains(&component) {
+            i += 1;
+            component = format!("{}${}", base, i);
+        }
+        taken.insert(component.clone());
+        ret.insert(name.as_str(), component);
+    }
+    ret
+}
+
+// the component name of a sub template, e.g. `Card` for `card`
+pub(crate) fn component_name(name: &str) -> String {
+    let name: String = dash_to_camel(name)
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_digit() => format!("T{}", name),
+        Some(c) => c.to_ascii_uppercase().to_string() + chars.as_str(),
+        None => "T".to_string(),
+    }
+}
+
+// the reserved words of JavaScript, which cannot be the names of the scopes
+const JS_RESERVED_WORDS: &[&str] = &[
+    "arguments",
+    "await",
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "debugger",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "enum",
+    "eval",
+    "export",
+    "extends",
+    "false",
+    "finally",
+    "for",
+    "function",
+    "if",
+    "implements",
+    "import",
+    "in",
+    "instanceof",
+    "interface",
+    "let",
+    "new",
+    "null",
+    "package",
+    "private",
+    "protected",
+    "public",
+    "return",
+    "static",
+    "super",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typeof",
+    "undefined",
+    "var",
+    "void",
+    "while",
+    "with",
+    "yield",
+];
+
+// whether a scope name can be printed as written in the source,
+// which does not shadow the `reserved` identifiers, the `$N` names or the component names
+pub(crate) fn is_free_name(name: &str, reserved: &[&str]) -> bool {
+    is_ident(name)
+        && !name.starts_with(|c: char| c == '$' || c.is_ascii_uppercase())
+        && !JS_RESERVED_WORDS.contains(&name)
+        && !reserved.contains(&name)
+}
+
+pub(crate) fn is_ident(s: &str) -> bool {
+    !s.is_empty()
+        && s.chars().enumerate().all(|(i, c)| {
+            c.is_ascii_alphabetic() || c == '_' || c == '$' || i > 0 && c.is_ascii_digit()
+        })
+}
+
+// convert the style text into the object literal, e.g. `{color:'red',fontSize:data.s+'px'}`
+// returns `None` if the property names are not static
+pub(crate) fn style_object(printer: &TmplPrinter, value: &TmplAttrValue) -> Option<String> {
+    let mut pieces: Vec<Result<&str, &TmplExpr>> = vec![];
+    match value {
+        TmplAttrValue::Static(s) => pieces.push(Ok(s)),
+        TmplAttrValue::Dynamic { expr, .. } => {
+            for seg in text_segments(expr)? {
+                match seg {
+                    TmplExpr::LitStr(s) => pieces.push(Ok(s)),
+                    TmplExpr::ToStringWithoutUndefined(_) => pieces.push(Err(seg)),
+                    _ => unreachable!(),
+                }
+            }
+        }
+    }
+    // the value of a declaration is a list of the static text and the expressions
+    let mut decls: Vec<(String, Vec<Result<String, &TmplExpr>>)> = vec![];
+    let mut name = String::new();
+    let mut value: Option<Vec<Result<String, &TmplExpr>>> = None;
+    for piece in pieces {
+        match piece {
+            Ok(s) => {
+                for c in s.chars() {
+                    match (&mut value, c) {
+                        (None, ':') => value = Some(vec![]),
+                        (None, ';') => name.clear(),
+                        (None, c) => name.push(c),
+                        (Some(_), ';') => {
+                            decls.push((std::mem::take(&mut name), value.take().unwrap()))
+                        }
+                        (Some(v), c) => match v.last_mut() {
+                            Some(Ok(text)) => text.push(c),
+                            _ => v.push(Ok(c.to_string())),
+                        },
+                    }
+                }
+            }
+            Err(x) => value.as_mut()?.push(Err(x)),
+        }
+    }
+    if let Some(v) = value {
+        decls.push((name, v));
+    }
+    let items: Vec<String> = decls
+        .into_iter()
+        .filter(|(name, _)| !name.trim().is_empty())
+        .map(|(name, mut v)| {
+            let name = name.trim();
+            let key = if name.starts_with("--") {
+                gen_wxml_lit_str(name)
+            } else {
+                dash_to_camel(name)
+            };
+            if let Some(Ok(s)) = v.first_mut() {
+                *s = s.trim_start().to_string();
+            }
+            if let Some(Ok(s)) = v.last_mut() {
+                *s = s.trim_end().to_string();
+            }
+            v.retain(|x| !matches!(x, Ok(s) if s.is_empty()));
+            let mut parts: Vec<String> = v
+                .iter()
+                .map(|x| match x {
+                    Ok(s) => gen_wxml_lit_str(s),
+                    Err(x) => printer.expr_string(x, TmplExprLevel::Multiply),
+                })
+                .collect();
+            // make sure the leading expressions are concatenated as strings
+            if v.len() != 1 && !matches!(v.first(), Some(Ok(_)) | None) {
+                if let Some(Err(_)) = v.get(1) {
+                    parts.insert(0, "''".to_string());
+                }
+            }
+            if parts.is_empty() {
+                parts.push("''".to_string());
+            }
+            format!("{}:{}", key, parts.join("+"))
+        })
+        .collect();
+    Some(format!("{{{}}}", items.join(",")))
+}
+
+// the key of a `wx:for` item, e.g. `$0.id` for `wx:key="id"`
+pub(crate) fn for_key(key: Option<&str>, item_name: &str, index_name: &str) -> String {
+    match key {
+        None => index_name.to_string(),
+        Some("*this") => item_name.to_string(),
+        Some(key) if is_ident(key) => format!("{}.{}", item_name, key),
+        Some(key) => format!("{}[{}]", item_name, gen_wxml_lit_str(key)),
+    }
+}
+
+// the React events of the builtin component events
+const REACT_EVENTS: &[(&str, &str)] = &[
+    ("tap", "Click"),
+    ("touchstart", "TouchStart"),
+    ("touchmove", "TouchMove"),
+    ("touchend", "TouchEnd"),
+    ("touchcancel", "TouchCancel"),
+    ("transitionend", "TransitionEnd"),
+    ("animationstart", "AnimationStart"),
+    ("animationiteration", "AnimationIteration"),
+    ("animationend", "AnimationEnd"),
+    ("input", "Input"),
+    ("focus", "Focus"),
+    ("blur", "Blur"),
+    ("change", "Change"),
+    ("submit", "Submit"),
+    ("reset", "Reset"),
+    ("scroll", "Scroll"),
+    ("load", "Load"),
+    ("error", "Error"),
+    ("play", "Play"),
+    ("pause", "Pause"),
+    ("ended", "Ended"),
+    ("timeupdate", "TimeUpdate"),
+    ("waiting", "Waiting"),
+];
+
+// the React prop of an event, e.g. `onClick` for `tap`, or `None` if React has no such event;
+// the events of custom components are callback props, e.g. `onItemTap` for `item-tap`
+pub(crate) fn event_prop(tag_name: &str, name: &str, capture: bool) -> Option<String> {
+    let event_name = if builtin_component(tag_name).is_some() {
+        let (_, x) = REACT_EVENTS.iter().find(|(x, _)| *x == name)?;
+        x.to_string()
+    } else {
+        let name = dash_to_camel(name);
+        let mut chars = name.chars();
+        match chars.next() {
+            Some(c) => c.to_ascii_uppercase().to_string() + chars.as_str(),
+            None => return None,
+        }
+    };
+    Some(format!(
+        "on{}{}",
+        event_name,
+        if capture { "Capture" } else { "" }
+    ))
+}
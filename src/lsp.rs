@@ -0,0 +1,656 @@
+//! A language server for the templates, handling the JSON-RPC messages of the Language Server Protocol.
+//!
+//! The transport is left to the caller, e.g. the `Content-Length` framing over stdio in the `lsp` command.
+//! Only the full text document sync is supported.
+
+use std::{collections::HashMap, fs, ops::Range};
+
+use serde_json::{json, Value};
+
+use crate::{
+    complete::{complete, TmplCompletionKind},
+    cst::{CstElement, TmplCst},
+    data_path::collect_data_paths_at,
+    diagnostic::TmplDiagnosticLevel,
+    lint::{lint, LintConfig},
+    path,
+    rename::rename,
+    tree::TmplTree,
+    TmplGroup,
+};
+
+const METHOD_NOT_FOUND: i64 = -32601;
+const REQUEST_FAILED: i64 = -32803;
+const SYMBOL_KIND_FUNCTION: u32 = 12;
+
+pub struct TmplLanguageServer {
+    root: String,
+    group: TmplGroup,
+    lint_config: LintConfig,
+    documents: HashMap<String, String>,
+    parse_errors: HashMap<String, Value>,
+    exited: bool,
+}
+
+impl TmplLanguageServer {
+    /// Create a server for the templates in the directory `root`.
+    ///
+    /// The `group` should contain the templates in `root`, keyed by their paths relative to `root`.
+    /// The documents outside `root` are ignored.
+    pub fn new(root: &str, group: TmplGroup) -> Self {
+        Self {
+            root: root.trim_end_matches('/').to_string(),
+            group,
+            lint_config: LintConfig::new(),
+            documents: HashMap::new(),
+            parse_errors: HashMap::new(),
+            exited: false,
+        }
+    }
+
+    pub fn set_lint_config(&mut self, config: LintConfig) {
+        self.lint_config = config;
+    }
+
+    /// Whether the `exit` notification is received.
+    pub fn is_exited(&self) -> bool {
+        self.exited
+    }
+
+    /// Handle a request or a notification, returning the response and the notifications to send.
+    pub fn handle_message(&mut self, msg: &Value) -> Vec<Value> {
+        // the responses of the client are not used
+        let method = match msg.get("method").and_then(|x| x.as_str()) {
+            Some(x) => x,
+            None => return vec![],
+        };
+        let params = msg.get("params").unwrap_or(&Value::Null);
+        let result = match method {
+            "initialize" => Ok(json!({
+                "capabilities": {
+                    "textDocumentSync": 1,
+                    "definitionProvider": true,
+                    "hoverProvider": true,
+                    "referencesProvider": true,
+                    "documentSymbolProvider": true,
+                    "renameProvider": true,
+                    "completionProvider": { "triggerCharacters": ["<", ":", "\"", "'", "{", "/"] },
+                },
+                "serverInfo": { "name": "wxml-compiler" },
+            })),
+            "shutdown" => Ok(Value::Null),
+            "exit" => {
+                self.exited = true;
+                return vec![];
+            }
+            "textDocument/didOpen" => {
+                let doc = &params["textDocument"];
+                if let (Some(path), Some(text)) = (self.uri_path(&doc["uri"]), doc["text"].as_str())
+                {
+                    self.update_document(&path, text);
+                }
+                return self.publish_diagnostics();
+            }
+            "textDocument/didChange" => {
+                let text = params["contentChanges"]
+                    .as_array()
+                    .and_then(|x| x.last())
+                    .and_then(|x| x["text"].as_str());
+                if let (Some(path), Some(text)) =
+                    (self.uri_path(&params["textDocument"]["uri"]), text)
+                {
+                    self.update_document(&path, text);
+                }
+                return self.publish_diagnostics();
+            }
+            "textDocument/didClose" => return self.close_document(&params["textDocument"]["uri"]),
+            "textDocument/definition" => Ok(self.definition(params).unwrap_or(Value::Null)),
+            "textDocument/hover" => Ok(self.hover(params).unwrap_or(Value::Null)),
+            "textDocument/references" => Ok(self.references(params).unwrap_or(Value::Null)),
+            "textDocument/completion" => Ok(self.completion(params).unwrap_or(Value::Null)),
+            "textDocument/documentSymbol" => {
+                Ok(self.document_symbols(params).unwrap_or(Value::Null))
+            }
+            "textDocument/rename" => self.rename(params).map_err(|x| (REQUEST_FAILED, x)),
+            _ => Err((METHOD_NOT_FOUND, format!("unknown method `{}`", method))),
+        };
+        // notifications get no responses
+        let id = match msg.get("id") {
+            Some(x) => x,
+            None => return vec![],
+        };
+        let ret = match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": code, "message": message },
+            }),
+        };
+        vec![ret]
+    }
+
+    // the template path of a `.wxml` file uri in the root
+    fn uri_path(&self, uri: &Value) -> Option<String> {
+        let file = percent_decode(uri.as_str()?.strip_prefix("file://")?);
+        let rel = file.strip_prefix(&self.root)?.strip_prefix('/')?;
+        Some(rel.strip_suffix(".wxml")?.to_string())
+    }
+
+    fn file_uri(&self, file: &str) -> String {
+        format!(
+            "file://{}",
+            percent_encode(&format!("{}/{}", self.root, file))
+        )
+    }
+
+    // the text of the open document, or the file content in the root
+    fn source(&self, path: &str) -> Option<String> {
+        match self.documents.get(path) {
+            Some(x) => Some(x.clone()),
+            None => fs::read_to_string(format!("{}/{}.wxml", self.root, path)).ok(),
+        }
+    }
+
+    fn update_document(&mut self, path: &str, text: &str) {
+        self.documents.insert(path.to_string(), text.to_string());
+        match self.group.add_tmpl(path, text) {
+            Ok(_) => {
+                self.parse_errors.remove(path);
+            }
+            Err(e) => {
+                let diagnostic = json!({
+                    "range": {
+                        "start": lsp_position(text, tree_pos_offset(text, e.start_pos)),
+                        "end": lsp_position(text, tree_pos_offset(text, e.end_pos)),
+                    },
+                    "severity": 1,
+                    "source": "wxml",
+                    "message": e.message,
+                });
+                self.parse_errors.insert(path.to_string(), diagnostic);
+            }
+        }
+    }
+
+    fn close_document(&mut self, uri: &Value) -> Vec<Value> {
+        let path = match self.uri_path(uri) {
+            Some(x) => x,
+            None => return vec![],
+        };
+        self.documents.remove(&path);
+        self.parse_errors.remove(&path);
+        // go back to the content on disk
+        if let Some(source) = self.source(&path) {
+            let _ = self.group.add_tmpl(&path, &source);
+        }
+        let mut ret = self.publish_diagnostics();
+        ret.push(json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/publishDiagnostics",
+            "params": { "uri": uri, "diagnostics": [] },
+        }));
+        ret
+    }
+
+    // lint the whole group, since a change may affect the diagnostics of the other templates
+    fn publish_diagnostics(&self) -> Vec<Value> {
+        let diagnostics = lint(&self.group, &self.lint_config);
+        let mut paths: Vec<&String> = self.documents.keys().collect();
+        paths.sort();
+        paths
+            .into_iter()
+            .map(|path| {
+                let source = &self.documents[path];
+                let list: Vec<Value> = match self.parse_errors.get(path) {
+                    Some(x) => vec![x.clone()],
+                    None => diagnostics
+                        .iter()
+                        .filter(|d| &d.path == path)
+                        .map(|d| {
+                            let severity = match d.level {
+                                TmplDiagnosticLevel::Error => 1,
+                                TmplDiagnosticLevel::Warning => 2,
+                            };
+                            json!({
+                                "range": {
+                                    "start": lsp_position(source, tree_pos_offset(source, d.start_pos)),
+                                    "end": lsp_position(source, tree_pos_offset(source, d.end_pos)),
+                                },
+                                "severity": severity,
+                                "code": d.code,
+                                "source": "wxml",
+                                "message": d.message,
+                            })
+                        })
+                        .collect(),
+                };
+                json!({
+                    "jsonrpc": "2.0",
+                    "method": "textDocument/publishDiagnostics",
+                    "params": { "uri": self.file_uri(&format!("{}.wxml", path)), "diagnostics": list },
+                })
+            })
+            .collect()
+    }
+
+    // the template path, the source and the offset of `textDocument` and `position` in the params
+    fn document_at(&self, params: &Value) -> Option<(String, String, usize)> {
+        let path = self.uri_path(&params["textDocument"]["uri"])?;
+        let source = self.source(&path)?;
+        let offset = lsp_offset(&source, &params["position"])?;
+        Some((path, source, offset))
+    }
+
+    fn location(&self, file: &str, source: &str, range: Range<usize>) -> Value {
+        json!({ "uri": self.file_uri(file), "range": lsp_range(source, range) })
+    }
+
+    // the location of the name in `<template name>`
+    fn template_location(&self, path: &str, name: &str) -> Option<Value> {
+        let source = self.source(path)?;
+        let cst = TmplCst::parse(&source).ok()?;
+        let range = cst
+            .elements()
+            .into_iter()
+            .filter(|x| x.tag_name() == "template")
+            .filter_map(|x| x.attr("name")?.value())
+            .map(|x| x.inner_range())
+            .find(|x| cst.text(x) == name)?;
+        Some(self.location(&format!("{}.wxml", path), &source, range))
+    }
+
+    fn definition(&self, params: &Value) -> Option<Value> {
+        let (path, source, offset) = self.document_at(params)?;
+        let cst = TmplCst::parse(&source).ok()?;
+        let (elem, attr) = cst.attr_at(offset)?;
+        let value = cst.text(&attr.value()?.inner_range());
+        if value.contains("{{") {
+            return None;
+        }
+        let file = match (elem.tag_name(), attr.name()) {
+            ("template", "is") => {
+                let target = self.group.find_template(&path, value)?;
+                return self.template_location(&target, value);
+            }
+            ("import", "src") | ("include", "src") => {
+                let target = path::resolve(&path, value.trim_end_matches(".wxml"));
+                format!("{}.wxml", target)
+            }
+            ("wxs", "src") => {
+                let target = path::resolve(&path, value);
+                if target.ends_with(".wxs") {
+                    target
+                } else {
+                    format!("{}.wxs", target)
+                }
+            }
+            _ => return None,
+        };
+        Some(json!({ "uri": self.file_uri(&file), "range": lsp_range("", 0..0) }))
+    }
+
+    fn hover(&self, params: &Value) -> Option<Value> {
+        let (path, source, offset) = self.document_at(params)?;
+        let tree = self.group.get_tree(&path)?;
+        let cst = TmplCst::parse(&source).ok()?;
+        let elements: Vec<&CstElement> = cst
+            .elements()
+            .into_iter()
+            .filter(|x| element_range(x).contains(&offset))
+            .collect();
+        // the elements are in pre-order, so the last one is the innermost
+        let elem = elements.last()?;
+        let paths = collect_data_paths_at(tree, tree_pos(&source, elem.start_tag().start));
+        if paths.is_empty() {
+            return None;
+        }
+        let in_sub_template = elements
+            .iter()
+            .any(|x| x.tag_name() == "template" && x.attr("name").is_some());
+        let mut value = if in_sub_template {
+            "Data paths (relative to the template `data`):\n".to_string()
+        } else {
+            "Data paths:\n".to_string()
+        };
+        for p in paths {
+            value += &format!("\n- `{}`", p);
+        }
+        Some(json!({
+            "contents": { "kind": "markdown", "value": value },
+            "range": lsp_range(&source, elem.start_tag()),
+        }))
+    }
+
+    fn references(&self, params: &Value) -> Option<Value> {
+        let (path, source, offset) = self.document_at(params)?;
+        let cst = TmplCst::parse(&source).ok()?;
+        let (elem, attr) = cst.attr_at(offset)?;
+        let name = cst.text(&attr.value()?.inner_range());
+        let def_path = match (elem.tag_name(), attr.name()) {
+            ("template", "name") => path.clone(),
+            ("template", "is") if !name.contains("{{") => self.group.find_template(&path, name)?,
+            _ => return None,
+        };
+        // the template itself and the ones importing it, unless they define a template of the same name
+        let mut users: Vec<&str> = self
+            .group
+            .trees()
+            .filter(|x| {
+                x.path != def_path
+                    && !x.sub_templates.contains_key(name)
+                    && x.imports
+                        .iter()
+                        .any(|import| path::resolve(&x.path, import) == def_path)
+            })
+            .map(|x| x.path.as_str())
+            .collect();
+        users.sort();
+        users.insert(0, &def_path);
+        let mut ret = vec![];
+        if params["context"]["includeDeclaration"].as_bool() == Some(true) {
+            ret.extend(self.template_location(&def_path, name));
+        }
+        for user in users {
+            let source = match self.source(user) {
+                Some(x) => x,
+                None => continue,
+            };
+            let cst = match TmplCst::parse(&source) {
+                Ok(x) => x,
+                Err(_) => continue,
+            };
+            for elem in cst.elements() {
+                if elem.tag_name() != "template" {
+                    continue;
+                }
+                if let Some(value) = elem.attr("is").and_then(|x| x.value()) {
+                    if cst.text(&value.inner_range()) == name {
+                        let file = format!("{}.wxml", user);
+                        ret.push(self.location(&file, &source, value.inner_range()));
+                    }
+                }
+            }
+        }
+        Some(Value::Array(ret))
+    }
+
+    fn completion(&self, params: &Value) -> Option<Value> {
+        let (path, source, offset) = self.document_at(params)?;
+        // the tree of a new file may not be parsed yet
+        let ret = match self.group.complete(&path, &source, offset) {
+            Some(x) => x,
+            None => complete(&TmplTree::new(), &source, offset),
+        };
+        let range = lsp_range(&source, ret.start..offset);
+        let items: Vec<Value> = ret
+            .items
+            .iter()
+            .map(|x| {
+                let kind = match x.kind {
+                    TmplCompletionKind::Tag => 7,
+                    TmplCompletionKind::Attribute => 10,
+                    TmplCompletionKind::Event => 23,
+                    TmplCompletionKind::Value => 20,
+                    TmplCompletionKind::Template => 3,
+                    TmplCompletionKind::Path => 17,
+                    TmplCompletionKind::Variable => 6,
+                    TmplCompletionKind::Module => 9,
+                };
+                json!({
+                    "label": x.label,
+                    "kind": kind,
+                    "detail": x.detail,
+                    "textEdit": { "range": range, "newText": x.label },
+                })
+            })
+            .collect();
+        Some(json!({ "isIncomplete": false, "items": items }))
+    }
+
+    fn rename(&self, params: &Value) -> Result<Value, String> {
+        let (path, _, offset) = self
+            .document_at(params)
+            .ok_or_else(|| "the document is not found".to_string())?;
+        let new_name = params["newName"].as_str().unwrap_or_default();
+        let edits = rename(&self.group, &path, offset, new_name, |x| self.source(x))?;
+        let mut changes = serde_json::Map::new();
+        for (path, edits) in edits {
+            let source = self.source(&path).unwrap_or_default();
+            let edits: Vec<Value> = edits
+                .into_iter()
+                .map(|x| json!({ "range": lsp_range(&source, x.range), "newText": x.new_text }))
+                .collect();
+            changes.insert(
+                self.file_uri(&format!("{}.wxml", path)),
+                Value::Array(edits),
+            );
+        }
+        Ok(json!({ "changes": changes }))
+    }
+
+    fn document_symbols(&self, params: &Value) -> Option<Value> {
+        let path = self.uri_path(&params["textDocument"]["uri"])?;
+        let source = self.source(&path)?;
+        let cst = TmplCst::parse(&source).ok()?;
+        let ret: Vec<Value> = cst
+            .elements()
+            .into_iter()
+            .filter(|x| x.tag_name() == "template")
+            .filter_map(|elem| {
+                let name = elem.attr("name")?.value()?.inner_range();
+                Some(json!({
+                    "name": cst.text(&name),
+                    "kind": SYMBOL_KIND_FUNCTION,
+                    "range": lsp_range(&source, element_range(elem)),
+                    "selectionRange": lsp_range(&source, name),
+                }))
+            })
+            .collect();
+        Some(Value::Array(ret))
+    }
+}
+
+fn element_range(elem: &CstElement) -> Range<usize> {
+    let start_tag = elem.start_tag();
+    let end = elem.end_tag().map(|x| x.end).unwrap_or(start_tag.end);
+    start_tag.start..end
+}
+
+// convert a byte offset to a position of lines and UTF-16 characters
+fn lsp_position(source: &str, offset: usize) -> Value {
+    let before = &source[..offset.min(source.len())];
+    let line = before.matches('\n').count();
+    let line_start = before.rfind('\n').map(|x| x + 1).unwrap_or(0);
+    let character: usize = before[line_start..].chars().map(|c| c.len_utf16()).sum();
+    json!({ "line": line, "character": character })
+}
+
+fn lsp_range(source: &str, range: Range<usize>) -> Value {
+    json!({ "start": lsp_position(source, range.start), "end": lsp_position(source, range.end) })
+}
+
+fn lsp_offset(source: &str, position: &Value) -> Option<usize> {
+    let line = position["line"].as_u64()? as usize;
+    let character = position["character"].as_u64()? as usize;
+    let line_start = if line == 0 {
+        0
+    } else {
+        source.match_indices('\n').nth(line - 1)?.0 + 1
+    };
+    let mut utf16 = 0;
+    for (i, c) in source[line_start..].char_indices() {
+        if utf16 >= character || c == '\n' {
+            return Some(line_start + i);
+        }
+        utf16 += c.len_utf16();
+    }
+    Some(source.len())
+}
+
+// the byte offset of a 1-based `(line, column)` in the trees, in which the columns count chars
+fn tree_pos_offset(source: &str, pos: (usize, usize)) -> usize {
+    let line_start = if pos.0 <= 1 {
+        0
+    } else {
+        match source.match_indices('\n').nth(pos.0 - 2) {
+            Some((i, _)) => i + 1,
+            None => return source.len(),
+        }
+    };
+    source[line_start..]
+        .char_indices()
+        .nth(pos.1.saturating_sub(1))
+        .map(|(i, _)| line_start + i)
+        .unwrap_or(source.len())
+}
+
+fn tree_pos(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset];
+    let line_start = before.rfind('\n').map(|x| x + 1).unwrap_or(0);
+    (
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    )
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut ret = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|x| std::str::from_utf8(x).ok())
+            .and_then(|x| u8::from_str_radix(x, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(x)) => {
+                ret.push(x);
+                i += 3;
+            }
+            (x, _) => {
+                ret.push(x);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&ret).to_string()
+}
+
+fn percent_encode(s: &str) -> String {
+    let mut ret = String::new();
+    for b in s.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' => {
+                ret.push(b as char)
+            }
+            _ => ret += &format!("%{:02X}", b),
+        }
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(id: u64, method: &str, params: Value) -> Value {
+        json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params })
+    }
+
+    fn at(file: &str, line: u32, character: u32) -> Value {
+        json!({
+            "textDocument": { "uri": format!("file:///ws/{}", file) },
+            "position": { "line": line, "character": character },
+            "context": { "includeDeclaration": true },
+        })
+    }
+
+    #[test]
+    fn it_serves_language_features() {
+        let mut group = TmplGroup::new();
+        let common = r#"<template name="card"><view>{{ title }}</view></template>"#;
+        group.add_tmpl("common", common).unwrap();
+        let mut server = TmplLanguageServer::new("/ws", group);
+        let ret = server.handle_message(&request(1, "initialize", json!({})));
+        assert_eq!(ret[0]["result"]["capabilities"]["hoverProvider"], true);
+        let open = |uri: &str, text: &str| {
+            json!({
+                "jsonrpc": "2.0",
+                "method": "textDocument/didOpen",
+                "params": { "textDocument": { "uri": uri, "text": text } },
+            })
+        };
+        server.handle_message(&open("file:///ws/common.wxml", common));
+
+        let index = r#"<import src="./common" />
+<template is="card" data="{{ title }}" />
+<view wx:for="{{ list }}">{{ item.name }}</view>
+<view wx:for="{{ list }}" />"#;
+        let ret = server.handle_message(&open("file:///ws/index.wxml", index));
+        assert_eq!(ret.len(), 2);
+        let diagnostics = &ret[1]["params"]["diagnostics"];
+        assert_eq!(diagnostics[0]["code"], "for-without-key");
+        assert_eq!(diagnostics[0]["range"]["start"]["line"], 2);
+
+        let ret = server.handle_message(&request(
+            2,
+            "textDocument/definition",
+            at("index.wxml", 1, 15),
+        ));
+        assert_eq!(ret[0]["result"]["uri"], "file:///ws/common.wxml");
+        assert_eq!(ret[0]["result"]["range"]["start"]["character"], 16);
+        let ret = server.handle_message(&request(
+            3,
+            "textDocument/definition",
+            at("index.wxml", 0, 15),
+        ));
+        assert_eq!(ret[0]["result"]["uri"], "file:///ws/common.wxml");
+
+        let ret = server.handle_message(&request(4, "textDocument/hover", at("index.wxml", 2, 30)));
+        let value = ret[0]["result"]["contents"]["value"].as_str().unwrap();
+        assert_eq!(value, "Data paths:\n\n- `list`\n- `list[*].name`");
+
+        let ret = server.handle_message(&request(
+            5,
+            "textDocument/references",
+            at("index.wxml", 1, 15),
+        ));
+        let list = ret[0]["result"].as_array().unwrap();
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[1]["uri"], "file:///ws/index.wxml");
+
+        let ret = server.handle_message(&request(
+            6,
+            "textDocument/documentSymbol",
+            at("common.wxml", 0, 0),
+        ));
+        assert_eq!(ret[0]["result"][0]["name"], "card");
+
+        let ret = server.handle_message(&request(
+            7,
+            "textDocument/completion",
+            at("index.wxml", 2, 29),
+        ));
+        assert_eq!(ret[0]["result"]["items"][0]["label"], "item");
+
+        let mut params = at("index.wxml", 1, 15);
+        params["newName"] = json!("panel");
+        let ret = server.handle_message(&request(8, "textDocument/rename", params));
+        let changes = &ret[0]["result"]["changes"];
+        assert_eq!(changes["file:///ws/common.wxml"][0]["newText"], "panel");
+        assert_eq!(
+            changes["file:///ws/index.wxml"][0]["range"]["start"]["character"],
+            14
+        );
+        let mut params = at("index.wxml", 2, 0);
+        params["newName"] = json!("x");
+        let ret = server.handle_message(&request(9, "textDocument/rename", params));
+        assert_eq!(ret[0]["error"]["code"], REQUEST_FAILED);
+
+        let ret = server.handle_message(&request(10, "unknown/method", json!({})));
+        assert_eq!(ret[0]["error"]["code"], METHOD_NOT_FOUND);
+        server.handle_message(&json!({ "jsonrpc": "2.0", "method": "exit" }));
+        assert!(server.is_exited());
+    }
+}
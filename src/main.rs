@@ -1,12 +1,415 @@
+use clap::{Arg, ArgAction, ArgMatches, Command};
 use log::*;
-use std::{fs, path::Path};
+use std::{
+    fs,
+    io::{self, BufRead, Write},
+    path::{Path, PathBuf},
+    process::exit,
+};
 use wxml_compiler::*;
 
 fn main() {
-    println!("Hello, world!");
+    let matches = Command::new("wxml-compiler")
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("lint")
+                .about("Check the wxml files in a directory")
+                .arg(
+                    Arg::new("config")
+                        .long("config")
+                        .value_name("FILE")
+                        .help("The lint config file in JSON"),
+                )
+                .arg(Arg::new("dir").required(true)),
+        )
+        .subcommand(
+            Command::new("codemod")
+                .about("Rewrite the wxml files in a directory with declarative rules")
+                .arg(
+                    Arg::new("rules")
+                        .long("rules")
+                        .value_name("FILE")
+                        .required(true)
+                        .help("The rewrite rules in JSON"),
+                )
+                .arg(
+                    Arg::new("dry-run")
+                        .long("dry-run")
+                        .action(ArgAction::SetTrue)
+                        .help("Print the diff without writing the files"),
+                )
+                .arg(Arg::new("dir").required(true)),
+        )
+        .subcommand(
+            Command::new("events")
+                .about("List the event bindings in the wxml files in a directory")
+                .arg(
+                    Arg::new("json")
+                        .long("json")
+                        .action(ArgAction::SetTrue)
+                        .help("Print the bindings as JSON keyed by template paths"),
+                )
+                .arg(Arg::new("dir").required(true)),
+        )
+        .subcommand(
+            Command::new("lsp")
+                .about("Run a language server over stdio for the wxml files in a directory")
+                .arg(
+                    Arg::new("config")
+                        .long("config")
+                        .value_name("FILE")
+                        .help("The lint config file in JSON"),
+                )
+                .arg(
+                    Arg::new("dir")
+                        .default_value(".")
+                        .help("The workspace root"),
+                ),
+        )
+        .get_matches();
+
+    match matches.subcommand() {
+        Some(("lint", matches)) => lint_command(matches),
+        Some(("codemod", matches)) => codemod_command(matches),
+        Some(("events", matches)) => events_command(matches),
+        Some(("lsp", matches)) => lsp_command(matches),
+        _ => unreachable!(),
+    }
+}
+
+fn read_lint_config(matches: &ArgMatches) -> LintConfig {
+    match matches.get_one::<String>("config") {
+        None => LintConfig::new(),
+        Some(file) => {
+            let config = fs::read_to_string(file)
+                .map_err(|e| e.to_string())
+                .and_then(|s| LintConfig::from_json(&s));
+            match config {
+                Ok(x) => x,
+                Err(e) => {
+                    eprintln!("Invalid lint config {}: {}", file, e);
+                    exit(2);
+                }
+            }
+        }
+    }
+}
+
+fn lint_command(matches: &ArgMatches) {
+    let config = read_lint_config(matches);
+    let dir = Path::new(matches.get_one::<String>("dir").unwrap());
+    let mut group = TmplGroup::new();
+    // the files which fail to parse are reported as errors
+    let mut diagnostics = vec![];
+    load_wxml_files(&mut group, dir, &mut vec![], &mut diagnostics);
+    load_data_types(&mut group, dir);
+    load_component_configs(&mut group, dir);
+    diagnostics.extend(lint(&group, &config));
+    let errors = diagnostics
+        .iter()
+        .filter(|x| x.level == TmplDiagnosticLevel::Error)
+        .count();
+    for d in diagnostics.iter() {
+        println!("{}", d);
+    }
+    println!(
+        "{} error(s), {} warning(s)",
+        errors,
+        diagnostics.len() - errors
+    );
+    if errors > 0 {
+        exit(1);
+    }
+}
+
+fn codemod_command(matches: &ArgMatches) {
+    let file = matches.get_one::<String>("rules").unwrap();
+    let codemod = fs::read_to_string(file)
+        .map_err(|e| e.to_string())
+        .and_then(|s| Codemod::from_json(&s));
+    let codemod = match codemod {
+        Ok(x) => x,
+        Err(e) => {
+            eprintln!("Invalid codemod rules {}: {}", file, e);
+            exit(2);
+        }
+    };
+    let dry_run = matches.get_flag("dry-run");
+    let dir = Path::new(matches.get_one::<String>("dir").unwrap());
+    let mut files = vec![];
+    find_wxml_files(dir, &mut files);
+    files.sort();
+    let mut changed = 0;
+    for file in files {
+        let name = file.to_str().unwrap_or("");
+        let source = match fs::read_to_string(&file) {
+            Ok(x) => x,
+            Err(e) => {
+                eprintln!("{}: {}", name, e);
+                continue;
+            }
+        };
+        match codemod.apply(&source) {
+            Err(e) => eprintln!("{}: {}", name, e),
+            Ok(None) => {}
+            Ok(Some(new_source)) => {
+                changed += 1;
+                if dry_run {
+                    print!("{}", unified_diff(name, &source, &new_source));
+                } else if let Err(e) = fs::write(&file, new_source) {
+                    eprintln!("{}: {}", name, e);
+                }
+            }
+        }
+    }
+    if dry_run {
+        println!("{} file(s) would be changed", changed);
+    } else {
+        println!("{} file(s) changed", changed);
+    }
+}
+
+fn events_command(matches: &ArgMatches) {
+    let dir = Path::new(matches.get_one::<String>("dir").unwrap());
+    let mut group = TmplGroup::new();
+    let mut failures = vec![];
+    load_wxml_files(&mut group, dir, &mut vec![], &mut failures);
+    for d in failures.iter() {
+        eprintln!("{}", d);
+    }
+    if matches.get_flag("json") {
+        println!("{}", group.event_bindings_json());
+        return;
+    }
+    let mut trees: Vec<&TmplTree> = group.trees().collect();
+    trees.sort_by_key(|x| x.path());
+    for tree in trees {
+        for binding in collect_event_bindings(tree) {
+            let handler = match &binding.handler {
+                TmplEventHandler::Method(x) => x.to_string(),
+                TmplEventHandler::Dynamic(x) => format!("{{{{{}}}}}", x),
+            };
+            let sub_template = match &binding.sub_template {
+                Some(x) => format!(" (in template \"{}\")", x),
+                None => String::new(),
+            };
+            println!(
+                "{}:{}:{}: <{}> {} -> {}{}",
+                tree.path(),
+                binding.start_pos.0,
+                binding.start_pos.1,
+                binding.tag_name,
+                binding.attr_name(),
+                handler,
+                sub_template
+            );
+        }
+    }
+}
+
+fn lsp_command(matches: &ArgMatches) {
+    let config = read_lint_config(matches);
+    let dir = Path::new(matches.get_one::<String>("dir").unwrap());
+    let root = match fs::canonicalize(dir) {
+        Ok(x) => x,
+        Err(e) => {
+            eprintln!("Invalid workspace root {}: {}", dir.to_str().unwrap_or(""), e);
+            exit(2);
+        }
+    };
+    let mut group = TmplGroup::new();
+    let mut failures = vec![];
+    load_wxml_files(&mut group, &root, &mut vec![], &mut failures);
+    for d in failures.iter() {
+        eprintln!("{}", d);
+    }
+    load_data_types(&mut group, &root);
+    load_component_configs(&mut group, &root);
+    let mut server = TmplLanguageServer::new(root.to_str().unwrap_or(""), group);
+    server.set_lint_config(config);
+    let mut stdin = io::stdin().lock();
+    let mut stdout = io::stdout().lock();
+    while !server.is_exited() {
+        let msg = match read_lsp_message(&mut stdin) {
+            Ok(Some(x)) => x,
+            Ok(None) => break,
+            Err(e) => {
+                warn!("Invalid message: {}", e);
+                continue;
+            }
+        };
+        for msg in server.handle_message(&msg) {
+            let content = msg.to_string();
+            let ret = write!(stdout, "Content-Length: {}\r\n\r\n{}", content.len(), content)
+                .and_then(|_| stdout.flush());
+            if ret.is_err() {
+                exit(1);
+            }
+        }
+    }
+}
+
+// read a message framed by the `Content-Length` header, or `None` at the end of the input
+fn read_lsp_message(input: &mut impl BufRead) -> Result<Option<serde_json::Value>, String> {
+    let mut len = None;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line).map_err(|e| e.to_string())? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                len = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+    let len = len.ok_or_else(|| "missing Content-Length".to_string())?;
+    let mut content = vec![0; len];
+    input.read_exact(&mut content).map_err(|e| e.to_string())?;
+    serde_json::from_slice(&content).map_err(|e| e.to_string())
+}
+
+// the number of unchanged lines around the changes in a diff hunk
+const DIFF_CONTEXT: usize = 3;
+
+// a unified diff of the lines based on the longest common subsequence
+fn unified_diff(name: &str, old: &str, new: &str) -> String {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    // the lines prefixed by ` `, `-` or `+`, with the indexes of the old and the new lines
+    let mut ops: Vec<(char, usize, usize)> = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            ops.push((' ', i, j));
+            i += 1;
+            j += 1;
+        } else if j >= new.len() || i < old.len() && lcs[i + 1][j] >= lcs[i][j + 1] {
+            ops.push(('-', i, j));
+            i += 1;
+        } else {
+            ops.push(('+', i, j));
+            j += 1;
+        }
+    }
+    // the range of a hunk, in which an empty range starts at the line before it
+    let range = |start: usize, count: usize| match count {
+        0 => format!("{},0", start),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{}", start + 1, count),
+    };
+    let mut out = format!("--- {}\n+++ {}\n", name, name);
+    let mut k = 0;
+    while let Some(first) = ops[k..].iter().position(|x| x.0 != ' ') {
+        let first = k + first;
+        let start = first.saturating_sub(DIFF_CONTEXT).max(k);
+        // the changes separated by at most twice the context are in the same hunk
+        let mut last = first;
+        for (x, op) in ops.iter().enumerate().skip(first) {
+            if op.0 != ' ' {
+                last = x;
+            } else if x - last > 2 * DIFF_CONTEXT {
+                break;
+            }
+        }
+        let end = (last + 1 + DIFF_CONTEXT).min(ops.len());
+        let hunk = &ops[start..end];
+        let old_count = hunk.iter().filter(|x| x.0 != '+').count();
+        let new_count = hunk.iter().filter(|x| x.0 != '-').count();
+        out += &format!(
+            "@@ -{} +{} @@\n",
+            range(hunk[0].1, old_count),
+            range(hunk[0].2, new_count)
+        );
+        for (op, i, j) in hunk {
+            let line = if *op == '+' { new[*j] } else { old[*i] };
+            out += &format!("{}{}\n", op, line);
+        }
+        k = end;
+    }
+    out
+}
+
+fn find_wxml_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let list = match fs::read_dir(dir) {
+        Ok(x) => x,
+        Err(_) => {
+            warn!("List dir failed: {}", dir.to_str().unwrap_or(""));
+            return;
+        }
+    };
+    for entry in list.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            find_wxml_files(&path, files);
+        } else if path.extension().and_then(|x| x.to_str()) == Some("wxml") {
+            files.push(path);
+        }
+    }
+}
+
+// the page data type of `a/b.wxml` is read from `a/b.schema.json`
+fn load_data_types(group: &mut TmplGroup, dir: &Path) {
+    let paths: Vec<String> = group.trees().map(|x| x.path().to_string()).collect();
+    for path in paths {
+        let file = dir.join(format!("{}.schema.json", path));
+        if let Ok(content) = fs::read_to_string(&file) {
+            match serde_json::from_str(&content) {
+                Ok(schema) => group.set_data_type(&path, DataType::from_json_schema(&schema)),
+                Err(e) => warn!("Parse schema failed: {}: {}", file.to_str().unwrap_or(""), e),
+            }
+        }
+    }
+}
+
+// the component config of `a/b.wxml` is read from `a/b.json`, and the global one from `app.json`
+// the property definitions of the component `a/b` are read from `a/b.properties.json`
+fn load_component_configs(group: &mut TmplGroup, dir: &Path) {
+    let mut paths: Vec<String> = group.trees().map(|x| x.path().to_string()).collect();
+    paths.push("app".to_string());
+    for path in paths {
+        let file = dir.join(format!("{}.json", path));
+        if let Ok(content) = fs::read_to_string(&file) {
+            match TmplComponentConfig::from_json(&content) {
+                Ok(config) => group.set_component_config(&path, config),
+                Err(e) => warn!("Parse config failed: {}: {}", file.to_str().unwrap_or(""), e),
+            }
+        }
+        let file = dir.join(format!("{}.properties.json", path));
+        if let Ok(content) = fs::read_to_string(&file) {
+            match TmplComponentProperties::from_json(&content) {
+                Ok(properties) => group.set_component_properties(&path, properties),
+                Err(e) => warn!(
+                    "Parse properties failed: {}: {}",
+                    file.to_str().unwrap_or(""),
+                    e
+                ),
+            }
+        }
+    }
 }
 
-fn load_wxml_files(group: &mut TmplGroup, dir: &Path, wxml_path: &mut Vec<String>) -> u64 {
+// the files which fail to parse are added to `failures` as `parse-error` diagnostics
+fn load_wxml_files(
+    group: &mut TmplGroup,
+    dir: &Path,
+    wxml_path: &mut Vec<String>,
+    failures: &mut Vec<TmplDiagnostic>,
+) -> u64 {
     trace!("Search in path: {}", dir.to_str().unwrap_or(""));
     let mut size: u64 = 0;
     match fs::read_dir(dir) {
@@ -24,7 +427,7 @@ fn load_wxml_files(group: &mut TmplGroup, dir: &Path, wxml_path: &mut Vec<String
                         let file_size = entry.metadata().unwrap().len();
                         if path.is_dir() {
                             wxml_path.push(entry.file_name().to_str().unwrap().into());
-                            size += load_wxml_files(group, &path, wxml_path);
+                            size += load_wxml_files(group, &path, wxml_path, failures);
                             wxml_path.pop();
                         } else if path
                             .extension()
@@ -47,7 +450,17 @@ fn load_wxml_files(group: &mut TmplGroup, dir: &Path, wxml_path: &mut Vec<String
                                             .unwrap()
                                             .to_string(),
                                     );
-                                    group.add_tmpl(&wxml_path.join("/"), &content).unwrap();
+                                    let p = wxml_path.join("/");
+                                    if let Err(e) = group.add_tmpl(&p, &content) {
+                                        failures.push(TmplDiagnostic {
+                                            level: TmplDiagnosticLevel::Error,
+                                            code: "parse-error".to_string(),
+                                            message: e.message,
+                                            path: p.clone(),
+                                            start_pos: e.start_pos,
+                                            end_pos: e.end_pos,
+                                        });
+                                    }
                                     wxml_path.pop();
                                     size += file_size;
                                 }
@@ -87,11 +500,31 @@ mod tests {
         )
         .unwrap();
 
+        fs::write(test_dir.join("broken.wxml"), "<view>ok</view></text>").unwrap();
+
         let mut group = TmplGroup::new();
-        load_wxml_files(&mut group, &test_dir, &mut vec![]);
+        let mut failures = vec![];
+        load_wxml_files(&mut group, &test_dir, &mut vec![], &mut failures);
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].path, "broken");
+        assert_eq!(failures[0].level, TmplDiagnosticLevel::Error);
 
         // println!("{:#?}", group);
 
         fs::remove_dir_all(test_dir).unwrap();
     }
+
+    #[test]
+    fn it_prints_unified_diff() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\n";
+        let new = "a\nB\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\nm\n";
+        assert_eq!(
+            unified_diff("index.wxml", old, new),
+            "--- index.wxml\n+++ index.wxml\n@@ -1,5 +1,5 @@\n a\n-b\n+B\n c\n d\n e\n@@ -10,3 +10,4 @@\n j\n k\n l\n+m\n"
+        );
+        assert_eq!(
+            unified_diff("a.wxml", "", "x\n"),
+            "--- a.wxml\n+++ a.wxml\n@@ -0,0 +1 @@\n+x\n"
+        );
+    }
 }
@@ -1,59 +1,80 @@
 use std::borrow::Cow;
 
 use crate::{
-    element::{TmplAttrValue, TmplElement, TmplTextNode, TmplVirtualType},
+    diagnostic::{TmplDiagnostic, TmplDiagnosticLevel, TmplPosRange},
+    dialect::TmplDialect,
+    element::{TmplAttrValue, TmplComment, TmplElement, TmplNode, TmplTextNode, TmplVirtualType},
     entities::decode,
     expr::TmplExpr,
-    parse_text_entity::parse_text_entity,
-    Rule, TextEntity,
+    parse_text_entity::{parse_text_entity, parse_text_entity_with_decode},
+    parser::parse_unbraced_value,
+    Rule, TextEntity, TmplParseOptions, TmplWhitespaceMode,
 };
 use pest::iterators::{Pair, Pairs};
 
 // 解析Rule::segment的子节点
 // 包含 tag | text_node
-pub(crate) fn parse_segment(target: &mut TmplElement, pairs: &mut Pairs<'_, Rule>) {
+// returns whether the segment ends with the end tag of `target`
+pub(crate) fn parse_segment(
+    target: &mut TmplElement,
+    pairs: &mut Pairs<'_, Rule>,
+    comments: &mut Vec<TmplComment>,
+    diagnostics: &mut Vec<TmplDiagnostic>,
+    options: &TmplParseOptions,
+) -> bool {
+    let mut closed = false;
     while let Some(pair) = pairs.peek() {
         match pair.as_rule() {
             Rule::tag => {
-                if parse_tag(target, pair, pairs) == false {
-                    return;
+                if let Some(matched) =
+                    parse_tag(target, pair, pairs, comments, diagnostics, options)
+                {
+                    closed = matched;
+                    break;
                 }
             }
             Rule::text_node => {
-                parse_text_node(target, pair);
+                parse_text_node(target, pair, options);
                 pairs.next();
             }
             _ => unreachable!(),
         }
     }
+    apply_whitespace(target, options.whitespace);
+    closed
 }
 
-fn parse_tag(target: &mut TmplElement, pair: Pair<'_, Rule>, pairs: &mut Pairs<'_, Rule>) -> bool {
+// returns `Some` for an end tag, which ends the segment, with whether it matches `target`
+fn parse_tag(
+    target: &mut TmplElement,
+    pair: Pair<'_, Rule>,
+    pairs: &mut Pairs<'_, Rule>,
+    comments: &mut Vec<TmplComment>,
+    diagnostics: &mut Vec<TmplDiagnostic>,
+    options: &TmplParseOptions,
+) -> Option<bool> {
+    let span = pair.as_span();
+    let (start_pos, end_pos) = (span.start_pos().line_col(), span.end_pos().line_col());
     let mut tag_pairs = pair.into_inner();
     if let Some(pair) = tag_pairs.next() {
-        let read_attr = |mut pairs: Pairs<Rule>| {
-            let name = pairs.next().unwrap();
-            let value = match pairs.next() {
-                None => TmplAttrValue::Dynamic {
-                    expr: Box::new(TmplExpr::LitBool(true)),
-                    binding_map_keys: None,
-                },
-                Some(x) => {
-                    let value = x.into_inner().next().unwrap();
-                    match parse_text_entity(value) {
-                        TextEntity::Static(s) => TmplAttrValue::Static(s),
-                        TextEntity::Dynamic(expr) => TmplAttrValue::Dynamic {
-                            expr,
-                            binding_map_keys: None,
-                        },
-                    }
-                }
-            };
-            (name.as_str().to_string(), value)
-        };
         match pair.as_rule() {
+            Rule::comment => {
+                let content = pair.into_inner().next().unwrap().as_str();
+                let comment = TmplComment {
+                    content: content.to_string(),
+                    start_pos,
+                    end_pos,
+                };
+                if options.preserve_comments {
+                    target.children.push(TmplNode::Comment(comment.clone()));
+                }
+                comments.push(comment);
+                pairs.next();
+            }
             Rule::wxs_script_tag_begin => {
                 let mut elem = TmplElement::new("wxs", TmplVirtualType::Pure);
+                elem.start_pos = start_pos;
+                elem.end_pos = end_pos;
                 let pair = tag_pairs.next().unwrap();
                 match pair.as_rule() {
                     Rule::wxs_script_tag => {
@@ -61,9 +82,9 @@ fn parse_tag(target: &mut TmplElement, pair: Pair<'_, Rule>, pairs: &mut Pairs<'
                         while let Some(pair) = wxs_pairs.next() {
                             match pair.as_rule() {
                                 Rule::attr => {
-                                    let pairs = pair.into_inner();
-                                    let (name, value) = read_attr(pairs);
-                                    elem.add_attr(name.as_str(), value);
+                                    for (name, value) in read_attr(pair, options) {
+                                        elem.add_attr(name.as_str(), value);
+                                    }
                                 }
                                 Rule::wxs_script_body => {
                                     let text: String = pair
@@ -96,10 +117,12 @@ fn parse_tag(target: &mut TmplElement, pair: Pair<'_, Rule>, pairs: &mut Pairs<'
                         TmplVirtualType::None
                     };
                     let mut elem = TmplElement::new(tag_name, virtual_type);
+                    elem.start_pos = start_pos;
+                    elem.end_pos = end_pos;
                     while let Some(pair) = pairs.next() {
-                        let pairs = pair.into_inner();
-                        let (name, value) = read_attr(pairs);
-                        elem.add_attr(name.as_str(), value);
+                        for (name, value) in read_attr(pair, options) {
+                            elem.add_attr(name.as_str(), value);
+                        }
                     }
                     elem
                 };
@@ -111,7 +134,17 @@ fn parse_tag(target: &mut TmplElement, pair: Pair<'_, Rule>, pairs: &mut Pairs<'
                     pairs.next();
                 } else {
                     pairs.next();
-                    parse_segment(&mut elem, pairs);
+                    // the element is closed by the end tag of an ancestor or the end of the file
+                    if !parse_segment(&mut elem, pairs, comments, diagnostics, options) {
+                        diagnostics.push(TmplDiagnostic::new(
+                            TmplDiagnosticLevel::Warning,
+                            "unclosed-tag",
+                            format!("`<{}>` is not closed", elem.tag_name),
+                            "",
+                            start_pos,
+                            end_pos,
+                        ));
+                    }
                 }
                 target.append_element(elem);
             }
@@ -124,23 +157,269 @@ fn parse_tag(target: &mut TmplElement, pair: Pair<'_, Rule>, pairs: &mut Pairs<'
                 if tag_name_matched {
                     pairs.next();
                 }
-                return false;
+                return Some(tag_name_matched);
             }
             _ => unreachable!(),
         }
     } else {
         pairs.next();
     }
-    return true;
+    None
+}
+
+// read a `Rule::attr` as WXML attributes
+// an attribute of other dialects may be normalized into several ones, e.g. `s-for="item, index in list"`
+fn read_attr(pair: Pair<'_, Rule>, options: &TmplParseOptions) -> Vec<(String, TmplAttrValue)> {
+    let mut pairs = pair.into_inner();
+    let name = options
+        .dialect
+        .normalize_attr_name(pairs.next().unwrap().as_str())
+        .into_owned();
+    let (raw, raw_span, value) = match pairs.next() {
+        None => (
+            "",
+            ((0, 0), (0, 0)),
+            TmplAttrValue::new_dynamic(Box::new(TmplExpr::LitBool(true)), ((0, 0), (0, 0))),
+        ),
+        Some(x) => {
+            let value = x.into_inner().next().unwrap();
+            let raw = value.as_str();
+            let span = value.as_span();
+            let raw_span = (span.start_pos().line_col(), span.end_pos().line_col());
+            let span = expr_span(&value);
+            let value = match parse_text_entity(value) {
+                TextEntity::Static(s) => TmplAttrValue::Static(s),
+                TextEntity::Dynamic(expr) => TmplAttrValue::new_dynamic(expr, span),
+            };
+            (raw, raw_span, value)
+        }
+    };
+    if options.dialect != TmplDialect::Swan || !matches!(value, TmplAttrValue::Static(_)) {
+        return vec![(name, value)];
+    }
+    let dynamic = |s: &str| {
+        parse_unbraced_value(s.trim()).map(|expr| TmplAttrValue::new_dynamic(expr, raw_span))
+    };
+    match name.as_str() {
+        "wx:if" | "wx:elif" if !raw.trim().is_empty() => {
+            let value = dynamic(raw).unwrap_or(value);
+            vec![(name, value)]
+        }
+        "wx:for" => {
+            let (raw, track_by) = match raw.split_once(" trackBy ") {
+                Some((x, y)) => (x, Some(y.trim())),
+                None => (raw, None),
+            };
+            let (names, list) = match raw.split_once(" in ") {
+                Some((x, y)) => (Some(x), y),
+                None => (None, raw),
+            };
+            let mut ret = vec![(name, dynamic(list).unwrap_or(value))];
+            let mut item_name = "item";
+            if let Some(names) = names {
+                let (item, index) = match names.split_once(',') {
+                    Some((x, y)) => (x.trim(), Some(y.trim())),
+                    None => (names.trim(), None),
+                };
+                item_name = item;
+                ret.push((
+                    "wx:for-item".to_string(),
+                    TmplAttrValue::Static(item.to_string()),
+                ));
+                if let Some(index) = index {
+                    ret.push((
+                        "wx:for-index".to_string(),
+                        TmplAttrValue::Static(index.to_string()),
+                    ));
+                }
+            }
+            if let Some(track_by) = track_by {
+                let key = if track_by == item_name {
+                    "*this"
+                } else {
+                    track_by
+                        .strip_prefix(item_name)
+                        .and_then(|x| x.strip_prefix('.'))
+                        .unwrap_or(track_by)
+                };
+                ret.push(("wx:key".to_string(), TmplAttrValue::Static(key.to_string())));
+            }
+            ret
+        }
+        // two-way bindings like `value="{= value =}"`
+        _ if !name.contains(':')
+            && raw.starts_with("{=")
+            && raw.ends_with("=}")
+            && raw.len() >= 4 =>
+        {
+            match dynamic(&raw[2..raw.len() - 2]) {
+                Some(value) => vec![(format!("model:{}", name), value)],
+                None => vec![(name, value)],
+            }
+        }
+        _ => vec![(name, value)],
+    }
+}
+
+// the span from the first `{{` to the last `}}` of the text entities in the pair
+fn expr_span(pair: &Pair<'_, Rule>) -> TmplPosRange {
+    let mut exprs = pair.clone().into_inner().filter(|x| {
+        x.clone()
+            .into_inner()
+            .next()
+            .is_some_and(|x| x.as_rule() == Rule::expr_or_obj)
+    });
+    let first = match exprs.next() {
+        Some(x) => x,
+        None => return ((0, 0), (0, 0)),
+    };
+    let end = exprs
+        .last()
+        .unwrap_or_else(|| first.clone())
+        .as_span()
+        .end_pos();
+    (first.as_span().start_pos().line_col(), end.line_col())
+}
+
+// whether the element is a `<text>` which keeps its whitespace
+fn is_whitespace_kept(elem: &TmplElement, mode: TmplWhitespaceMode) -> bool {
+    mode == TmplWhitespaceMode::TextAware
+        && elem.tag_name == "text"
+        && matches!(elem.virtual_type, TmplVirtualType::None)
+}
+
+// whether the text of the element keeps the entities undecoded,
+// which is a `<text>` whose `decode` is absent or a falsy literal like `decode="false"`
+pub(crate) fn keeps_raw_entities(elem: &TmplElement, mode: TmplWhitespaceMode) -> bool {
+    if !is_whitespace_kept(elem, mode) {
+        return false;
+    }
+    let decode = elem.attrs.iter().find(|x| x.is_property("decode"));
+    match decode.map(|x| &x.value) {
+        None => true,
+        Some(TmplAttrValue::Static(s)) => s.is_empty() || s == "false",
+        Some(TmplAttrValue::Dynamic { expr, .. }) => match &**expr {
+            TmplExpr::LitBool(x) => !x,
+            TmplExpr::LitStr(x) => x.is_empty(),
+            TmplExpr::LitInt(x) => *x == 0,
+            TmplExpr::LitNull | TmplExpr::LitUndefined => true,
+            _ => false,
+        },
+    }
 }
 
-fn parse_text_node(target: &mut TmplElement, pair: Pair<'_, Rule>) {
-    match parse_text_entity(pair) {
+fn parse_text_node(target: &mut TmplElement, pair: Pair<'_, Rule>, options: &TmplParseOptions) {
+    let decode = !keeps_raw_entities(target, options.whitespace);
+    let span = expr_span(&pair);
+    match parse_text_entity_with_decode(pair, decode) {
         TextEntity::Static(s) => {
-            if s.trim() != "" {
+            if options.whitespace != TmplWhitespaceMode::RemoveBlank || s.trim() != "" {
                 target.append_text_node(TmplTextNode::new_static(s))
             }
         }
-        TextEntity::Dynamic(expr) => target.append_text_node(TmplTextNode::new_dynamic(expr)),
+        TextEntity::Dynamic(expr) => target.append_text_node(TmplTextNode::new_dynamic(expr, span)),
+    }
+}
+
+// the static text in a text node, which is the `LitStr` segments of `a{{b}}c`
+// returns `None` for each expression segment
+fn text_pieces(node: &mut TmplTextNode) -> Vec<Option<&mut String>> {
+    fn collect<'a>(expr: &'a mut TmplExpr, ret: &mut Vec<Option<&'a mut String>>) -> bool {
+        match expr {
+            TmplExpr::Plus(x, y) => collect(x, ret) && collect(y, ret),
+            TmplExpr::LitStr(s) => {
+                ret.push(Some(s));
+                true
+            }
+            TmplExpr::ToStringWithoutUndefined(_) => {
+                ret.push(None);
+                true
+            }
+            _ => false,
+        }
+    }
+    let mut ret = vec![];
+    match node {
+        TmplTextNode::Static(s) => ret.push(Some(s)),
+        TmplTextNode::Dynamic { expr, .. } => {
+            // a single expression like `{{ 'a' + b }}` is not the text
+            if !collect(expr, &mut ret) {
+                ret.clear();
+                ret.push(None);
+            }
+        }
+    }
+    ret
+}
+
+fn collapse_whitespace(s: &str) -> String {
+    let mut ret = String::new();
+    let mut in_space = false;
+    for c in s.chars() {
+        if c.is_ascii_whitespace() {
+            if !in_space {
+                ret.push(' ');
+            }
+            in_space = true;
+        } else {
+            ret.push(c);
+            in_space = false;
+        }
+    }
+    ret
+}
+
+// apply the whitespace mode to the text children, after all children of the element are parsed
+fn apply_whitespace(elem: &mut TmplElement, mode: TmplWhitespaceMode) {
+    match mode {
+        TmplWhitespaceMode::RemoveBlank | TmplWhitespaceMode::Preserve => return,
+        TmplWhitespaceMode::Collapse | TmplWhitespaceMode::TextAware => {}
+    }
+    if is_whitespace_kept(elem, mode) {
+        let space = elem
+            .attrs
+            .iter()
+            .find(|x| x.is_property("space"))
+            .and_then(|x| match x.value.as_static()? {
+                "ensp" => Some('\u{2002}'),
+                "emsp" => Some('\u{2003}'),
+                "nbsp" => Some('\u{a0}'),
+                _ => None,
+            });
+        if let Some(space) = space {
+            for child in elem.children.iter_mut() {
+                if let TmplNode::TextNode(node) = child {
+                    for s in text_pieces(node).into_iter().flatten() {
+                        *s = s.replace(' ', &space.to_string());
+                    }
+                }
+            }
+        }
+        return;
+    }
+    let is_content = |x: &TmplNode| !matches!(x, TmplNode::Comment(_));
+    let first = elem.children.iter().position(is_content);
+    let last = elem.children.iter().rposition(is_content);
+    for (i, child) in elem.children.iter_mut().enumerate() {
+        let node = match child {
+            TmplNode::TextNode(x) => x,
+            _ => continue,
+        };
+        let mut pieces = text_pieces(node);
+        let len = pieces.len();
+        for (j, piece) in pieces.iter_mut().enumerate() {
+            if let Some(s) = piece {
+                let mut text = collapse_whitespace(s);
+                if j == 0 && Some(i) == first {
+                    text = text.trim_start_matches(' ').to_string();
+                }
+                if j + 1 == len && Some(i) == last {
+                    text = text.trim_end_matches(' ').to_string();
+                }
+                **s = text;
+            }
+        }
     }
+    elem.children
+        .retain(|x| !matches!(x, TmplNode::TextNode(TmplTextNode::Static(s)) if s.is_empty()));
 }
@@ -4,6 +4,14 @@ use crate::{entities, expr::*, parse_common_op, parser::*};
 use pest::iterators::Pair;
 
 pub(crate) fn parse_text_entity(pair: Pair<'_, Rule>) -> TextEntity<String> {
+    parse_text_entity_with_decode(pair, true)
+}
+
+// parse the text, in which the entities are kept as they are unless `decode` is set
+pub(crate) fn parse_text_entity_with_decode(
+    pair: Pair<'_, Rule>,
+    decode: bool,
+) -> TextEntity<String> {
     let mut is_dynamic = false;
     let segs: Vec<TextEntity<Cow<str>>> = pair
         .into_inner()
@@ -14,7 +22,8 @@ pub(crate) fn parse_text_entity(pair: Pair<'_, Rule>) -> TextEntity<String> {
                     is_dynamic = true;
                     TextEntity::Dynamic(parse_expr_or_obj(pair))
                 }
-                Rule::entity => TextEntity::Static(entities::decode(pair.as_str())),
+                Rule::entity if decode => TextEntity::Static(entities::decode(pair.as_str())),
+                Rule::entity => TextEntity::Static(Cow::Borrowed(pair.as_str())),
                 Rule::pure_text => TextEntity::Static(Cow::Borrowed(pair.as_str())),
                 _ => unreachable!(),
             }
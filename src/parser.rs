@@ -3,14 +3,19 @@ use std::{
     fmt::{Debug, Display},
 };
 
-use pest::Parser;
+use pest::{
+    iterators::{Pair, Pairs},
+    Parser,
+};
 use pest_derive::Parser;
 
 use crate::{
     convert_tree::{convert_directives, prepare_expr_in_tree},
+    dialect::TmplDialect,
     element::TmplAttrValue,
     expr::TmplExpr,
     parse_segment::parse_segment,
+    parse_text_entity::parse_expr_or_obj,
     tree::TmplTree,
 };
 
@@ -56,8 +61,46 @@ impl Display for TmplParseError {
 
 impl Error for TmplParseError {}
 
-pub fn parse_tmpl(tmpl_str: &str) -> Result<TmplTree, TmplParseError> {
-    let mut pairs = TmplParser::parse(Rule::main, tmpl_str).map_err(|e| {
+/// The options of the template parser.
+#[derive(Debug, Clone, Default)]
+pub struct TmplParseOptions {
+    /// Keep the `<!-- -->` comments as `TmplNode::Comment` in the tree.
+    ///
+    /// The comments are ignored by the code generator but emitted by the printer.
+    pub preserve_comments: bool,
+    /// The dialect of the template source.
+    ///
+    /// The directives and events of other dialects, such as `a:if` and `onTap`,
+    /// are normalized into the WXML ones.
+    pub dialect: TmplDialect,
+    /// How the whitespace in the text nodes is handled.
+    pub whitespace: TmplWhitespaceMode,
+}
+
+/// The whitespace handling of the text nodes.
+///
+/// The whitespace is handled while parsing, so the code generator and the printer output the same text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TmplWhitespaceMode {
+    /// Drop the static text which is all whitespace, and keep the other text verbatim.
+    #[default]
+    RemoveBlank,
+    /// Keep all the text, including the whitespace between tags.
+    Preserve,
+    /// Collapse each run of whitespace into a single space and trim the text at the edges of an element,
+    /// like the HTML rendering.
+    Collapse,
+    /// Like `Collapse`, but keep the whitespace and the undecoded entities inside `<text>`.
+    ///
+    /// The entities are decoded only with a truthy `decode` attribute of `<text>`,
+    /// and the spaces are converted to the character of its static `space` attribute (`ensp`, `emsp` or `nbsp`).
+    /// The printer writes the undecoded text back as it is.
+    TextAware,
+}
+
+// parse the pest pairs of `Rule::main`
+pub(crate) fn parse_pairs(tmpl_str: &str) -> Result<Pairs<'_, Rule>, TmplParseError> {
+    TmplParser::parse(Rule::main, tmpl_str).map_err(|e| {
         let (start_pos, end_pos) = match e.line_col {
             pest::error::LineColLocation::Pos(p) => (p, p),
             pest::error::LineColLocation::Span(start, end) => (start, end),
@@ -76,16 +119,56 @@ pub fn parse_tmpl(tmpl_str: &str) -> Result<TmplTree, TmplParseError> {
             start_pos,
             end_pos,
         }
-    })?;
+    })
+}
+
+// parse the `{{}}` at the start of the text as a `Rule::text_entity`
+pub(crate) fn parse_braced_expr_pair(s: &str) -> Option<Pair<'_, Rule>> {
+    TmplParser::parse(Rule::text_entity, s).ok()?.next()
+}
+
+// parse an attribute value written without `{{}}`, such as the directive values of Swan
+pub(crate) fn parse_unbraced_value(s: &str) -> Option<Box<TmplExpr>> {
+    let pair = TmplParser::parse(Rule::expr_or_obj, s).ok()?.next()?;
+    if pair.as_str().len() != s.len() {
+        return None;
+    }
+    Some(parse_expr_or_obj(pair))
+}
+
+pub fn parse_tmpl(tmpl_str: &str) -> Result<TmplTree, TmplParseError> {
+    parse_tmpl_with_options(tmpl_str, &TmplParseOptions::default())
+}
+
+pub fn parse_tmpl_with_options(
+    tmpl_str: &str,
+    options: &TmplParseOptions,
+) -> Result<TmplTree, TmplParseError> {
+    parse_tmpl_with_transform(tmpl_str, options, |_| {})
+}
+
+// the `transform` is called after the directives are converted,
+// and before the scope names in expressions are replaced by scope indexes
+pub(crate) fn parse_tmpl_with_transform(
+    tmpl_str: &str,
+    options: &TmplParseOptions,
+    transform: impl FnOnce(&mut TmplTree),
+) -> Result<TmplTree, TmplParseError> {
+    let mut pairs = parse_pairs(tmpl_str)?;
 
     let mut tree = TmplTree::new();
+    tree.whitespace = options.whitespace;
     // 获取Rule::main下的Rule::segment
     let main_pair = pairs.next().unwrap();
     // 获取Rule::segment下的子节点
     let mut segment = main_pair.into_inner().next().unwrap().into_inner();
-    parse_segment(tree.root_mut(), &mut segment);
-    convert_directives(&mut tree);
-    prepare_expr_in_tree(&mut tree);
+    parse_segment(
+        &mut tree.root,
+        &mut segment,
+        &mut tree.comments,
+        &mut tree.diagnostics,
+        options,
+    );
     if let Some(pair) = segment.peek() {
         let span = pair.as_span();
         return Err(TmplParseError {
@@ -94,5 +177,8 @@ pub fn parse_tmpl(tmpl_str: &str) -> Result<TmplTree, TmplParseError> {
             end_pos: span.end_pos().line_col(),
         });
     }
+    convert_directives(&mut tree);
+    transform(&mut tree);
+    prepare_expr_in_tree(&mut tree);
     Ok(tree)
 }
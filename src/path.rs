@@ -46,3 +46,21 @@ pub(crate) fn resolve(base: &str, rel: &str) -> String {
     }
     slices.join("/")
 }
+
+// the relative path from the directory of `base` to `target`, like `../a/b` or `./b`
+pub(crate) fn relative(base: &str, target: &str) -> String {
+    let base: Vec<&str> = base.split('/').collect();
+    let base = &base[..base.len() - 1];
+    let target: Vec<&str> = target.split('/').collect();
+    let common = base
+        .iter()
+        .zip(target.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let mut slices = vec![".."; base.len() - common];
+    if slices.is_empty() {
+        slices.push(".");
+    }
+    slices.extend(&target[common..]);
+    slices.join("/")
+}
@@ -0,0 +1,526 @@
+//! Renaming the sub templates, the wxs modules and the `wx:for` scope names across a group.
+//!
+//! The edits are computed on the lossless `TmplCst` of each file.
+//! The identifiers in `{{}}` are resolved with the same scoping as the compiler,
+//! so a data field or a member with the same name is not touched.
+
+use std::{collections::BTreeMap, ops::Range};
+
+use pest::iterators::Pair;
+
+use crate::{
+    cst::{CstAttr, CstElement, CstNode, TmplCst, TmplTextEdit},
+    parser::parse_braced_expr_pair,
+    path,
+    utils::dash_to_camel,
+    Rule, TmplGroup,
+};
+
+const KEYWORDS: &[&str] = &["true", "false", "null", "undefined"];
+
+fn is_ident(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+        && !KEYWORDS.contains(&s)
+}
+
+// the names declared by a `wx:for` or by the `slot:` attributes of an element,
+// with the offset of the element or the first `slot:` attribute as the id
+#[derive(Debug, Clone)]
+struct Scope {
+    id: usize,
+    names: Vec<String>,
+}
+
+impl Scope {
+    fn for_of(elem: &CstElement, cst: &TmplCst) -> Option<Self> {
+        elem.attr("wx:for").or_else(|| elem.attr("wx:for-items"))?;
+        let value = |name: &str, default: &str| {
+            elem.attr(name)
+                .and_then(|x| x.value())
+                .map(|x| cst.text(&x.inner_range()).trim().to_string())
+                .unwrap_or_else(|| default.to_string())
+        };
+        Some(Self {
+            id: elem.start_tag().start,
+            names: vec![value("wx:for-item", "item"), value("wx:for-index", "index")],
+        })
+    }
+
+    // the slot values, e.g. `row` for both `slot:row` and `slot:item="row"`
+    fn slot_of(elem: &CstElement, cst: &TmplCst) -> Option<Self> {
+        let attrs: Vec<&CstAttr> = elem
+            .attrs()
+            .iter()
+            .filter(|x| x.name().starts_with("slot:"))
+            .collect();
+        let names = attrs
+            .iter()
+            .map(|attr| {
+                let value = attr.value().map(|x| cst.text(&x.inner_range()).trim());
+                match value {
+                    Some(x) if !x.is_empty() => x.to_string(),
+                    _ => dash_to_camel(&attr.name()["slot:".len()..].to_ascii_lowercase()),
+                }
+            })
+            .collect();
+        Some(Self {
+            id: attrs.first()?.range().start,
+            names,
+        })
+    }
+
+    fn declares(&self, name: &str) -> bool {
+        self.names.iter().any(|x| x == name)
+    }
+}
+
+// an identifier referenced in an expression, with the scopes around it
+struct IdentRef {
+    range: Range<usize>,
+    name: String,
+    // a shorthand property like `{{ a, b: 1 }}`, in which the name is also the key
+    shorthand: bool,
+    scopes: Vec<Scope>,
+}
+
+impl IdentRef {
+    // the id of the scope declaring the name, or `None` for the data fields and the wxs modules
+    fn binding(&self, name: &str) -> Option<usize> {
+        self.scopes
+            .iter()
+            .rev()
+            .find(|x| x.declares(name))
+            .map(|x| x.id)
+    }
+
+    fn in_scope(&self, id: usize) -> bool {
+        self.scopes.iter().any(|x| x.id == id)
+    }
+
+    // whether the name is declared by a scope inside the one of the id
+    fn declared_inside(&self, id: usize, name: &str) -> bool {
+        self.scopes
+            .iter()
+            .skip_while(|x| x.id != id)
+            .skip(1)
+            .any(|x| x.declares(name))
+    }
+
+    // the shorthand property keeps its key, e.g. `{{ a: b }}` for `{{ a }}`
+    fn edit(&self, new_name: &str) -> TmplTextEdit {
+        if self.shorthand {
+            edit(self.range.clone(), &format!("{}: {}", self.name, new_name))
+        } else {
+            edit(self.range.clone(), new_name)
+        }
+    }
+}
+
+// find the identifiers referenced in the `{{}}` of a raw text, with whether they are shorthand properties
+fn expr_idents(text: &str, base: usize, ret: &mut Vec<(Range<usize>, bool)>) {
+    let mut rest = 0;
+    while let Some(start) = text[rest..].find("{{") {
+        let start = rest + start;
+        match parse_braced_expr_pair(&text[start..]) {
+            Some(pair) => {
+                rest = start + pair.as_str().len();
+                idents_in_pair(pair, base + start, ret);
+            }
+            None => rest = start + 2,
+        }
+    }
+}
+
+// the object keys and the member names are not references
+fn idents_in_pair(pair: Pair<'_, Rule>, base: usize, ret: &mut Vec<(Range<usize>, bool)>) {
+    let range = |x: &Pair<'_, Rule>| base + x.as_span().start()..base + x.as_span().end();
+    match pair.as_rule() {
+        Rule::static_member => {}
+        Rule::ident => {
+            if !KEYWORDS.contains(&pair.as_str()) {
+                ret.push((range(&pair), false));
+            }
+        }
+        Rule::lit_obj_item => {
+            let pairs: Vec<Pair<'_, Rule>> = pair.into_inner().collect();
+            match pairs.as_slice() {
+                [key] if key.as_rule() == Rule::ident => {
+                    if !KEYWORDS.contains(&key.as_str()) {
+                        ret.push((range(key), true));
+                    }
+                }
+                [key, value] if key.as_rule() != Rule::spread => {
+                    idents_in_pair(value.clone(), base, ret)
+                }
+                _ => {
+                    for x in pairs {
+                        idents_in_pair(x, base, ret);
+                    }
+                }
+            }
+        }
+        _ => {
+            for x in pair.into_inner() {
+                idents_in_pair(x, base, ret);
+            }
+        }
+    }
+}
+
+fn collect_refs(
+    cst: &TmplCst,
+    nodes: &[CstNode],
+    scopes: &mut Vec<Scope>,
+    ret: &mut Vec<IdentRef>,
+) {
+    let push = |ranges: Vec<(Range<usize>, bool)>, scopes: &[Scope], ret: &mut Vec<IdentRef>| {
+        for (range, shorthand) in ranges {
+            ret.push(IdentRef {
+                name: cst.text(&range).to_string(),
+                range,
+                shorthand,
+                scopes: scopes.to_vec(),
+            });
+        }
+    };
+    for node in nodes {
+        match node {
+            CstNode::Text(range) => {
+                let mut ranges = vec![];
+                expr_idents(cst.text(range), range.start, &mut ranges);
+                push(ranges, scopes, ret);
+            }
+            CstNode::Comment(_) => {}
+            CstNode::Element(elem) => {
+                let for_scope = Scope::for_of(elem, cst);
+                let slot_scope = Scope::slot_of(elem, cst);
+                for attr in elem.attrs() {
+                    let value = match attr.value() {
+                        Some(x) => x.inner_range(),
+                        None => continue,
+                    };
+                    let mut ranges = vec![];
+                    expr_idents(cst.text(&value), value.start, &mut ranges);
+                    // the slot values are inside the `wx:for` and `wx:if` of the element
+                    let mut s = scopes.clone();
+                    match attr.name() {
+                        "wx:for" | "wx:for-items" => {}
+                        "wx:for-item" | "wx:for-index" | "wx:key" => continue,
+                        "wx:if" | "wx:elif" => s.extend(for_scope.clone()),
+                        _ => s.extend(for_scope.iter().chain(slot_scope.iter()).cloned()),
+                    }
+                    push(ranges, &s, ret);
+                }
+                // the inline wxs scripts are not templates
+                if elem.tag_name() == "wxs" {
+                    continue;
+                }
+                let len = scopes.len();
+                scopes.extend(for_scope);
+                scopes.extend(slot_scope);
+                collect_refs(cst, elem.children(), scopes, ret);
+                scopes.truncate(len);
+            }
+        }
+    }
+}
+
+fn edit(range: Range<usize>, new_name: &str) -> TmplTextEdit {
+    TmplTextEdit {
+        range,
+        new_text: new_name.to_string(),
+    }
+}
+
+// the value range of an attribute, which should not be an expression
+fn static_value(cst: &TmplCst, attr: &CstAttr) -> Result<Range<usize>, String> {
+    let range = attr
+        .value()
+        .map(|x| x.inner_range())
+        .ok_or_else(|| format!("`{}` has no value", attr.name()))?;
+    if cst.text(&range).contains("{{") {
+        return Err(format!("`{}` is not a static name", attr.name()));
+    }
+    Ok(range)
+}
+
+fn rename_template(
+    group: &TmplGroup,
+    source_of: &dyn Fn(&str) -> Option<String>,
+    def_path: &str,
+    name: &str,
+    new_name: &str,
+) -> Result<BTreeMap<String, Vec<TmplTextEdit>>, String> {
+    if new_name.is_empty() || new_name.contains(|c: char| c.is_whitespace() || "\"'{}".contains(c))
+    {
+        return Err(format!("`{}` is not a valid template name", new_name));
+    }
+    if group
+        .get_tree(def_path)
+        .is_some_and(|x| x.sub_templates.contains_key(new_name))
+    {
+        return Err(format!("template `{}` already exists", new_name));
+    }
+    // the template itself and the ones importing it, unless they define a template of the same name
+    let mut users: Vec<&str> = group
+        .trees()
+        .filter(|x| {
+            x.path != def_path
+                && !x.sub_templates.contains_key(name)
+                && x.imports
+                    .iter()
+                    .any(|import| path::resolve(&x.path, import) == def_path)
+        })
+        .map(|x| x.path.as_str())
+        .collect();
+    users.sort();
+    users.insert(0, def_path);
+    let mut ret = BTreeMap::new();
+    for user in users {
+        let source = source_of(user).ok_or_else(|| format!("cannot read template `{}`", user))?;
+        let cst = TmplCst::parse(&source).map_err(|e| format!("{}: {}", user, e))?;
+        let mut edits = vec![];
+        for elem in cst.elements() {
+            if elem.tag_name() != "template" {
+                continue;
+            }
+            let attr_name = if user == def_path && elem.attr("name").is_some() {
+                "name"
+            } else {
+                "is"
+            };
+            if let Some(value) = elem.attr(attr_name).and_then(|x| x.value()) {
+                if cst.text(&value.inner_range()) == name {
+                    edits.push(edit(value.inner_range(), new_name));
+                }
+            }
+        }
+        if !edits.is_empty() {
+            ret.insert(user.to_string(), edits);
+        }
+    }
+    Ok(ret)
+}
+
+fn rename_module(
+    cst: &TmplCst,
+    refs: &[IdentRef],
+    decl: Range<usize>,
+    name: &str,
+    new_name: &str,
+) -> Result<Vec<TmplTextEdit>, String> {
+    let modules = cst
+        .elements()
+        .into_iter()
+        .filter(|x| x.tag_name() == "wxs")
+        .filter_map(|x| x.attr("module")?.value())
+        .any(|x| cst.text(&x.inner_range()).trim() == new_name);
+    if modules {
+        return Err(format!("module `{}` already exists", new_name));
+    }
+    let mut edits = vec![edit(decl, new_name)];
+    for r in refs.iter() {
+        if r.name == new_name && r.binding(new_name).is_none() {
+            return Err(format!("`{}` is already used as a data field", new_name));
+        }
+        if r.name == name && r.binding(name).is_none() {
+            if r.binding(new_name).is_some() {
+                return Err(format!(
+                    "`{}` would be shadowed by a `wx:for` name",
+                    new_name
+                ));
+            }
+            edits.push(r.edit(new_name));
+        }
+    }
+    Ok(edits)
+}
+
+fn rename_for_scope(
+    cst: &TmplCst,
+    refs: &[IdentRef],
+    elem: &CstElement,
+    decl: Range<usize>,
+    new_name: &str,
+) -> Result<Vec<TmplTextEdit>, String> {
+    let scope = Scope::for_of(elem, cst).ok_or_else(|| "no `wx:for` on the element".to_string())?;
+    let name = cst.text(&decl).trim();
+    if scope.declares(new_name) {
+        return Err(format!(
+            "`{}` is already declared by the `wx:for`",
+            new_name
+        ));
+    }
+    let mut edits = vec![edit(decl.clone(), new_name)];
+    for r in refs.iter().filter(|x| x.in_scope(scope.id)) {
+        // a data field, a module or an outer `wx:for` name would be captured
+        if r.name == new_name && !r.declared_inside(scope.id, new_name) {
+            return Err(format!(
+                "`{}` is already used in the scope of the `wx:for`",
+                new_name
+            ));
+        }
+        if r.name == name && r.binding(name) == Some(scope.id) {
+            if r.declared_inside(scope.id, new_name) {
+                return Err(format!(
+                    "`{}` would be shadowed by an inner `wx:for` or slot value",
+                    new_name
+                ));
+            }
+            edits.push(r.edit(new_name));
+        }
+    }
+    Ok(edits)
+}
+
+/// Rename the symbol declared at the byte `offset` of the template `path`, returning the text edits keyed by template paths.
+///
+/// The offset can be on the name of a `<template name>` (or a `<template is>` referring to it),
+/// a `<wxs module>`, a `wx:for-item` or a `wx:for-index`.
+/// The source of each template is read with `source_of`, which should match the parsed trees in the group.
+/// An error is returned if the new name is invalid or would collide with an existing name in the scope.
+pub fn rename(
+    group: &TmplGroup,
+    path: &str,
+    offset: usize,
+    new_name: &str,
+    source_of: impl Fn(&str) -> Option<String>,
+) -> Result<BTreeMap<String, Vec<TmplTextEdit>>, String> {
+    let source = source_of(path).ok_or_else(|| format!("cannot read template `{}`", path))?;
+    let cst = TmplCst::parse(&source).map_err(|e| e.to_string())?;
+    let (elem, attr) = cst
+        .attr_at(offset)
+        .ok_or_else(|| "no symbol to rename at the position".to_string())?;
+    let decl = static_value(&cst, attr)?;
+    let name = cst.text(&decl).trim();
+    if name.is_empty() {
+        return Err("no symbol to rename at the position".to_string());
+    }
+    if name == new_name {
+        return Ok(BTreeMap::new());
+    }
+    let edits = match (elem.tag_name(), attr.name()) {
+        ("template", "name") => return rename_template(group, &source_of, path, name, new_name),
+        ("template", "is") => {
+            let def_path = group
+                .find_template(path, name)
+                .ok_or_else(|| format!("template `{}` is not found", name))?;
+            return rename_template(group, &source_of, &def_path, name, new_name);
+        }
+        ("wxs", "module") | (_, "wx:for-item") | (_, "wx:for-index") => {
+            if !is_ident(new_name) {
+                return Err(format!("`{}` is not a valid identifier", new_name));
+            }
+            let mut refs = vec![];
+            collect_refs(&cst, cst.nodes(), &mut vec![], &mut refs);
+            if attr.name() == "module" {
+                rename_module(&cst, &refs, decl, name, new_name)?
+            } else {
+                rename_for_scope(&cst, &refs, elem, decl, new_name)?
+            }
+        }
+        _ => return Err("no symbol to rename at the position".to_string()),
+    };
+    let mut ret = BTreeMap::new();
+    ret.insert(path.to_string(), edits);
+    Ok(ret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn apply(source: &str, edits: &[TmplTextEdit]) -> String {
+        let mut ret = source.to_string();
+        let mut edits = edits.to_vec();
+        edits.sort_by_key(|x| std::cmp::Reverse(x.range.start));
+        for e in edits {
+            ret.replace_range(e.range, &e.new_text);
+        }
+        ret
+    }
+
+    #[test]
+    fn it_renames_symbols() {
+        let mut sources = HashMap::new();
+        sources.insert(
+            "common",
+            r#"<template name="card">{{ title }}</template><template is="card" />"#,
+        );
+        sources.insert(
+            "index",
+            r#"<import src="common" /><wxs module="fmt" src="./fmt.wxs" />
+<template is="card" />
+<view wx:for="{{ list }}" wx:for-item="row" class="{{ row.fmt }}">{{ fmt.a(row) }}{{ item.row }}
+  <view wx:for="{{ row.children }}" wx:for-item="row">{{ row }}</view>
+</view>"#,
+        );
+        let mut group = TmplGroup::new();
+        for (path, source) in sources.iter() {
+            group.add_tmpl(path, source).unwrap();
+        }
+        let source_of = |path: &str| sources.get(path).map(|x| x.to_string());
+
+        let offset = sources["index"].find("\"card\"").unwrap() + 1;
+        let ret = rename(&group, "index", offset, "panel", source_of).unwrap();
+        assert_eq!(
+            apply(sources["common"], &ret["common"]),
+            r#"<template name="panel">{{ title }}</template><template is="panel" />"#
+        );
+        assert_eq!(ret["index"].len(), 1);
+
+        let offset = sources["index"].find("fmt").unwrap();
+        let ret = rename(&group, "index", offset, "format", source_of).unwrap();
+        let index = apply(sources["index"], &ret["index"]);
+        assert!(index.contains(r#"<wxs module="format""#));
+        assert!(index.contains(r#"class="{{ row.fmt }}">{{ format.a(row) }}"#));
+
+        let offset = sources["index"].find("\"row\"").unwrap() + 1;
+        let ret = rename(&group, "index", offset, "entry", source_of).unwrap();
+        let index = apply(sources["index"], &ret["index"]);
+        assert!(index.contains(
+            r#"wx:for-item="entry" class="{{ entry.fmt }}">{{ fmt.a(entry) }}{{ item.row }}"#
+        ));
+        assert!(
+            index.contains(r#"<view wx:for="{{ entry.children }}" wx:for-item="row">{{ row }}"#)
+        );
+
+        // `item` is a data field in the scope, and `fmt` is a module
+        assert!(rename(&group, "index", offset, "item", source_of).is_err());
+        assert!(rename(&group, "index", offset, "fmt", source_of).is_err());
+        assert!(rename(&group, "index", offset, "a b", source_of).is_err());
+    }
+
+    #[test]
+    fn it_resolves_object_keys_and_slot_values() {
+        let source = r#"<wxs module="fmt" src="./fmt.wxs" />
+<view wx:for="{{ list }}" wx:for-item="row">
+  <template is="card" data="{{ row: row }}" />
+  <template is="card" data="{{ row, i: 1 }}" />
+  <view data-a="{{ { row } }}" />
+  <comp slot:row slot:item="fmt">{{ row }}{{ fmt }}</comp>
+</view>"#;
+        let mut group = TmplGroup::new();
+        group.add_tmpl("index", source).unwrap();
+        let source_of = |_: &str| Some(source.to_string());
+
+        let offset = source.find("\"row\"").unwrap() + 1;
+        let ret = rename(&group, "index", offset, "entry", source_of).unwrap();
+        let index = apply(source, &ret["index"]);
+        assert!(index.contains(r#"data="{{ row: entry }}""#));
+        assert!(index.contains(r#"data="{{ row: entry, i: 1 }}""#));
+        assert!(index.contains(r#"data-a="{{ { row: entry } }}""#));
+        // `row` is shadowed by the slot value
+        assert!(index.contains(r#"<comp slot:row slot:item="fmt">{{ row }}{{ fmt }}</comp>"#));
+
+        // the `fmt` in the slot is not the module
+        let offset = source.find("fmt").unwrap();
+        let ret = rename(&group, "index", offset, "format", source_of).unwrap();
+        assert_eq!(ret["index"].len(), 1);
+    }
+}
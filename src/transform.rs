@@ -0,0 +1,116 @@
+use crate::{
+    diagnostic::{TmplDiagnostic, TmplDiagnosticLevel},
+    element::TmplElement,
+    tree::TmplTree,
+};
+
+/// A custom rewrite of the template tree, added to a group with `TmplGroup::add_transform`.
+///
+/// Transforms run in the order they are added, after the `wx:` directives are converted
+/// and before the scope names in expressions are resolved.
+/// So the expressions still contain the original `wx:for-item` and `wx:for-index` names.
+pub trait TmplTransform {
+    fn transform(&mut self, tree: &mut TmplTree, ctx: &mut TmplTransformContext);
+}
+
+impl<F: FnMut(&mut TmplTree, &mut TmplTransformContext)> TmplTransform for F {
+    fn transform(&mut self, tree: &mut TmplTree, ctx: &mut TmplTransformContext) {
+        self(tree, ctx)
+    }
+}
+
+pub struct TmplTransformContext {
+    path: String,
+    pub(crate) diagnostics: Vec<TmplDiagnostic>,
+}
+
+impl TmplTransformContext {
+    pub(crate) fn new(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            diagnostics: vec![],
+        }
+    }
+
+    /// The path of the template being transformed.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Report a diagnostic at the start tag of `elem`.
+    pub fn report(
+        &mut self,
+        level: TmplDiagnosticLevel,
+        code: &str,
+        message: &str,
+        elem: &TmplElement,
+    ) {
+        self.diagnostics.push(TmplDiagnostic::new(
+            level,
+            code,
+            message.to_string(),
+            &self.path,
+            elem.start_pos,
+            elem.end_pos,
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        element::{TmplAttrKind, TmplAttrValue, TmplNode},
+        visitor::{walk_element_mut, VisitorMut},
+        TmplGroup,
+    };
+
+    struct TrackMarker;
+
+    impl VisitorMut for TrackMarker {
+        fn visit_element_mut(&mut self, elem: &mut TmplElement) {
+            if elem.tag_name_is("button") {
+                elem.add_attr("data-track", TmplAttrValue::Static("click".to_string()));
+            }
+            walk_element_mut(self, elem);
+        }
+    }
+
+    #[test]
+    fn it_runs_transforms_in_order() {
+        let mut group = TmplGroup::new();
+        group.add_transform(|tree: &mut TmplTree, ctx: &mut TmplTransformContext| {
+            for node in tree.root.children.iter_mut() {
+                if let TmplNode::Element(elem) = node {
+                    if elem.tag_name_is("old-button") {
+                        elem.set_tag_name("button");
+                        ctx.report(
+                            TmplDiagnosticLevel::Warning,
+                            "deprecated-component",
+                            "`old-button` is replaced by `button`",
+                            elem,
+                        );
+                    }
+                }
+            }
+        });
+        group.add_transform(|tree: &mut TmplTree, _: &mut TmplTransformContext| {
+            TrackMarker.visit_tree_mut(tree)
+        });
+        group
+            .add_tmpl("pages/index", "<old-button>{{ text }}</old-button>")
+            .unwrap();
+
+        let tree = &group.trees["pages/index"];
+        let elem = match &tree.root.children[0] {
+            TmplNode::Element(elem) => elem,
+            _ => unreachable!(),
+        };
+        assert_eq!(elem.tag_name, "button");
+        assert!(matches!(&elem.attrs[0].kind, TmplAttrKind::Data { name } if name == "track"));
+        let diagnostics = group.diagnostics();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].path, "pages/index");
+        assert_eq!(diagnostics[0].start_pos, (1, 1));
+    }
+}
@@ -2,19 +2,29 @@ use std::{collections::HashMap, vec};
 
 use crate::{
     binding_map::BindingMapCollector,
-    element::{TmplElement, TmplScript, TmplVirtualType},
+    diagnostic::{TmplDiagnostic, TmplPosRange},
+    element::{TmplComment, TmplDescendants, TmplElement, TmplScript, TmplVirtualType},
+    parser::TmplWhitespaceMode,
     path,
 };
 
+/// A parsed template file.
+///
+/// The `<import>`, `<wxs>` and `<template name>` tags are collected into the tree
+/// rather than kept in the element tree.
 #[derive(Debug)]
 pub struct TmplTree {
     pub(crate) path: String,
     pub(crate) root: TmplElement,
     pub(crate) imports: Vec<String>,
+    pub(crate) import_positions: Vec<TmplPosRange>,
     pub(crate) includes: Vec<String>,
     pub(crate) sub_templates: HashMap<String, TmplElement>,
     pub(crate) scripts: Vec<TmplScript>,
+    pub(crate) comments: Vec<TmplComment>,
+    pub(crate) whitespace: TmplWhitespaceMode,
     pub(crate) binding_map_collector: BindingMapCollector,
+    pub(crate) diagnostics: Vec<TmplDiagnostic>,
 }
 
 impl TmplTree {
@@ -23,22 +33,67 @@ impl TmplTree {
             path: String::new(),
             root: TmplElement::new("", TmplVirtualType::None),
             imports: vec![],
+            import_positions: vec![],
             includes: vec![],
             sub_templates: HashMap::new(),
             scripts: vec![],
+            comments: vec![],
+            whitespace: TmplWhitespaceMode::default(),
             binding_map_collector: BindingMapCollector::new(),
+            diagnostics: vec![],
         }
     }
 
-    pub(crate) fn root(&self) -> &TmplElement {
+    /// The path of the template in the group, or an empty string if it is parsed alone.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// The virtual root element, whose children are the top level nodes.
+    pub fn root(&self) -> &TmplElement {
         &self.root
     }
 
-    pub(crate) fn root_mut(&mut self) -> &mut TmplElement {
-        &mut self.root
+    /// The `src` of `<import>` tags, with the `.wxml` extension stripped.
+    pub fn imports(&self) -> &[String] {
+        &self.imports
+    }
+
+    /// The `src` of `<include>` tags, with the `.wxml` extension stripped.
+    pub fn includes(&self) -> &[String] {
+        &self.includes
+    }
+
+    /// The `<template name>` definitions keyed by names.
+    pub fn sub_templates(&self) -> &HashMap<String, TmplElement> {
+        &self.sub_templates
+    }
+
+    pub fn sub_template(&self, name: &str) -> Option<&TmplElement> {
+        self.sub_templates.get(name)
+    }
+
+    pub fn scripts(&self) -> &[TmplScript] {
+        &self.scripts
+    }
+
+    /// All `<!-- -->` comments in source order.
+    pub fn comments(&self) -> &[TmplComment] {
+        &self.comments
+    }
+
+    /// The diagnostics reported while adding the template into a group.
+    pub fn diagnostics(&self) -> &[TmplDiagnostic] {
+        &self.diagnostics
+    }
+
+    /// Iterate over all elements under the root in depth-first pre-order, excluding sub templates.
+    pub fn elements(&self) -> TmplDescendants<'_> {
+        self.root.descendants()
     }
 
-    pub(crate) fn get_direct_dependencies(&self) -> Vec<String> {
+    /// The absolute paths of the imported and included templates.
+    pub fn get_direct_dependencies(&self) -> Vec<String> {
         let mut ret = vec![];
         for target_path in self.imports.iter() {
             ret.push(path::resolve(&self.path, &target_path));
@@ -49,7 +104,8 @@ impl TmplTree {
         ret
     }
 
-    pub(crate) fn get_script_dependencies(&self) -> Vec<String> {
+    /// The absolute paths of the referenced `.wxs` files.
+    pub fn get_script_dependencies(&self) -> Vec<String> {
         let mut ret = vec![];
         for script in self.scripts.iter() {
             match script {
@@ -63,6 +119,30 @@ impl TmplTree {
         ret
     }
 
+    /// Whether the diagnostic is disabled by a suppression comment in this template.
+    ///
+    /// `<!-- wxml-disable-next-line rule-a, rule-b -->` disables the rules on the next line,
+    /// and `<!-- wxml-disable rule-a -->` disables the rules in the whole file.
+    /// All rules are disabled if no rule is listed.
+    pub fn is_suppressed(&self, diagnostic: &TmplDiagnostic) -> bool {
+        self.comments.iter().any(|comment| {
+            let mut words = comment
+                .content
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|x| !x.is_empty());
+            let applies = match words.next() {
+                Some("wxml-disable") => true,
+                Some("wxml-disable-next-line") => diagnostic.start_pos.0 == comment.end_pos.0 + 1,
+                _ => false,
+            };
+            if !applies {
+                return false;
+            }
+            let mut rules = words.peekable();
+            rules.peek().is_none() || rules.any(|x| x == diagnostic.code)
+        })
+    }
+
     pub(crate) fn get_inline_script_module_name(&self) -> Vec<String> {
         let mut ret = vec![];
         for script in self.scripts.iter() {
@@ -82,6 +162,7 @@ impl TmplTree {
                 TmplScript::Inline {
                     module_name: m,
                     content,
+                    ..
                 } => {
                     if module_name == m {
                         return Some(&content);
@@ -113,7 +194,52 @@ impl TmplTree {
             None => self.scripts.push(TmplScript::Inline {
                 module_name: String::from(module_name),
                 content: String::from(new_content),
+                start_pos: (0, 0),
+                end_pos: (0, 0),
             }),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{parser::parse_tmpl, TmplAttrKind, TmplVirtualType};
+
+    #[test]
+    fn it_exposes_read_only_tree() {
+        let tree = parse_tmpl(
+            r#"
+                <import src="./common.wxml" />
+                <wxs module="utils" src="./utils.wxs" />
+                <view id="a"><text bind:tap="onTap">{{ title }}</text></view>
+                <image wx:for="{{ list }}" src="{{ item.url }}" />
+                <template name="card"><text>card</text></template>
+            "#,
+        )
+        .unwrap();
+        assert_eq!(tree.imports(), ["./common"]);
+        assert_eq!(tree.scripts()[0].module_name(), "utils");
+        assert!(tree.sub_template("card").is_some());
+
+        let tags: Vec<&str> = tree.elements().map(|x| x.tag_name()).collect();
+        assert_eq!(tags, vec!["view", "text", "block", "image"]);
+
+        let text = tree.elements().nth(1).unwrap();
+        assert!(matches!(
+            text.attrs()[0].kind(),
+            TmplAttrKind::Event { catch: false, .. }
+        ));
+        assert_eq!(text.attrs()[0].kind().name(), Some("tap"));
+        assert_eq!(text.attrs()[0].value().as_static(), Some("onTap"));
+        let title = text.children()[0].as_text_node().unwrap();
+        assert!(title.as_expr().is_some());
+        assert_eq!(title.expr_pos(), Some(((4, 53), (4, 64))));
+        assert_eq!(text.attrs()[0].value().expr_pos(), None);
+
+        let list = tree.elements().nth(2).unwrap();
+        assert!(matches!(list.virtual_type(), TmplVirtualType::For { .. }));
+        assert_eq!(list.source_scope_names(), ["item", "index"]);
+        assert_eq!(list.child_elements().next().unwrap().tag_name(), "image");
+        assert_eq!(list.start_pos(), (5, 17));
+    }
+}
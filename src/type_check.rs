@@ -0,0 +1,747 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::{
+    diagnostic::{TmplDiagnostic, TmplDiagnosticLevel, TmplPosRange},
+    element::{TmplAttrValue, TmplElement, TmplNode, TmplScript, TmplTextNode, TmplVirtualType},
+    expr::TmplExpr,
+    tree::TmplTree,
+};
+
+// refs in a recursive schema are expanded up to this depth
+const MAX_SCHEMA_DEPTH: usize = 32;
+
+// the methods of an array which can be called in the expressions, e.g. `list.indexOf(item)`
+const ARRAY_METHODS: &[&str] = &[
+    "concat",
+    "every",
+    "filter",
+    "find",
+    "findIndex",
+    "forEach",
+    "includes",
+    "indexOf",
+    "join",
+    "lastIndexOf",
+    "map",
+    "reduce",
+    "reduceRight",
+    "reverse",
+    "slice",
+    "some",
+    "sort",
+    "toString",
+];
+
+/// The static type of a value in the page `data`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DataType {
+    Any,
+    Null,
+    Boolean,
+    Number,
+    String,
+    Function,
+    Array(Box<DataType>),
+    Object {
+        fields: HashMap<String, DataType>,
+        /// The type of fields not listed in `fields`, or `None` if no other field exists.
+        rest: Option<Box<DataType>>,
+    },
+    Union(Vec<DataType>),
+}
+
+impl DataType {
+    /// Convert a JSON Schema into a data type.
+    ///
+    /// `type`, `properties`, `additionalProperties`, `items`, `anyOf`, `oneOf` and local `$ref`s are supported.
+    /// An object schema with `properties` is treated as closed unless `additionalProperties` is given,
+    /// so that typos in data paths can be reported.
+    pub fn from_json_schema(schema: &Value) -> Self {
+        from_json_schema_rec(schema, schema, 0)
+    }
+
+    /// Convert a TypeScript-like type description into a data type.
+    ///
+    /// An object describes its fields, e.g. `{ "user": { "name": "string" }, "tags": "string[]" }`.
+    /// A string is a type name, optionally with `[]` suffixes and `|` unions.
+    pub fn from_type_description(desc: &Value) -> Self {
+        match desc {
+            Value::String(s) => parse_type_str(s),
+            Value::Object(map) => DataType::Object {
+                fields: map
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), Self::from_type_description(v)))
+                    .collect(),
+                rest: None,
+            },
+            Value::Array(list) if list.len() == 1 => {
+                DataType::Array(Box::new(Self::from_type_description(&list[0])))
+            }
+            _ => DataType::Any,
+        }
+    }
+
+    pub(crate) fn union(list: Vec<DataType>) -> Self {
+        let mut ret: Vec<DataType> = vec![];
+        for item in list.into_iter() {
+            let items = match item {
+                DataType::Union(x) => x,
+                x => vec![x],
+            };
+            for item in items.into_iter() {
+                if let DataType::Any = item {
+                    return DataType::Any;
+                }
+                if !ret.contains(&item) {
+                    ret.push(item);
+                }
+            }
+        }
+        match ret.len() {
+            0 => DataType::Any,
+            1 => ret.pop().unwrap(),
+            _ => DataType::Union(ret),
+        }
+    }
+
+    pub(crate) fn kind_name(&self) -> Option<&'static str> {
+        match self {
+            DataType::Any => None,
+            DataType::Null => Some("null"),
+            DataType::Boolean => Some("boolean"),
+            DataType::Number => Some("number"),
+            DataType::String => Some("string"),
+            DataType::Function => Some("function"),
+            DataType::Array(_) => Some("array"),
+            DataType::Object { .. } => Some("object"),
+            DataType::Union(_) => None,
+        }
+    }
+
+    // `None` if the field never exists
+    pub(crate) fn member_type(&self, name: &str) -> Option<DataType> {
+        match self {
+            DataType::Object { fields, rest } => fields
+                .get(name)
+                .cloned()
+                .or_else(|| rest.as_ref().map(|x| (**x).clone())),
+            DataType::Array(_) | DataType::String if name == "length" => Some(DataType::Number),
+            DataType::Array(_) if ARRAY_METHODS.contains(&name) => Some(DataType::Function),
+            DataType::Array(_) => None,
+            DataType::Union(list) => {
+                let found: Vec<DataType> = list
+                    .iter()
+                    .filter(|x| **x != DataType::Null)
+                    .filter_map(|x| x.member_type(name))
+                    .collect();
+                if found.is_empty() {
+                    None
+                } else {
+                    Some(DataType::union(found))
+                }
+            }
+            _ => Some(DataType::Any),
+        }
+    }
+
+    // `None` if the value cannot be iterated by `wx:for`
+    //
+    // An object is iterated over its field values, and a string over its characters.
+    pub(crate) fn item_type(&self) -> Option<DataType> {
+        match self {
+            DataType::Any => Some(DataType::Any),
+            DataType::Array(x) => Some((**x).clone()),
+            DataType::String => Some(DataType::String),
+            DataType::Object { fields, rest } => {
+                let list = fields.values().chain(rest.as_deref()).cloned().collect();
+                Some(DataType::union(list))
+            }
+            DataType::Union(list) => {
+                let found: Vec<DataType> = list
+                    .iter()
+                    .filter(|x| **x != DataType::Null)
+                    .filter_map(|x| x.item_type())
+                    .collect();
+                if found.is_empty() {
+                    None
+                } else {
+                    Some(DataType::union(found))
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn is_callable(&self) -> bool {
+        match self {
+            DataType::Any | DataType::Function => true,
+            DataType::Union(list) => list.iter().any(|x| x.is_callable()),
+            _ => false,
+        }
+    }
+}
+
+fn from_json_schema_rec(schema: &Value, root: &Value, depth: usize) -> DataType {
+    if depth > MAX_SCHEMA_DEPTH {
+        return DataType::Any;
+    }
+    let obj = match schema {
+        Value::Object(x) => x,
+        _ => return DataType::Any,
+    };
+    if let Some(Value::String(r)) = obj.get("$ref") {
+        return match r.strip_prefix('#').and_then(|p| root.pointer(p)) {
+            Some(target) => from_json_schema_rec(target, root, depth + 1),
+            None => DataType::Any,
+        };
+    }
+    for key in ["anyOf", "oneOf"] {
+        if let Some(Value::Array(list)) = obj.get(key) {
+            return DataType::union(
+                list.iter()
+                    .map(|x| from_json_schema_rec(x, root, depth + 1))
+                    .collect(),
+            );
+        }
+    }
+    match obj.get("type") {
+        Some(Value::String(t)) => from_json_schema_type(t, obj, root, depth),
+        Some(Value::Array(list)) => DataType::union(
+            list.iter()
+                .filter_map(|t| t.as_str())
+                .map(|t| from_json_schema_type(t, obj, root, depth))
+                .collect(),
+        ),
+        _ if obj.contains_key("properties") => from_json_schema_type("object", obj, root, depth),
+        _ => DataType::Any,
+    }
+}
+
+fn from_json_schema_type(
+    t: &str,
+    obj: &serde_json::Map<String, Value>,
+    root: &Value,
+    depth: usize,
+) -> DataType {
+    match t {
+        "null" => DataType::Null,
+        "boolean" => DataType::Boolean,
+        "number" | "integer" => DataType::Number,
+        "string" => DataType::String,
+        "function" => DataType::Function,
+        "array" => DataType::Array(Box::new(match obj.get("items") {
+            Some(items) => from_json_schema_rec(items, root, depth + 1),
+            None => DataType::Any,
+        })),
+        "object" => {
+            let properties = obj.get("properties").and_then(|x| x.as_object());
+            let fields = properties
+                .map(|map| {
+                    map.iter()
+                        .map(|(k, v)| (k.to_string(), from_json_schema_rec(v, root, depth + 1)))
+                        .collect()
+                })
+                .unwrap_or_default();
+            let rest = match obj.get("additionalProperties") {
+                Some(Value::Bool(false)) => None,
+                Some(Value::Bool(true)) => Some(Box::new(DataType::Any)),
+                Some(x) => Some(Box::new(from_json_schema_rec(x, root, depth + 1))),
+                None if properties.is_some() => None,
+                None => Some(Box::new(DataType::Any)),
+            };
+            DataType::Object { fields, rest }
+        }
+        _ => DataType::Any,
+    }
+}
+
+fn parse_type_str(s: &str) -> DataType {
+    let list = s
+        .split('|')
+        .map(|s| {
+            let mut s = s.trim();
+            let mut array_depth = 0;
+            while let Some(x) = s.strip_suffix("[]") {
+                s = x.trim_end();
+                array_depth += 1;
+            }
+            let mut ty = match s {
+                "null" | "undefined" => DataType::Null,
+                "boolean" => DataType::Boolean,
+                "number" => DataType::Number,
+                "string" => DataType::String,
+                "function" | "Function" => DataType::Function,
+                "array" => DataType::Array(Box::new(DataType::Any)),
+                "object" => DataType::Object {
+                    fields: HashMap::new(),
+                    rest: Some(Box::new(DataType::Any)),
+                },
+                _ => DataType::Any,
+            };
+            for _ in 0..array_depth {
+                ty = DataType::Array(Box::new(ty));
+            }
+            ty
+        })
+        .collect();
+    DataType::union(list)
+}
+
+/// Check every expression in the template against the type of the page `data`.
+///
+/// The sub templates (`<template name>`) are not checked since their data come from the callers.
+pub fn check_data_types(tree: &TmplTree, data: &DataType) -> Vec<TmplDiagnostic> {
+    let mut checker = TypeChecker {
+        path: &tree.path,
+        data,
+        diagnostics: vec![],
+        pos: (tree.root.start_pos, tree.root.end_pos),
+    };
+    let scopes: Vec<Scope> = tree
+        .scripts
+        .iter()
+        .map(|script| {
+            let module_name = match script {
+                TmplScript::Inline { module_name, .. } => module_name,
+                TmplScript::GlobalRef { module_name, .. } => module_name,
+            };
+            Scope {
+                ty: DataType::Any,
+                path: Some(module_name.to_string()),
+            }
+        })
+        .collect();
+    checker.check_children(&tree.root, &scopes);
+    checker.diagnostics
+}
+
+#[derive(Clone)]
+struct Scope {
+    ty: DataType,
+    path: Option<String>,
+}
+
+struct TypeChecker<'a> {
+    path: &'a str,
+    data: &'a DataType,
+    diagnostics: Vec<TmplDiagnostic>,
+    pos: TmplPosRange,
+}
+
+// the data path of an expression, for messages
+fn describe(expr: &TmplExpr, scopes: &[Scope]) -> Option<String> {
+    match expr {
+        TmplExpr::Ident(x) => Some(x.to_string()),
+        TmplExpr::ScopeIndex(i) => scopes.get(*i).and_then(|x| x.path.clone()),
+        TmplExpr::StaticMember(x, name) => describe(x, scopes).map(|x| format!("{}.{}", x, name)),
+        TmplExpr::DynamicMember(x, _) => describe(x, scopes).map(|x| format!("{}[*]", x)),
+        _ => None,
+    }
+}
+
+impl<'a> TypeChecker<'a> {
+    fn report(&mut self, code: &str, message: String) {
+        self.diagnostics.push(TmplDiagnostic::new(
+            TmplDiagnosticLevel::Error,
+            code,
+            message,
+            self.path,
+            self.pos.0,
+            self.pos.1,
+        ));
+    }
+
+    fn check_attr_value(&mut self, v: &TmplAttrValue, scopes: &[Scope]) -> DataType {
+        match v {
+            TmplAttrValue::Static(_) => DataType::String,
+            TmplAttrValue::Dynamic { expr, .. } => self.infer(expr, scopes),
+        }
+    }
+
+    fn check_children(&mut self, parent: &TmplElement, scopes: &[Scope]) {
+        for node in parent.children.iter() {
+            match node {
+                TmplNode::TextNode(text_node) => match text_node {
+                    TmplTextNode::Static(_) => {}
+                    TmplTextNode::Dynamic { expr, .. } => {
+                        self.pos = (parent.start_pos, parent.end_pos);
+                        self.infer(expr, scopes);
+                    }
+                },
+                TmplNode::Comment(_) => {}
+                TmplNode::Element(elem) => {
+                    self.pos = (elem.start_pos, elem.end_pos);
+                    let mut new_scopes = None;
+                    match &elem.virtual_type {
+                        TmplVirtualType::None => {}
+                        TmplVirtualType::Pure => {}
+                        TmplVirtualType::IfGroup => {}
+                        TmplVirtualType::If { cond } => {
+                            self.check_attr_value(cond, scopes);
+                        }
+                        TmplVirtualType::Elif { cond } => {
+                            self.check_attr_value(cond, scopes);
+                        }
+                        TmplVirtualType::Else => {}
+                        TmplVirtualType::For { list, key, .. } => {
+                            let ty = self.check_attr_value(list, scopes);
+                            let list_path = match list {
+                                TmplAttrValue::Dynamic { expr, .. } => describe(expr, scopes),
+                                TmplAttrValue::Static(_) => None,
+                            };
+                            let item_ty = match ty.item_type() {
+                                Some(x) => x,
+                                None if matches!(list, TmplAttrValue::Static(_)) => DataType::Any,
+                                None => {
+                                    self.report(
+                                        "for-non-array",
+                                        format!(
+                                            "`wx:for` over {} `{}` which is not an array",
+                                            ty.kind_name().unwrap_or("value"),
+                                            list_path.as_deref().unwrap_or("expression"),
+                                        ),
+                                    );
+                                    DataType::Any
+                                }
+                            };
+                            if let Some(key) = key {
+                                if key != "*this" && item_ty.member_type(key).is_none() {
+                                    let message = match &list_path {
+                                        Some(p) => format!(
+                                            "`wx:key` field `{}` does not exist on `{}[*]`",
+                                            key, p
+                                        ),
+                                        None => {
+                                            format!("`wx:key` field `{}` does not exist", key)
+                                        }
+                                    };
+                                    self.report("for-key-unknown-field", message);
+                                }
+                            }
+                            let mut s = scopes.to_vec();
+                            s.push(Scope {
+                                ty: item_ty,
+                                path: list_path.map(|x| format!("{}[*]", x)),
+                            });
+                            s.push(Scope {
+                                ty: DataType::Number,
+                                path: None,
+                            });
+                            new_scopes = Some(s);
+                        }
+                        TmplVirtualType::TemplateRef { target, data } => {
+                            self.check_attr_value(target, scopes);
+                            self.check_attr_value(data, scopes);
+                        }
+                        TmplVirtualType::Include { .. } => {}
+                        TmplVirtualType::Slot { name, props } => {
+                            self.check_attr_value(name, scopes);
+                            if let Some(props) = props {
+                                for attr in props.iter() {
+                                    self.check_attr_value(&attr.value, scopes);
+                                }
+                            }
+                        }
+                    }
+                    if !elem.slot_values.is_empty() {
+                        let mut s = new_scopes.take().unwrap_or_else(|| scopes.to_vec());
+                        for _ in elem.slot_values.iter() {
+                            s.push(Scope {
+                                ty: DataType::Any,
+                                path: None,
+                            });
+                        }
+                        new_scopes = Some(s);
+                    }
+                    let scopes_ref = new_scopes.as_deref().unwrap_or(scopes);
+                    for attr in elem.attrs.iter() {
+                        self.check_attr_value(&attr.value, scopes_ref);
+                    }
+                    if let Some(slot) = elem.slot.as_ref() {
+                        self.check_attr_value(slot, scopes_ref);
+                    }
+                    self.check_children(elem, scopes_ref);
+                }
+            }
+        }
+    }
+
+    fn check_comparison(&mut self, op: &str, x: &DataType, y: &DataType) {
+        let (a, b) = match (x.kind_name(), y.kind_name()) {
+            (Some(a), Some(b)) => (a, b),
+            _ => return,
+        };
+        // comparing with `null` or `undefined` is a common null check
+        if a == "null" || b == "null" {
+            return;
+        }
+        let message = match op {
+            "===" | "!==" => {
+                if a == b {
+                    return;
+                }
+                let result = if op == "===" { "false" } else { "true" };
+                format!("`{}` between {} and {} is always {}", op, a, b, result)
+            }
+            _ => {
+                if a == b && (a == "number" || a == "string") {
+                    return;
+                }
+                format!("`{}` between {} and {}", op, a, b)
+            }
+        };
+        self.report("incompatible-comparison", message);
+    }
+
+    fn infer(&mut self, expr: &TmplExpr, scopes: &[Scope]) -> DataType {
+        match expr {
+            TmplExpr::ScopeIndex(i) => scopes
+                .get(*i)
+                .map(|x| x.ty.clone())
+                .unwrap_or(DataType::Any),
+            TmplExpr::Ident(x) => match self.data.member_type(x) {
+                Some(ty) => ty,
+                None => {
+                    self.report(
+                        "unknown-field",
+                        format!("data field `{}` does not exist", x),
+                    );
+                    DataType::Any
+                }
+            },
+            TmplExpr::ToStringWithoutUndefined(x) => {
+                self.infer(x, scopes);
+                DataType::String
+            }
+
+            TmplExpr::LitUndefined => DataType::Null,
+            TmplExpr::LitNull => DataType::Null,
+            TmplExpr::LitStr(_) => DataType::String,
+            TmplExpr::LitInt(_) => DataType::Number,
+            TmplExpr::LitFloat(_) => DataType::Number,
+            TmplExpr::LitBool(_) => DataType::Boolean,
+            TmplExpr::LitObj(x) => {
+                let mut fields = HashMap::new();
+                let mut rest = None;
+                for (k, v) in x.iter() {
+                    let ty = self.infer(v, scopes);
+                    match k {
+                        Some(k) => {
+                            fields.insert(k.to_string(), ty);
+                        }
+                        None => rest = Some(Box::new(DataType::Any)),
+                    }
+                }
+                DataType::Object { fields, rest }
+            }
+            TmplExpr::LitArr(x) => {
+                let list = x.iter().map(|x| self.infer(x, scopes)).collect();
+                DataType::Array(Box::new(DataType::union(list)))
+            }
+
+            TmplExpr::StaticMember(x, name) => {
+                let ty = self.infer(x, scopes);
+                match ty.member_type(name) {
+                    Some(ty) => ty,
+                    None => {
+                        let message = match describe(x, scopes) {
+                            Some(p) => format!("field `{}` does not exist on `{}`", name, p),
+                            None => format!("field `{}` does not exist", name),
+                        };
+                        self.report("unknown-field", message);
+                        DataType::Any
+                    }
+                }
+            }
+            TmplExpr::DynamicMember(x, y) => {
+                let ty = self.infer(x, scopes);
+                self.infer(y, scopes);
+                match (&ty, &**y) {
+                    (DataType::Array(item), _) => (**item).clone(),
+                    (DataType::String, _) => DataType::String,
+                    (DataType::Object { .. }, TmplExpr::LitStr(name)) => {
+                        match ty.member_type(name) {
+                            Some(ty) => ty,
+                            None => {
+                                let message = match describe(x, scopes) {
+                                    Some(p) => {
+                                        format!("field `{}` does not exist on `{}`", name, p)
+                                    }
+                                    None => format!("field `{}` does not exist", name),
+                                };
+                                self.report("unknown-field", message);
+                                DataType::Any
+                            }
+                        }
+                    }
+                    _ => DataType::Any,
+                }
+            }
+            TmplExpr::FuncCall(x, y) => {
+                let ty = self.infer(x, scopes);
+                for y in y.iter() {
+                    self.infer(y, scopes);
+                }
+                if !ty.is_callable() {
+                    let message = match describe(x, scopes) {
+                        Some(p) => format!(
+                            "`{}` is {} and cannot be called",
+                            p,
+                            ty.kind_name().unwrap_or("not a function")
+                        ),
+                        None => "the expression is not a function and cannot be called".to_string(),
+                    };
+                    self.report("not-callable", message);
+                }
+                DataType::Any
+            }
+
+            TmplExpr::Reverse(x) => {
+                self.infer(x, scopes);
+                DataType::Boolean
+            }
+            TmplExpr::BitReverse(x) | TmplExpr::Positive(x) | TmplExpr::Negative(x) => {
+                self.infer(x, scopes);
+                DataType::Number
+            }
+
+            TmplExpr::Plus(x, y) => {
+                let x = self.infer(x, scopes);
+                let y = self.infer(y, scopes);
+                match (x, y) {
+                    (DataType::String, _) | (_, DataType::String) => DataType::String,
+                    (DataType::Number, DataType::Number) => DataType::Number,
+                    _ => DataType::Any,
+                }
+            }
+            TmplExpr::Multiply(x, y)
+            | TmplExpr::Divide(x, y)
+            | TmplExpr::Mod(x, y)
+            | TmplExpr::Minus(x, y)
+            | TmplExpr::BitAnd(x, y)
+            | TmplExpr::BitXor(x, y)
+            | TmplExpr::BitOr(x, y) => {
+                self.infer(x, scopes);
+                self.infer(y, scopes);
+                DataType::Number
+            }
+
+            TmplExpr::Lt(x, y)
+            | TmplExpr::Gt(x, y)
+            | TmplExpr::Lte(x, y)
+            | TmplExpr::Gte(x, y)
+            | TmplExpr::EqFull(x, y)
+            | TmplExpr::NeFull(x, y) => {
+                let op = match expr {
+                    TmplExpr::Lt(..) => "<",
+                    TmplExpr::Gt(..) => ">",
+                    TmplExpr::Lte(..) => "<=",
+                    TmplExpr::Gte(..) => ">=",
+                    TmplExpr::EqFull(..) => "===",
+                    _ => "!==",
+                };
+                let x = self.infer(x, scopes);
+                let y = self.infer(y, scopes);
+                self.check_comparison(op, &x, &y);
+                DataType::Boolean
+            }
+            TmplExpr::Eq(x, y) | TmplExpr::Ne(x, y) => {
+                self.infer(x, scopes);
+                self.infer(y, scopes);
+                DataType::Boolean
+            }
+
+            TmplExpr::LogicAnd(x, y) | TmplExpr::LogicOr(x, y) => {
+                let x = self.infer(x, scopes);
+                let y = self.infer(y, scopes);
+                DataType::union(vec![x, y])
+            }
+
+            TmplExpr::Cond(x, y, z) => {
+                self.infer(x, scopes);
+                let y = self.infer(y, scopes);
+                let z = self.infer(z, scopes);
+                DataType::union(vec![y, z])
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::parser::parse_tmpl;
+
+    fn check(tmpl: &str, schema: Value) -> Vec<String> {
+        let tree = parse_tmpl(tmpl).unwrap();
+        let data = DataType::from_json_schema(&schema);
+        check_data_types(&tree, &data)
+            .into_iter()
+            .map(|x| format!("{}: {}", x.code, x.message))
+            .collect()
+    }
+
+    #[test]
+    fn it_checks_data_paths() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "user": {
+                    "type": "object",
+                    "properties": { "name": { "type": "string" } },
+                },
+                "list": {
+                    "type": "array",
+                    "items": { "$ref": "#/definitions/item" },
+                },
+                "count": { "type": "number" },
+            },
+            "definitions": {
+                "item": { "properties": { "id": { "type": "number" } } },
+            },
+        });
+        let ret = check(
+            r#"
+                <view>{{ user.nmae }}{{ usr }}</view>
+                <view wx:for="{{list}}">{{ item.id + item.title }}</view>
+                <view wx:for="{{count}}" />
+                <view wx:for="{{user}}" wx:key="*this">{{ item.length }}</view>
+                <view wx:for="abc">{{ item }}</view>
+                <view wx:if="{{ count === user.name }}">{{ count(1) }}</view>
+                <view>{{ list.indexOf(list[0]) }}{{ list.join(',') }}{{ list.lenght }}</view>
+            "#,
+            schema,
+        );
+        assert_eq!(
+            ret,
+            vec![
+                "unknown-field: field `nmae` does not exist on `user`",
+                "unknown-field: data field `usr` does not exist",
+                "unknown-field: field `title` does not exist on `list[*]`",
+                "for-non-array: `wx:for` over number `count` which is not an array",
+                "incompatible-comparison: `===` between number and string is always false",
+                "not-callable: `count` is number and cannot be called",
+                "unknown-field: field `lenght` does not exist on `list`",
+            ]
+        );
+    }
+
+    #[test]
+    fn it_reads_type_descriptions() {
+        let desc = json!({ "tags": "string[] | null", "user": { "age": "number" } });
+        let ret = DataType::from_type_description(&desc);
+        assert_eq!(
+            ret.member_type("tags").and_then(|x| x.item_type()),
+            Some(DataType::String)
+        );
+        assert_eq!(
+            ret.member_type("user").and_then(|x| x.member_type("age")),
+            Some(DataType::Number)
+        );
+        assert_eq!(ret.member_type("other"), None);
+    }
+}
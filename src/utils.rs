@@ -14,6 +14,19 @@ pub(crate) fn dash_to_camel(s: &str) -> String {
     camel_name
 }
 
+pub(crate) fn camel_to_dash(s: &str) -> String {
+    let mut dash_name = String::new();
+    for c in s.chars() {
+        if c.is_ascii_uppercase() {
+            dash_name.push('-');
+            dash_name.push(c.to_ascii_lowercase());
+        } else {
+            dash_name.push(c);
+        }
+    }
+    dash_name
+}
+
 // 生成parse方法
 #[macro_export]
 macro_rules! parse_common_op {
@@ -0,0 +1,426 @@
+//! Visitors over the template tree.
+//!
+//! Each `visit_*` method defaults to the corresponding `walk_*` function,
+//! which visits the children of the node in source order.
+//! Override a `visit_*` method to inspect a node, and call `walk_*` inside it to keep recursing.
+
+use crate::{
+    element::{
+        TmplAttr, TmplAttrValue, TmplComment, TmplElement, TmplNode, TmplTextNode, TmplVirtualType,
+    },
+    expr::TmplExpr,
+    tree::TmplTree,
+};
+
+pub trait Visitor {
+    fn visit_tree(&mut self, tree: &TmplTree) {
+        walk_tree(self, tree)
+    }
+
+    /// Visit a `<template name>` definition in the tree.
+    fn visit_sub_template(&mut self, _name: &str, elem: &TmplElement) {
+        self.visit_element(elem)
+    }
+
+    fn visit_element(&mut self, elem: &TmplElement) {
+        walk_element(self, elem)
+    }
+
+    fn visit_virtual_type(&mut self, virtual_type: &TmplVirtualType) {
+        walk_virtual_type(self, virtual_type)
+    }
+
+    fn visit_attr(&mut self, attr: &TmplAttr) {
+        walk_attr(self, attr)
+    }
+
+    fn visit_attr_value(&mut self, value: &TmplAttrValue) {
+        walk_attr_value(self, value)
+    }
+
+    fn visit_text_node(&mut self, text_node: &TmplTextNode) {
+        walk_text_node(self, text_node)
+    }
+
+    fn visit_comment(&mut self, _comment: &TmplComment) {}
+
+    fn visit_expr(&mut self, expr: &TmplExpr) {
+        walk_expr(self, expr)
+    }
+}
+
+pub fn walk_tree<V: Visitor + ?Sized>(v: &mut V, tree: &TmplTree) {
+    v.visit_element(&tree.root);
+    let mut names: Vec<&String> = tree.sub_templates.keys().collect();
+    names.sort();
+    for name in names {
+        v.visit_sub_template(name, &tree.sub_templates[name]);
+    }
+}
+
+pub fn walk_element<V: Visitor + ?Sized>(v: &mut V, elem: &TmplElement) {
+    v.visit_virtual_type(&elem.virtual_type);
+    for attr in elem.attrs.iter() {
+        v.visit_attr(attr);
+    }
+    if let Some(slot) = elem.slot.as_ref() {
+        v.visit_attr_value(slot);
+    }
+    for node in elem.children.iter() {
+        match node {
+            TmplNode::TextNode(text_node) => v.visit_text_node(text_node),
+            TmplNode::Element(elem) => v.visit_element(elem),
+            TmplNode::Comment(comment) => v.visit_comment(comment),
+        }
+    }
+}
+
+pub fn walk_virtual_type<V: Visitor + ?Sized>(v: &mut V, virtual_type: &TmplVirtualType) {
+    match virtual_type {
+        TmplVirtualType::None => {}
+        TmplVirtualType::Pure => {}
+        TmplVirtualType::For { list, .. } => v.visit_attr_value(list),
+        TmplVirtualType::IfGroup => {}
+        TmplVirtualType::If { cond } => v.visit_attr_value(cond),
+        TmplVirtualType::Elif { cond } => v.visit_attr_value(cond),
+        TmplVirtualType::Else => {}
+        TmplVirtualType::TemplateRef { target, data } => {
+            v.visit_attr_value(target);
+            v.visit_attr_value(data);
+        }
+        TmplVirtualType::Include { .. } => {}
+        TmplVirtualType::Slot { name, props } => {
+            v.visit_attr_value(name);
+            if let Some(props) = props {
+                for attr in props.iter() {
+                    v.visit_attr(attr);
+                }
+            }
+        }
+    }
+}
+
+pub fn walk_attr<V: Visitor + ?Sized>(v: &mut V, attr: &TmplAttr) {
+    v.visit_attr_value(&attr.value)
+}
+
+pub fn walk_attr_value<V: Visitor + ?Sized>(v: &mut V, value: &TmplAttrValue) {
+    match value {
+        TmplAttrValue::Static(_) => {}
+        TmplAttrValue::Dynamic { expr, .. } => v.visit_expr(expr),
+    }
+}
+
+pub fn walk_text_node<V: Visitor + ?Sized>(v: &mut V, text_node: &TmplTextNode) {
+    match text_node {
+        TmplTextNode::Static(_) => {}
+        TmplTextNode::Dynamic { expr, .. } => v.visit_expr(expr),
+    }
+}
+
+pub fn walk_expr<V: Visitor + ?Sized>(v: &mut V, expr: &TmplExpr) {
+    match expr {
+        TmplExpr::ScopeIndex(_) => {}
+        TmplExpr::Ident(_) => {}
+        TmplExpr::ToStringWithoutUndefined(x) => v.visit_expr(x),
+
+        TmplExpr::LitUndefined => {}
+        TmplExpr::LitNull => {}
+        TmplExpr::LitStr(_) => {}
+        TmplExpr::LitInt(_) => {}
+        TmplExpr::LitFloat(_) => {}
+        TmplExpr::LitBool(_) => {}
+        TmplExpr::LitObj(x) => {
+            for x in x.iter() {
+                v.visit_expr(&x.1);
+            }
+        }
+        TmplExpr::LitArr(x) => {
+            for x in x.iter() {
+                v.visit_expr(x);
+            }
+        }
+
+        TmplExpr::StaticMember(x, _) => v.visit_expr(x),
+        TmplExpr::DynamicMember(x, y) => {
+            v.visit_expr(x);
+            v.visit_expr(y);
+        }
+        TmplExpr::FuncCall(x, y) => {
+            v.visit_expr(x);
+            for y in y.iter() {
+                v.visit_expr(y);
+            }
+        }
+
+        TmplExpr::Reverse(x)
+        | TmplExpr::BitReverse(x)
+        | TmplExpr::Positive(x)
+        | TmplExpr::Negative(x) => v.visit_expr(x),
+
+        TmplExpr::Multiply(x, y)
+        | TmplExpr::Divide(x, y)
+        | TmplExpr::Mod(x, y)
+        | TmplExpr::Plus(x, y)
+        | TmplExpr::Minus(x, y)
+        | TmplExpr::Lt(x, y)
+        | TmplExpr::Gt(x, y)
+        | TmplExpr::Lte(x, y)
+        | TmplExpr::Gte(x, y)
+        | TmplExpr::Eq(x, y)
+        | TmplExpr::Ne(x, y)
+        | TmplExpr::EqFull(x, y)
+        | TmplExpr::NeFull(x, y)
+        | TmplExpr::BitAnd(x, y)
+        | TmplExpr::BitXor(x, y)
+        | TmplExpr::BitOr(x, y)
+        | TmplExpr::LogicAnd(x, y)
+        | TmplExpr::LogicOr(x, y) => {
+            v.visit_expr(x);
+            v.visit_expr(y);
+        }
+
+        TmplExpr::Cond(x, y, z) => {
+            v.visit_expr(x);
+            v.visit_expr(y);
+            v.visit_expr(z);
+        }
+    }
+}
+
+/// The mutable version of `Visitor`, for rewriting the tree in place.
+pub trait VisitorMut {
+    fn visit_tree_mut(&mut self, tree: &mut TmplTree) {
+        walk_tree_mut(self, tree)
+    }
+
+    /// Visit a `<template name>` definition in the tree.
+    fn visit_sub_template_mut(&mut self, _name: &str, elem: &mut TmplElement) {
+        self.visit_element_mut(elem)
+    }
+
+    fn visit_element_mut(&mut self, elem: &mut TmplElement) {
+        walk_element_mut(self, elem)
+    }
+
+    fn visit_virtual_type_mut(&mut self, virtual_type: &mut TmplVirtualType) {
+        walk_virtual_type_mut(self, virtual_type)
+    }
+
+    fn visit_attr_mut(&mut self, attr: &mut TmplAttr) {
+        walk_attr_mut(self, attr)
+    }
+
+    fn visit_attr_value_mut(&mut self, value: &mut TmplAttrValue) {
+        walk_attr_value_mut(self, value)
+    }
+
+    fn visit_text_node_mut(&mut self, text_node: &mut TmplTextNode) {
+        walk_text_node_mut(self, text_node)
+    }
+
+    fn visit_comment_mut(&mut self, _comment: &mut TmplComment) {}
+
+    fn visit_expr_mut(&mut self, expr: &mut TmplExpr) {
+        walk_expr_mut(self, expr)
+    }
+}
+
+pub fn walk_tree_mut<V: VisitorMut + ?Sized>(v: &mut V, tree: &mut TmplTree) {
+    v.visit_element_mut(&mut tree.root);
+    let mut names: Vec<String> = tree.sub_templates.keys().cloned().collect();
+    names.sort();
+    for name in names {
+        if let Some(elem) = tree.sub_templates.get_mut(&name) {
+            v.visit_sub_template_mut(&name, elem);
+        }
+    }
+}
+
+pub fn walk_element_mut<V: VisitorMut + ?Sized>(v: &mut V, elem: &mut TmplElement) {
+    v.visit_virtual_type_mut(&mut elem.virtual_type);
+    for attr in elem.attrs.iter_mut() {
+        v.visit_attr_mut(attr);
+    }
+    if let Some(slot) = elem.slot.as_mut() {
+        v.visit_attr_value_mut(slot);
+    }
+    for node in elem.children.iter_mut() {
+        match node {
+            TmplNode::TextNode(text_node) => v.visit_text_node_mut(text_node),
+            TmplNode::Element(elem) => v.visit_element_mut(elem),
+            TmplNode::Comment(comment) => v.visit_comment_mut(comment),
+        }
+    }
+}
+
+pub fn walk_virtual_type_mut<V: VisitorMut + ?Sized>(
+    v: &mut V,
+    virtual_type: &mut TmplVirtualType,
+) {
+    match virtual_type {
+        TmplVirtualType::None => {}
+        TmplVirtualType::Pure => {}
+        TmplVirtualType::For { list, .. } => v.visit_attr_value_mut(list),
+        TmplVirtualType::IfGroup => {}
+        TmplVirtualType::If { cond } => v.visit_attr_value_mut(cond),
+        TmplVirtualType::Elif { cond } => v.visit_attr_value_mut(cond),
+        TmplVirtualType::Else => {}
+        TmplVirtualType::TemplateRef { target, data } => {
+            v.visit_attr_value_mut(target);
+            v.visit_attr_value_mut(data);
+        }
+        TmplVirtualType::Include { .. } => {}
+        TmplVirtualType::Slot { name, props } => {
+            v.visit_attr_value_mut(name);
+            if let Some(props) = props {
+                for attr in props.iter_mut() {
+                    v.visit_attr_mut(attr);
+                }
+            }
+        }
+    }
+}
+
+pub fn walk_attr_mut<V: VisitorMut + ?Sized>(v: &mut V, attr: &mut TmplAttr) {
+    v.visit_attr_value_mut(&mut attr.value)
+}
+
+pub fn walk_attr_value_mut<V: VisitorMut + ?Sized>(v: &mut V, value: &mut TmplAttrValue) {
+    match value {
+        TmplAttrValue::Static(_) => {}
+        TmplAttrValue::Dynamic { expr, .. } => v.visit_expr_mut(expr),
+    }
+}
+
+pub fn walk_text_node_mut<V: VisitorMut + ?Sized>(v: &mut V, text_node: &mut TmplTextNode) {
+    match text_node {
+        TmplTextNode::Static(_) => {}
+        TmplTextNode::Dynamic { expr, .. } => v.visit_expr_mut(expr),
+    }
+}
+
+pub fn walk_expr_mut<V: VisitorMut + ?Sized>(v: &mut V, expr: &mut TmplExpr) {
+    match expr {
+        TmplExpr::ScopeIndex(_) => {}
+        TmplExpr::Ident(_) => {}
+        TmplExpr::ToStringWithoutUndefined(x) => v.visit_expr_mut(x),
+
+        TmplExpr::LitUndefined => {}
+        TmplExpr::LitNull => {}
+        TmplExpr::LitStr(_) => {}
+        TmplExpr::LitInt(_) => {}
+        TmplExpr::LitFloat(_) => {}
+        TmplExpr::LitBool(_) => {}
+        TmplExpr::LitObj(x) => {
+            for x in x.iter_mut() {
+                v.visit_expr_mut(&mut x.1);
+            }
+        }
+        TmplExpr::LitArr(x) => {
+            for x in x.iter_mut() {
+                v.visit_expr_mut(x);
+            }
+        }
+
+        TmplExpr::StaticMember(x, _) => v.visit_expr_mut(x),
+        TmplExpr::DynamicMember(x, y) => {
+            v.visit_expr_mut(x);
+            v.visit_expr_mut(y);
+        }
+        TmplExpr::FuncCall(x, y) => {
+            v.visit_expr_mut(x);
+            for y in y.iter_mut() {
+                v.visit_expr_mut(y);
+            }
+        }
+
+        TmplExpr::Reverse(x)
+        | TmplExpr::BitReverse(x)
+        | TmplExpr::Positive(x)
+        | TmplExpr::Negative(x) => v.visit_expr_mut(x),
+
+        TmplExpr::Multiply(x, y)
+        | TmplExpr::Divide(x, y)
+        | TmplExpr::Mod(x, y)
+        | TmplExpr::Plus(x, y)
+        | TmplExpr::Minus(x, y)
+        | TmplExpr::Lt(x, y)
+        | TmplExpr::Gt(x, y)
+        | TmplExpr::Lte(x, y)
+        | TmplExpr::Gte(x, y)
+        | TmplExpr::Eq(x, y)
+        | TmplExpr::Ne(x, y)
+        | TmplExpr::EqFull(x, y)
+        | TmplExpr::NeFull(x, y)
+        | TmplExpr::BitAnd(x, y)
+        | TmplExpr::BitXor(x, y)
+        | TmplExpr::BitOr(x, y)
+        | TmplExpr::LogicAnd(x, y)
+        | TmplExpr::LogicOr(x, y) => {
+            v.visit_expr_mut(x);
+            v.visit_expr_mut(y);
+        }
+
+        TmplExpr::Cond(x, y, z) => {
+            v.visit_expr_mut(x);
+            v.visit_expr_mut(y);
+            v.visit_expr_mut(z);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_tmpl;
+
+    #[test]
+    fn it_visits_and_rewrites_tree() {
+        struct TagCounter(Vec<String>);
+        impl Visitor for TagCounter {
+            fn visit_element(&mut self, elem: &TmplElement) {
+                self.0.push(elem.tag_name.clone());
+                walk_element(self, elem);
+            }
+        }
+
+        struct IdentRenamer;
+        impl VisitorMut for IdentRenamer {
+            fn visit_expr_mut(&mut self, expr: &mut TmplExpr) {
+                if let TmplExpr::Ident(x) = expr {
+                    *x = x.to_uppercase();
+                }
+                walk_expr_mut(self, expr);
+            }
+        }
+
+        let mut tree = parse_tmpl(
+            r#"<view><text>{{ a + b.c }}</text></view><template name="t"><image /></template>"#,
+        )
+        .unwrap();
+        let mut counter = TagCounter(vec![]);
+        counter.visit_tree(&tree);
+        assert_eq!(counter.0, vec!["", "view", "text", "template", "image"]);
+
+        IdentRenamer.visit_tree_mut(&mut tree);
+        let text = match &tree.root.children[0] {
+            TmplNode::Element(view) => match &view.children[0] {
+                TmplNode::Element(text) => &text.children[0],
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        };
+        match text {
+            TmplNode::TextNode(TmplTextNode::Dynamic { expr, .. }) => match &**expr {
+                TmplExpr::Plus(x, y) => {
+                    assert!(matches!(&**x, TmplExpr::Ident(x) if x == "A"));
+                    assert!(matches!(&**y, TmplExpr::StaticMember(y, _)
+                        if matches!(&**y, TmplExpr::Ident(y) if y == "B")));
+                }
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        }
+    }
+}
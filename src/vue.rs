@@ -0,0 +1,338 @@
+use crate::{
+    diagnostic::{TmplDiagnostic, TmplPosRange},
+    dialect::TmplDialect,
+    display_debug::TmplPrinter,
+    element::{
+        TmplAttr, TmplAttrKind, TmplAttrValue, TmplElement, TmplNode, TmplTextNode, TmplVirtualType,
+    },
+    escape::{escape_html_text, gen_wxml_lit_str},
+    expr::{TmplExpr, TmplExprLevel},
+    lowering::{for_key, UnsupportedReporter},
+    tree::TmplTree,
+    utils::camel_to_dash,
+};
+
+/// Print the template as the `<template>` block of a Vue 3 single-file component.
+///
+/// The constructs without an equivalent in Vue, such as wxs modules and `<template is>`
+/// with a dynamic name, are reported as `unsupported-in-vue` warnings and dropped.
+pub fn print_tmpl_as_vue(tree: &TmplTree) -> (String, Vec<TmplDiagnostic>) {
+    let mut printer = VuePrinter {
+        printer: TmplPrinter::new(tree, TmplDialect::Wechat),
+        reporter: UnsupportedReporter::new("unsupported-in-vue", "Vue templates", &tree.path),
+    };
+    printer.printer.reserved_names = Some(&[]);
+    printer.printer.js_interpolation = true;
+    let mut out = String::from("<template>");
+    printer.write_tree(tree, &mut out);
+    out += "</template>";
+    (out, printer.reporter.diagnostics)
+}
+
+// the HTML void elements, which are self-closed
+const VOID_TAGS: [&str; 14] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+struct VuePrinter<'a> {
+    printer: TmplPrinter<'a>,
+    reporter: UnsupportedReporter<'a>,
+}
+
+impl<'a> VuePrinter<'a> {
+    // an expression in a directive value
+    fn expr(&self, expr: &TmplExpr) -> String {
+        escape_html_text(&self.printer.expr_string(expr, TmplExprLevel::Cond)).into_owned()
+    }
+
+    fn value_expr(&self, value: &TmplAttrValue) -> String {
+        match value {
+            TmplAttrValue::Static(s) => escape_html_text(&gen_wxml_lit_str(s)).into_owned(),
+            TmplAttrValue::Dynamic { expr, .. } => self.expr(expr),
+        }
+    }
+
+    fn write_tree(&mut self, tree: &TmplTree, out: &mut String) {
+        self.reporter.report_imports_and_scripts(tree);
+        let mut names: Vec<&String> = tree.sub_templates.keys().collect();
+        names.sort();
+        for name in names {
+            let elem = &tree.sub_templates[name];
+            let what = format!(r#"<template name="{}">"#, name);
+            self.reporter.report(&what, (elem.start_pos, elem.end_pos));
+        }
+        self.write_children(&tree.root, out);
+    }
+
+    fn write_children(&mut self, elem: &TmplElement, out: &mut String) {
+        for child in elem.children.iter() {
+            match child {
+                TmplNode::Element(x) => self.write_element(x, "", out),
+                // an object literal is not valid in the WXML style of `{{a:1}}`
+                TmplNode::TextNode(TmplTextNode::Dynamic { expr, .. })
+                    if matches!(&**expr, TmplExpr::LitObj(_)) =>
+                {
+                    *out += &format!("{{{{ {} }}}}", self.expr(expr));
+                }
+                _ => self.printer.write_node(child, out),
+            }
+        }
+    }
+
+    // `<block>` or a `wx:if` / `wx:for` wrapper without a single wrapped element
+    fn write_template(&mut self, elem: &TmplElement, directives: &str, out: &mut String) {
+        *out += &format!("<template{}>", directives);
+        self.write_children(elem, out);
+        *out += "</template>";
+    }
+
+    fn write_element(&mut self, elem: &TmplElement, directives: &str, out: &mut String) {
+        let pos = (elem.start_pos, elem.end_pos);
+        let mut virtual_string = directives.to_string();
+        let mut tag_name = elem.tag_name.as_str();
+        match &elem.virtual_type {
+            TmplVirtualType::None | TmplVirtualType::Pure => {}
+            TmplVirtualType::IfGroup => {
+                self.write_children(elem, out);
+                return;
+            }
+            TmplVirtualType::If { .. } | TmplVirtualType::Elif { .. } | TmplVirtualType::Else => {
+                virtual_string += &match &elem.virtual_type {
+                    TmplVirtualType::If { cond } => format!(r#" v-if="{}""#, self.value_expr(cond)),
+                    TmplVirtualType::Elif { cond } => {
+                        format!(r#" v-else-if="{}""#, self.value_expr(cond))
+                    }
+                    _ => " v-else".to_string(),
+                };
+                match self.printer.single_wrapped_child(elem) {
+                    Some(child) => self.write_element(child, &virtual_string, out),
+                    None => self.write_template(elem, &virtual_string, out),
+                }
+                return;
+            }
+            TmplVirtualType::For {
+                list,
+                item_name,
+                index_name,
+                key,
+            } => {
+                let list = self.value_expr(list);
+                let scope_len = self.printer.enter_scope(elem);
+                let names = self.printer.entered_scope_names(scope_len);
+                let item_name = names.first().unwrap_or(item_name).clone();
+                let index_name = names.get(1).unwrap_or(index_name).clone();
+                virtual_string +=
+                    &format!(r#" v-for="({}, {}) in {}""#, item_name, index_name, list);
+                if let Some(key) = key {
+                    let key = for_key(Some(key), &item_name, &index_name);
+                    virtual_string += &format!(r#" :key="{}""#, escape_html_text(&key));
+                }
+                // `v-if` has a higher priority than `v-for` on the same element in Vue 3,
+                // so only a plain element can take the `v-for`
+                match self.printer.single_wrapped_child(elem) {
+                    Some(child) if matches!(child.virtual_type, TmplVirtualType::None) => {
+                        self.write_element(child, &virtual_string, out)
+                    }
+                    _ => self.write_template(elem, &virtual_string, out),
+                }
+                self.printer.leave_scope(scope_len);
+                return;
+            }
+            TmplVirtualType::TemplateRef { target, data } => {
+                let name = match target {
+                    TmplAttrValue::Static(name) => name,
+                    TmplAttrValue::Dynamic { .. } => {
+                        self.reporter
+                            .report("<template is> with a dynamic name", pos);
+                        return;
+                    }
+                };
+                tag_name = "component";
+                virtual_string += &format!(r#" is="{}""#, escape_html_text(name));
+                match data {
+                    TmplAttrValue::Dynamic { expr, .. } => match &**expr {
+                        TmplExpr::LitObj(x) if x.is_empty() => {}
+                        _ => virtual_string += &format!(r#" v-bind="{}""#, self.expr(expr)),
+                    },
+                    TmplAttrValue::Static(_) => {
+                        self.reporter.report("<template is> with static data", pos);
+                    }
+                }
+            }
+            TmplVirtualType::Include { path } => {
+                self.reporter
+                    .report(&format!(r#"<include src="{}">"#, path), pos);
+                return;
+            }
+            TmplVirtualType::Slot { name, props } => {
+                match name {
+                    TmplAttrValue::Static(x) if x.is_empty() => {}
+                    TmplAttrValue::Static(x) => {
+                        virtual_string += &format!(r#" name="{}""#, escape_html_text(x))
+                    }
+                    TmplAttrValue::Dynamic { .. } => {
+                        virtual_string += &format!(r#" :name="{}""#, self.value_expr(name))
+                    }
+                }
+                if let Some(props) = props {
+                    for prop in props.iter() {
+                        self.write_attr(prop, pos, &mut virtual_string);
+                    }
+                }
+            }
+        }
+        if tag_name == "block" {
+            tag_name = "template";
+        }
+
+        let scope_len = self.printer.enter_scope(elem);
+        let mut attr_string = String::new();
+        for attr in elem.attrs.iter() {
+            self.write_attr(attr, pos, &mut attr_string);
+        }
+        if let Some(generics) = &elem.generics {
+            let mut list: Vec<&String> = generics.keys().collect();
+            list.sort();
+            for k in list {
+                self.reporter.report(&format!("generic:{}", k), pos);
+            }
+        }
+
+        // the slot content and the slot props are passed by a `<template #name>` wrapper
+        let slot_string = match &elem.slot {
+            None if elem.slot_values.is_empty() => None,
+            None => Some("#default".to_string()),
+            Some(TmplAttrValue::Static(x)) => Some(format!("#{}", escape_html_text(x))),
+            Some(x) => Some(format!("#[{}]", self.value_expr(x))),
+        };
+        if let Some(slot_string) = &slot_string {
+            *out += &format!("<template {}", slot_string);
+            if !elem.slot_values.is_empty() {
+                let names = self.printer.entered_scope_names(scope_len);
+                let provide_names = names
+                    .len()
+                    .checked_sub(elem.slot_values.len())
+                    .map(|start| &names[start..]);
+                let props: Vec<String> = elem
+                    .slot_values
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (capture_name, provide_name))| {
+                        let provide_name = provide_names.map_or(provide_name, |x| &x[i]);
+                        format!("{}: {}", capture_name, provide_name)
+                    })
+                    .collect();
+                *out += &format!(r#"="{{ {} }}""#, props.join(", "));
+            }
+            *out += ">";
+        }
+        if elem.children.is_empty() && VOID_TAGS.contains(&tag_name) {
+            *out += &format!("<{}{}{} />", tag_name, virtual_string, attr_string);
+        } else {
+            *out += &format!("<{}{}{}>", tag_name, virtual_string, attr_string);
+            self.write_children(elem, out);
+            *out += &format!("</{}>", tag_name);
+        }
+        if slot_string.is_some() {
+            *out += "</template>";
+        }
+        self.printer.leave_scope(scope_len);
+    }
+
+    // write an attribute with a leading space
+    fn write_attr(&mut self, attr: &TmplAttr, pos: TmplPosRange, out: &mut String) {
+        if self.reporter.report_wxml_only_attr(&attr.kind, pos) {
+            return;
+        }
+        let name = match &attr.kind {
+            TmplAttrKind::Id => "id".to_string(),
+            TmplAttrKind::Class => "class".to_string(),
+            TmplAttrKind::Style => "style".to_string(),
+            TmplAttrKind::Slot => "slot".to_string(),
+            TmplAttrKind::PropertyOrExternalClass { name } => name.to_string(),
+            TmplAttrKind::Data { name } => format!("data-{}", camel_to_dash(name)),
+            TmplAttrKind::ModelProperty { name } => {
+                *out += &format!(r#" v-model:{}="{}""#, name, self.value_expr(&attr.value));
+                return;
+            }
+            TmplAttrKind::Event {
+                capture,
+                catch,
+                mut_bind,
+                name,
+            } => {
+                if *mut_bind {
+                    self.reporter.report(&format!("mut-bind:{}", name), pos);
+                }
+                let mut modifiers = String::new();
+                if *catch {
+                    modifiers += ".stop";
+                }
+                if *capture {
+                    modifiers += ".capture";
+                }
+                // the handler is a method name in WXML
+                let handler = match &attr.value {
+                    TmplAttrValue::Static(x) => escape_html_text(x).into_owned(),
+                    TmplAttrValue::Dynamic { expr, .. } => self.expr(expr),
+                };
+                *out += &format!(r#" @{}{}="{}""#, name, modifiers, handler);
+                return;
+            }
+            // the WXML-only attributes, which are reported above
+            _ => return,
+        };
+        match &attr.value {
+            TmplAttrValue::Static(x) => *out += &format!(r#" {}="{}""#, name, escape_html_text(x)),
+            TmplAttrValue::Dynamic { expr, .. } => match &**expr {
+                TmplExpr::LitBool(true) => *out += &format!(" {}", name),
+                _ => *out += &format!(r#" :{}="{}""#, name, self.expr(expr)),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_tmpl;
+
+    #[test]
+    fn it_prints_vue_templates() {
+        let tree = parse_tmpl(
+            r#"<view wx:for="{{ list }}" wx:key="id" class="a {{ item.c }}" bind:tap="onTap" catch:longpress="onLong" hidden>{{ index }}</view><block wx:if="{{ a }}"><text>1</text><text>2</text></block><input wx:else model:value="{{ v }}" /><slot name="s" data="{{ x }}" /><comp><view slot:item>{{ item.name }}</view></comp>"#,
+        )
+        .unwrap();
+        let (code, diagnostics) = print_tmpl_as_vue(&tree);
+        assert_eq!(
+            code,
+            r#"<template><view v-for="(item, index) in list" :key="item.id" :class="'a '+(item.c??'')" @tap="onTap" @longpress.stop="onLong" hidden>{{index}}</view><template v-if="a"><text>1</text><text>2</text></template><input v-else v-model:value="v" /><slot name="s" :data="x"></slot><comp><template #default="{ item: item }"><view>{{item.name}}</view></template></comp></template>"#
+        );
+        assert!(diagnostics.is_empty());
+
+        // the scope names which are not valid in JavaScript fall back to the scope indexes
+        let tree = parse_tmpl(
+            r#"<block wx:for="{{ rows }}" wx:for-item="row" wx:for-index="class" wx:key="*this"><text wx:for="{{ row }}" wx:key="a-b">{{ class }}{{ item }}</text></block>"#,
+        )
+        .unwrap();
+        let (code, _) = print_tmpl_as_vue(&tree);
+        assert_eq!(
+            code,
+            r#"<template><template v-for="(row, $1) in rows" :key="row"><text v-for="(item, index) in row" :key="item['a-b']">{{$1}}{{item}}</text></template></template>"#
+        );
+
+        let tree = parse_tmpl(
+            r#"<wxs module="m">module.exports = {}</wxs><template is="{{ name }}" /><template is="card" data="{{ ...d }}" />"#,
+        )
+        .unwrap();
+        let (code, diagnostics) = print_tmpl_as_vue(&tree);
+        assert_eq!(
+            code,
+            r#"<template><component is="card" v-bind="{...d}"></component></template>"#
+        );
+        assert_eq!(diagnostics.len(), 2);
+        assert!(diagnostics.iter().all(|x| x.code == "unsupported-in-vue"));
+        assert_eq!(diagnostics[0].start_pos, (1, 1));
+    }
+}